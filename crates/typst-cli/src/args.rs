@@ -100,6 +100,18 @@ pub struct CompileCommand {
     /// apart from file names and line numbers.
     #[arg(long = "timings", value_name = "OUTPUT_JSON")]
     pub timings: Option<Option<PathBuf>>,
+
+    /// Traces the definitions and uses of variables whose names match one of
+    /// the comma-separated patterns, in which `*` matches any characters
+    ///
+    /// The trace is printed in chronological order after compilation.
+    #[arg(long = "trace-vars", value_name = "PATTERNS", value_delimiter = ',')]
+    pub trace_vars: Vec<String>,
+
+    /// Prints which top-level definitions of the main file changed after each
+    /// recompilation in watch mode
+    #[arg(long = "diff-definitions")]
+    pub diff_definitions: bool,
 }
 
 /// Initializes a new project from a template
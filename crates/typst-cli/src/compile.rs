@@ -9,8 +9,8 @@ use ecow::{eco_format, EcoString};
 use parking_lot::RwLock;
 use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
 use typst::diag::{bail, At, Severity, SourceDiagnostic, StrResult};
-use typst::eval::Tracer;
-use typst::foundations::{Datetime, Smart};
+use typst::eval::{Tracer, VarEvent};
+use typst::foundations::{Datetime, Module, Smart};
 use typst::layout::Frame;
 use typst::model::Document;
 use typst::syntax::{FileId, Source, Span};
@@ -72,13 +72,13 @@ pub fn compile(mut timer: Timer, mut command: CompileCommand) -> StrResult<()> {
 
 /// Compile a single time.
 ///
-/// Returns whether it compiled without errors.
+/// Returns the module the main file evaluated to, if evaluation succeeded.
 #[typst_macros::time(name = "compile once")]
 pub fn compile_once(
     world: &mut SystemWorld,
     command: &mut CompileCommand,
     watching: bool,
-) -> StrResult<()> {
+) -> StrResult<Option<Module>> {
     let start = std::time::Instant::now();
     if watching {
         Status::Compiling.print(command).unwrap();
@@ -94,11 +94,16 @@ pub fn compile_once(
         print_diagnostics(world, &errors, &[], command.common.diagnostic_format)
             .map_err(|err| eco_format!("failed to print diagnostics ({err})"))?;
 
-        return Ok(());
+        return Ok(None);
     }
 
     let mut tracer = Tracer::new();
-    let result = typst::compile(world, &mut tracer);
+    if !command.trace_vars.is_empty() {
+        tracer.trace_vars(command.trace_vars.iter().map(Into::into));
+    }
+
+    let (result, module) = typst::compile_with_module(world, &mut tracer);
+    let var_events = tracer.var_events().to_vec();
     let warnings = tracer.warnings();
 
     match result {
@@ -145,7 +150,12 @@ pub fn compile_once(
         }
     }
 
-    Ok(())
+    if !command.trace_vars.is_empty() {
+        print_var_trace(world, &var_events)
+            .map_err(|err| eco_format!("failed to print variable trace ({err})"))?;
+    }
+
+    Ok(module)
 }
 
 /// Export into the target format.
@@ -500,6 +510,39 @@ pub fn print_diagnostics(
     Ok(())
 }
 
+/// Print the traced definitions and uses of variables to the terminal.
+fn print_var_trace(world: &SystemWorld, events: &[VarEvent]) -> io::Result<()> {
+    let mut out = terminal::out();
+    writeln!(out, "variable trace:")?;
+    if events.is_empty() {
+        writeln!(out, "  no matching variable was defined or used")?;
+    }
+
+    for event in events {
+        let location =
+            locate(world, event.span()).unwrap_or_else(|| "unknown location".into());
+        match event {
+            VarEvent::Define { name, value, .. } => {
+                writeln!(out, "  {location}: define `{name}` = {value}")?
+            }
+            VarEvent::Access { name, .. } => writeln!(out, "  {location}: use `{name}`")?,
+        }
+    }
+
+    out.flush()
+}
+
+/// Describe where a span starts, as a file name, line, and column.
+fn locate(world: &SystemWorld, span: Span) -> Option<String> {
+    use codespan_reporting::files::Files;
+
+    let id = span.id()?;
+    let start = world.range(span)?.start;
+    let name = Files::name(world, id).ok()?;
+    let location = Files::location(world, id, start).ok()?;
+    Some(format!("{name}:{}:{}", location.line_number, location.column_number))
+}
+
 /// Create a label for a span.
 fn label(world: &SystemWorld, span: Span) -> Option<Label<FileId>> {
     Some(Label::primary(span.id()?, world.range(span)?))
@@ -11,6 +11,7 @@ use ecow::eco_format;
 use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher as _};
 use same_file::is_same_file;
 use typst::diag::{bail, StrResult};
+use typst::foundations::{Scope, ScopeDiff};
 
 use crate::args::{CompileCommand, Input, Output};
 use crate::compile::compile_once;
@@ -46,7 +47,14 @@ pub fn watch(mut timer: Timer, mut command: CompileCommand) -> StrResult<()> {
     };
 
     // Perform initial compilation.
-    timer.record(&mut world, |world| compile_once(world, &mut command, true))??;
+    let module =
+        timer.record(&mut world, |world| compile_once(world, &mut command, true))??;
+
+    // The definitions of the main file after the last successful evaluation,
+    // if they are diffed.
+    let mut exports = module
+        .filter(|_| command.diff_definitions)
+        .map(|module| module.scope().clone());
 
     // Watch all dependencies of the initial compilation.
     watcher.update(world.dependencies())?;
@@ -60,7 +68,18 @@ pub fn watch(mut timer: Timer, mut command: CompileCommand) -> StrResult<()> {
         world.reset();
 
         // Recompile.
-        timer.record(&mut world, |world| compile_once(world, &mut command, true))??;
+        let module = timer
+            .record(&mut world, |world| compile_once(world, &mut command, true))??;
+
+        // Show which definitions of the main file changed.
+        if let Some(module) = module.filter(|_| command.diff_definitions) {
+            let scope = module.scope().clone();
+            if let Some(previous) = &exports {
+                print_scope_diff(&previous.diff(&scope))
+                    .map_err(|err| eco_format!("failed to print definitions ({err})"))?;
+            }
+            exports = Some(scope);
+        }
 
         // Evict the cache.
         comemo::evict(10);
@@ -70,6 +89,35 @@ pub fn watch(mut timer: Timer, mut command: CompileCommand) -> StrResult<()> {
     }
 }
 
+/// Print which definitions of the main file changed since the last
+/// compilation.
+///
+/// Closures can't be compared, so they would be listed as maybe changed on
+/// every compilation. Hence, nothing is printed unless something definitely
+/// changed.
+fn print_scope_diff(diff: &ScopeDiff) -> io::Result<()> {
+    if diff.added.is_empty() && diff.removed.is_empty() && diff.changed.is_empty() {
+        return Ok(());
+    }
+
+    let mut out = terminal::out();
+    writeln!(out, "definitions of the main file:")?;
+    for (label, names) in [
+        ("added", &diff.added),
+        ("removed", &diff.removed),
+        ("changed", &diff.changed),
+        ("maybe changed", &diff.maybe_changed),
+    ] {
+        if !names.is_empty() {
+            let names: Vec<_> = names.iter().map(|name| format!("`{name}`")).collect();
+            writeln!(out, "  {label}: {}", names.join(", "))?;
+        }
+    }
+    writeln!(out)?;
+
+    out.flush()
+}
+
 /// Watches file system activity.
 struct Watcher {
     /// The output file. We ignore any events for it.
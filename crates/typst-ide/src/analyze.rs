@@ -1,7 +1,7 @@
 use comemo::Track;
 use ecow::{eco_vec, EcoString, EcoVec};
 use typst::engine::{Engine, Route};
-use typst::eval::{Tracer, Vm};
+use typst::eval::{Tracer, VarEvent, Vm};
 use typst::foundations::{Context, Label, Scopes, Styles, Value};
 use typst::introspection::{Introspector, Locator};
 use typst::model::{BibliographyElem, Document};
@@ -81,6 +81,18 @@ pub fn analyze_import(world: &dyn World, source: &LinkedNode) -> Option<Value> {
         .map(Value::Module)
 }
 
+/// Trace how variables are defined and used when compiling the document.
+///
+/// Only variables whose name matches one of the patterns are traced. A `*` in
+/// a pattern matches any sequence of characters. The events are in the order
+/// in which they happened.
+pub fn analyze_vars(world: &dyn World, patterns: &[&str]) -> Vec<VarEvent> {
+    let mut tracer = Tracer::new();
+    tracer.trace_vars(patterns.iter().map(|&pattern| pattern.into()));
+    typst::compile(world, &mut tracer).ok();
+    tracer.var_events().to_vec()
+}
+
 /// Find all labels and details for them.
 ///
 /// Returns:
@@ -115,3 +127,93 @@ pub fn analyze_labels(document: &Document) -> (Vec<(Label, Option<EcoString>)>,
 
     (output, split)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use comemo::Track;
+    use typst::eval::{eval_string_in, EvalMode, VarEvent};
+    use typst::foundations::{Repr, ScopeHooks, Scopes, Value};
+    use typst::syntax::Span;
+    use typst::World;
+
+    use super::analyze_vars;
+    use crate::tests::TestWorld;
+
+    /// Describe an event without its span.
+    fn describe(event: &VarEvent) -> String {
+        match event {
+            VarEvent::Define { name, value, .. } => format!("define {name} = {value}"),
+            VarEvent::Access { name, .. } => format!("use {name}"),
+        }
+    }
+
+    #[track_caller]
+    fn test(text: &str, patterns: &[&str], expected: &[&str]) {
+        let world = TestWorld::new(text);
+        let events = analyze_vars(&world, patterns);
+        let described: Vec<_> = events.iter().map(describe).collect();
+        assert_eq!(described, expected);
+    }
+
+    #[test]
+    fn test_analyze_vars() {
+        test(
+            "#let a = 1\n#let f(x) = a + x\n#let b = f(2)\n#b",
+            &["a", "b", "x"],
+            &["define a = 1", "define x = 2", "use a", "use x", "define b = 3", "use b"],
+        );
+    }
+
+    #[test]
+    fn test_analyze_vars_patterns() {
+        test(
+            "#let abc = 1\n#let abd = abc\n#let x = abd\n#{ x += 1 }",
+            &["ab*", "x"],
+            &[
+                "define abc = 1",
+                "use abc",
+                "define abd = 1",
+                "use abd",
+                "define x = 1",
+                "use x",
+            ],
+        );
+        test("#let a = 1\n#a", &["b*"], &[]);
+    }
+
+    #[test]
+    fn test_scope_hooks() {
+        #[derive(Default)]
+        struct Recorder(RefCell<Vec<String>>);
+
+        impl ScopeHooks for Recorder {
+            fn on_define(&self, name: &str, value: &Value, _: Span) {
+                self.0.borrow_mut().push(format!("define {name} = {}", value.repr()));
+            }
+
+            fn on_access(&self, name: &str, _: Span) {
+                self.0.borrow_mut().push(format!("use {name}"));
+            }
+        }
+
+        let world = TestWorld::new("");
+        let recorder = Recorder::default();
+        let scopes = Scopes::new(Some(world.library())).with_hooks(&recorder);
+        let world: &dyn World = &world;
+        let result = eval_string_in(
+            world.track(),
+            "{ let y = 1; let z = y + 1; z }",
+            Span::detached(),
+            EvalMode::Code,
+            scopes.view(),
+        );
+
+        assert_eq!(result.ok(), Some(Value::Int(2)));
+        assert_eq!(
+            *recorder.0.borrow(),
+            ["define y = 1", "use y", "define z = 2", "use z"]
+        );
+    }
+}
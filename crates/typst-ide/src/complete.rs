@@ -1,16 +1,17 @@
 use std::cmp::Reverse;
-use std::collections::{BTreeSet, HashSet};
+use std::collections::{BTreeMap, HashSet};
 
 use ecow::{eco_format, EcoString};
 use if_chain::if_chain;
 use serde::{Deserialize, Serialize};
 use typst::foundations::{
-    fields_on, format_str, mutable_methods_on, repr, AutoValue, CastInfo, Func, Label,
-    NoneValue, Repr, Scope, StyleChain, Styles, Type, Value,
+    fields_on, format_str, mutable_methods_on, repr, AutoValue, Binding, CastInfo, Func,
+    Label, NoneValue, Repr, Scope, StyleChain, Styles, Type, Value,
 };
 use typst::model::Document;
 use typst::syntax::{
-    ast, is_id_continue, is_id_start, is_ident, LinkedNode, Side, Source, SyntaxKind,
+    ast, docs_for, is_id_continue, is_id_start, is_ident, LinkedNode, Side, Source,
+    SyntaxKind,
 };
 use typst::text::RawElem;
 use typst::visualize::Color;
@@ -89,7 +90,10 @@ pub enum CompletionKind {
 
 /// Complete in comments. Or rather, don't!
 fn complete_comments(ctx: &mut CompletionContext) -> bool {
-    matches!(ctx.leaf.kind(), SyntaxKind::LineComment | SyntaxKind::BlockComment)
+    matches!(
+        ctx.leaf.kind(),
+        SyntaxKind::LineComment | SyntaxKind::DocComment | SyntaxKind::BlockComment
+    )
 }
 
 /// Complete in markup mode.
@@ -382,7 +386,8 @@ fn field_access_completions(
 
     if let Some(scope) = value.scope() {
         for (name, value) in scope.iter() {
-            ctx.value_completion(Some(name.clone()), value, true, None);
+            let docs = scope.get_binding(name).and_then(Binding::docs);
+            ctx.value_completion(Some(name.clone()), value, true, docs);
         }
     }
 
@@ -1318,7 +1323,16 @@ impl<'a> CompletionContext<'a> {
     ///
     /// Filters the global/math scope with the given filter.
     fn scope_completions(&mut self, parens: bool, filter: impl Fn(&Value) -> bool) {
-        let mut defined = BTreeSet::new();
+        let mut defined = BTreeMap::new();
+
+        // Math-only bindings can't be used outside of math mode.
+        let in_math = matches!(
+            self.leaf.parent_kind(),
+            Some(SyntaxKind::Equation)
+                | Some(SyntaxKind::Math)
+                | Some(SyntaxKind::MathFrac)
+                | Some(SyntaxKind::MathAttach)
+        );
 
         let mut ancestor = Some(self.leaf.clone());
         while let Some(node) = &ancestor {
@@ -1326,7 +1340,9 @@ impl<'a> CompletionContext<'a> {
             while let Some(node) = &sibling {
                 if let Some(v) = node.cast::<ast::LetBinding>() {
                     for ident in v.kind().bindings() {
-                        defined.insert(ident.get().clone());
+                        defined
+                            .entry(ident.get().clone())
+                            .or_insert_with(|| docs_for(node));
                     }
                 }
 
@@ -1340,17 +1356,29 @@ impl<'a> CompletionContext<'a> {
                                 .and_then(|source| analyze_import(self.world, &source))
                             {
                                 if imports.is_none() {
-                                    defined.extend(value.name().map(Into::into));
+                                    if let Some(name) = value.name() {
+                                        defined.entry(name.into()).or_insert(None);
+                                    }
                                 } else if let Some(scope) = value.scope() {
                                     for (name, _) in scope.iter() {
-                                        defined.insert(name.clone());
+                                        let Some(binding) = scope.get_binding(name)
+                                        else {
+                                            continue;
+                                        };
+                                        if binding.is_math_only() && !in_math {
+                                            continue;
+                                        }
+                                        let docs = binding.docs().map(Into::into);
+                                        defined.entry(name.clone()).or_insert(docs);
                                     }
                                 }
                             }
                         }
                         Some(ast::Imports::Items(items)) => {
                             for item in items.iter() {
-                                defined.insert(item.bound_name().get().clone());
+                                defined
+                                    .entry(item.bound_name().get().clone())
+                                    .or_insert(None);
                             }
                         }
                     }
@@ -1364,7 +1392,7 @@ impl<'a> CompletionContext<'a> {
                     if node.prev_sibling_kind() != Some(SyntaxKind::In) {
                         let pattern = v.pattern();
                         for ident in pattern.bindings() {
-                            defined.insert(ident.get().clone());
+                            defined.entry(ident.get().clone()).or_insert(None);
                         }
                     }
                 }
@@ -1376,28 +1404,25 @@ impl<'a> CompletionContext<'a> {
             break;
         }
 
-        let in_math = matches!(
-            self.leaf.parent_kind(),
-            Some(SyntaxKind::Equation)
-                | Some(SyntaxKind::Math)
-                | Some(SyntaxKind::MathFrac)
-                | Some(SyntaxKind::MathAttach)
-        );
-
         let scope = if in_math { self.math } else { self.global };
         for (name, value) in scope.iter() {
-            if filter(value) && !defined.contains(name) {
-                self.value_completion(Some(name.clone()), value, parens, None);
+            let binding = scope.get_binding(name);
+            if binding.is_some_and(|binding| binding.is_math_only() && !in_math) {
+                continue;
+            }
+            if filter(value) && !defined.contains_key(name) {
+                let docs = binding.and_then(Binding::docs);
+                self.value_completion(Some(name.clone()), value, parens, docs);
             }
         }
 
-        for name in defined {
+        for (name, docs) in defined {
             if !name.is_empty() {
                 self.completions.push(Completion {
                     kind: CompletionKind::Constant,
                     label: name,
                     apply: None,
-                    detail: None,
+                    detail: docs,
                 });
             }
         }
@@ -1406,7 +1431,10 @@ impl<'a> CompletionContext<'a> {
 
 #[cfg(test)]
 mod tests {
+    use comemo::Track;
+    use typst::engine::Route;
     use typst::eval::Tracer;
+    use typst::World;
 
     use super::autocomplete;
     use crate::tests::TestWorld;
@@ -1428,9 +1456,45 @@ mod tests {
         }
     }
 
+    #[track_caller]
+    fn test_detail(text: &str, label: &str, detail: Option<&str>) {
+        let world = TestWorld::new(text);
+        let (_, completions) =
+            autocomplete(&world, None, &world.main, text.len(), true).unwrap_or_default();
+
+        let completion = completions
+            .iter()
+            .find(|c| c.label == label)
+            .unwrap_or_else(|| panic!("{label:?} was not contained"));
+        assert_eq!(completion.detail.as_deref(), detail);
+    }
+
     #[test]
     fn test_autocomplete() {
         test("#i", 2, &["int", "if conditional"], &["foo"]);
         test("#().", 4, &["insert", "remove", "len", "all"], &["foo"]);
     }
+
+    #[test]
+    fn test_autocomplete_docs() {
+        test_detail("/// The answer.\n#let x = 42\n#x", "x", Some("The answer."));
+        test_detail("#let x = 42\n#x", "x", None);
+        test_detail("/// Detached.\n\n#let x = 42\n#x", "x", None);
+    }
+
+    #[test]
+    fn test_module_export_keeps_docs() {
+        let world = TestWorld::new("/// The answer.\n#let answer = 42\n#let other = 1");
+        let module = typst::eval::eval(
+            (&world as &dyn World).track(),
+            Route::default().track(),
+            Tracer::new().track_mut(),
+            &world.main,
+        )
+        .unwrap();
+
+        let scope = module.scope();
+        assert_eq!(scope.get_binding("answer").unwrap().docs(), Some("The answer."));
+        assert_eq!(scope.get_binding("other").unwrap().docs(), None);
+    }
 }
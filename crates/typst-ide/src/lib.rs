@@ -5,7 +5,7 @@ mod complete;
 mod jump;
 mod tooltip;
 
-pub use self::analyze::analyze_labels;
+pub use self::analyze::{analyze_labels, analyze_vars};
 pub use self::complete::{autocomplete, Completion, CompletionKind};
 pub use self::jump::{jump_from_click, jump_from_cursor, Jump};
 pub use self::tooltip::{tooltip, Tooltip};
@@ -1,12 +1,14 @@
 use std::fmt::Write;
+use std::path::Path;
 
 use ecow::{eco_format, EcoString};
 use if_chain::if_chain;
 use typst::eval::{CapturesVisitor, Tracer};
-use typst::foundations::{repr, Capturer, CastInfo, Repr, Value};
+use typst::foundations::{repr, CastInfo, Repr, Scopes, Value};
 use typst::layout::Length;
 use typst::model::Document;
-use typst::syntax::{ast, LinkedNode, Side, Source, SyntaxKind};
+use typst::syntax::package::PackageSpec;
+use typst::syntax::{ast, docs_for, LinkedNode, Side, Source, SyntaxKind};
 use typst::util::{round_2, Numeric};
 use typst::World;
 
@@ -33,6 +35,7 @@ pub fn tooltip(
     named_param_tooltip(world, &leaf)
         .or_else(|| font_tooltip(world, &leaf))
         .or_else(|| document.and_then(|doc| label_tooltip(doc, &leaf)))
+        .or_else(|| docs_tooltip(world, &leaf))
         .or_else(|| expr_tooltip(world, &leaf))
         .or_else(|| closure_tooltip(&leaf))
 }
@@ -46,6 +49,108 @@ pub enum Tooltip {
     Code(EcoString),
 }
 
+/// Tooltip for a hovered identifier whose definition is documented.
+fn docs_tooltip(world: &dyn World, leaf: &LinkedNode) -> Option<Tooltip> {
+    if !matches!(leaf.kind(), SyntaxKind::Ident | SyntaxKind::MathIdent) {
+        return None;
+    }
+
+    let name = leaf.text();
+    let parent = leaf.parent()?;
+
+    // A documented definition in a module: "module.item".
+    if let Some(access) = parent.cast::<ast::FieldAccess>() {
+        if access.field().span() == leaf.span() {
+            // Paths into the standard library resolve without evaluation,
+            // unless the file may bind their first segment to something else.
+            let scopes = Scopes::new(Some(world.library()));
+            if let Some(docs) = access
+                .path()
+                .filter(|path| {
+                    let first = path.split('.').next().unwrap_or_default();
+                    !may_be_bound(&root(leaf), first)
+                })
+                .and_then(|path| scopes.get_path(&path).ok())
+                .and_then(|resolved| resolved.binding()?.docs())
+            {
+                return Some(Tooltip::Text(docs.into()));
+            }
+
+            let target = parent.find(access.target().span())?;
+            let (value, _) = analyze_expr(world, &target).into_iter().next()?;
+            let docs = value.scope()?.get_binding(name)?.docs()?;
+            return Some(Tooltip::Text(docs.into()));
+        }
+    }
+
+    // A variable defined by a documented let binding.
+    let mut ancestor = Some(leaf.clone());
+    while let Some(node) = ancestor {
+        let mut sibling = node.prev_sibling();
+        while let Some(prev) = sibling {
+            if let Some(binding) = prev.cast::<ast::LetBinding>() {
+                if binding.kind().bindings().iter().any(|ident| ident.get() == name) {
+                    return docs_for(&prev).map(Tooltip::Text);
+                }
+            }
+            sibling = prev.prev_sibling();
+        }
+        ancestor = node.parent().cloned();
+    }
+
+    None
+}
+
+/// The root of the tree a node is part of.
+fn root<'a>(node: &LinkedNode<'a>) -> LinkedNode<'a> {
+    let mut node = node.clone();
+    while let Some(parent) = node.parent() {
+        node = parent.clone();
+    }
+    node
+}
+
+/// Whether a definition in or below the node may bind the given name.
+///
+/// Wildcard imports and bare imports of anything but a path may bind any
+/// name.
+fn may_be_bound(node: &LinkedNode, name: &str) -> bool {
+    let is = |ident: ast::Ident| ident.as_str() == name;
+    let bound = match node.cast::<ast::Expr>() {
+        Some(ast::Expr::Let(binding)) => binding.kind().bindings().into_iter().any(is),
+        Some(ast::Expr::For(for_loop)) => {
+            for_loop.pattern().bindings().into_iter().any(is)
+        }
+        Some(ast::Expr::Closure(closure)) => {
+            closure.params().children().any(|param| match param {
+                ast::Param::Pos(pattern) => pattern.bindings().into_iter().any(is),
+                ast::Param::Named(named) => is(named.name()),
+                ast::Param::Spread(spread) => spread.sink_ident().is_some_and(is),
+            })
+        }
+        Some(ast::Expr::Import(import)) => match (import.new_name(), import.imports()) {
+            (Some(new_name), _) if is(new_name) => true,
+            (_, Some(ast::Imports::Wildcard)) => true,
+            (_, Some(ast::Imports::Items(items))) => {
+                items.iter().any(|item| is(item.bound_name()))
+            }
+            (Some(_), None) => false,
+            (None, None) => match import.source() {
+                ast::Expr::Str(path) => match path.get().parse::<PackageSpec>() {
+                    Ok(spec) => spec.name.as_str() == name,
+                    Err(_) => Path::new(path.get().as_str())
+                        .file_stem()
+                        .map_or(true, |stem| stem == name),
+                },
+                _ => true,
+            },
+        },
+        _ => false,
+    };
+
+    bound || node.children().any(|child| may_be_bound(&child, name))
+}
+
 /// Tooltip for a hovered expression.
 fn expr_tooltip(world: &dyn World, leaf: &LinkedNode) -> Option<Tooltip> {
     let mut ancestor = leaf;
@@ -121,12 +226,10 @@ fn closure_tooltip(leaf: &LinkedNode) -> Option<Tooltip> {
     }
 
     // Analyze the closure's captures.
-    let mut visitor = CapturesVisitor::new(None, Capturer::Function);
-    visitor.visit(parent);
-
-    let captures = visitor.finish();
-    let mut names: Vec<_> =
-        captures.iter().map(|(name, _)| eco_format!("`{name}`")).collect();
+    let mut names: Vec<_> = CapturesVisitor::free_names(parent)
+        .into_iter()
+        .map(|name| eco_format!("`{name}`"))
+        .collect();
     if names.is_empty() {
         return None;
     }
@@ -250,3 +353,33 @@ fn font_tooltip(world: &dyn World, leaf: &LinkedNode) -> Option<Tooltip> {
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use typst::syntax::{parse, LinkedNode};
+
+    use super::may_be_bound;
+
+    #[track_caller]
+    fn test(text: &str, name: &str, bound: bool) {
+        let root = parse(text);
+        assert_eq!(may_be_bound(&LinkedNode::new(&root), name), bound, "{text}");
+    }
+
+    #[test]
+    fn test_may_be_bound() {
+        test("#calc.pi", "calc", false);
+        test("#let calc = (pi: 3)\n#calc.pi", "calc", true);
+        test("#let (a, calc) = (1, 2)", "calc", true);
+        test("#let f(calc) = calc.pi", "calc", true);
+        test("#let f(..calc) = none", "calc", true);
+        test("#for calc in () {}", "calc", true);
+        test("#import \"x.typ\" as calc", "calc", true);
+        test("#import \"x.typ\" as math", "calc", false);
+        test("#import \"x.typ\": a, b as calc", "calc", true);
+        test("#import \"x.typ\": *", "calc", true);
+        test("#import \"calc.typ\"", "calc", true);
+        test("#import \"other.typ\"", "calc", false);
+        test("#import \"@preview/calc:0.1.0\"", "calc", true);
+    }
+}
@@ -0,0 +1,120 @@
+//! Micro-benchmarks for splitting text into lines.
+//!
+//! Run with `cargo bench -p typst-syntax --bench lines`. Pass words after `--`
+//! to only run the benchmarks whose names contain one of them, e.g.
+//! `cargo bench -p typst-syntax --bench lines -- "last line"`.
+
+use std::fmt::Write;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use typst_syntax::{is_newline, lines, parse, split_newlines};
+use unscanny::Scanner;
+
+fn main() {
+    let text = raw_heavy_document();
+
+    bench("split lines (eager, before)", 100, || {
+        black_box(eager_split_newlines(&text).len());
+    });
+
+    bench("split lines (split_newlines)", 100, || {
+        black_box(split_newlines(&text).len());
+    });
+
+    bench("split lines (lines)", 100, || {
+        black_box(lines(&text).count());
+    });
+
+    bench("last line (eager, before)", 100, || {
+        black_box(eager_split_newlines(&text).last().copied());
+    });
+
+    bench("last line (lines)", 100, || {
+        black_box(lines(&text).next_back());
+    });
+
+    bench("parse raw-heavy document", 100, || {
+        black_box(parse(&text));
+    });
+}
+
+/// How many times each benchmark is measured.
+const SAMPLES: usize = 11;
+
+/// Run a closure repeatedly and print the median, fastest, and slowest
+/// average time per run over all samples.
+fn bench(name: &str, runs: u32, mut f: impl FnMut()) {
+    if !selected(name) {
+        return;
+    }
+
+    for _ in 0..runs / 10 {
+        f();
+    }
+
+    let mut samples: Vec<Duration> = (0..SAMPLES)
+        .map(|_| {
+            let start = Instant::now();
+            for _ in 0..runs {
+                f();
+            }
+            start.elapsed() / runs
+        })
+        .collect();
+
+    samples.sort();
+    println!(
+        "{name:<48} {:>12.2?} (min {:.2?}, max {:.2?})",
+        samples[SAMPLES / 2],
+        samples[0],
+        samples[SAMPLES - 1],
+    );
+}
+
+/// Whether a benchmark matches the filters given on the command line.
+fn selected(name: &str) -> bool {
+    let filters: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .collect();
+    filters.is_empty() || filters.iter().any(|filter| name.contains(filter.as_str()))
+}
+
+/// A document with 200 fenced raw blocks of 50 indented lines each.
+fn raw_heavy_document() -> String {
+    let mut text = String::new();
+    for block in 0..200 {
+        writeln!(text, "= Listing {block}\n").unwrap();
+        text.push_str("```rust\n");
+        for line in 0..50 {
+            writeln!(text, "    let x{line} = {line} * {block}; // a comment").unwrap();
+        }
+        text.push_str("```\n\n");
+    }
+    text
+}
+
+/// The implementation of `split_newlines` from before it collected the lazy
+/// line iterator.
+fn eager_split_newlines(text: &str) -> Vec<&str> {
+    let mut s = Scanner::new(text);
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut end = 0;
+
+    while let Some(c) = s.eat() {
+        if is_newline(c) {
+            if c == '\r' {
+                s.eat_if('\n');
+            }
+
+            lines.push(&text[start..end]);
+            start = s.cursor();
+        }
+        end = s.cursor();
+    }
+
+    lines.push(&text[start..]);
+    lines
+}
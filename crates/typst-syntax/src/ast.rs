@@ -2,6 +2,7 @@
 //!
 //! The AST is rooted in the [`Markup`] node.
 
+use std::borrow::Cow;
 use std::num::NonZeroUsize;
 use std::ops::Deref;
 
@@ -107,6 +108,8 @@ pub enum Expr<'a> {
 	Strong(Strong<'a>),
 	/// Emphasized content: `_Emphasized_`.
 	Emph(Emph<'a>),
+	/// Highlighted content: `==Highlighted==`.
+	Highlight(Highlight<'a>),
 	/// Raw text with optional syntax highlighting: `` `...` ``.
 	Raw(Raw<'a>),
 	/// A hyperlink: `https://typst.org`.
@@ -225,6 +228,7 @@ impl<'a> AstNode<'a> for Expr<'a> {
 			SyntaxKind::SmartQuote => node.cast().map(Self::SmartQuote),
 			SyntaxKind::Strong => node.cast().map(Self::Strong),
 			SyntaxKind::Emph => node.cast().map(Self::Emph),
+			SyntaxKind::Highlight => node.cast().map(Self::Highlight),
 			SyntaxKind::Raw => node.cast().map(Self::Raw),
 			SyntaxKind::Link => node.cast().map(Self::Link),
 			SyntaxKind::Label => node.cast().map(Self::Label),
@@ -288,6 +292,7 @@ impl<'a> AstNode<'a> for Expr<'a> {
 			Self::SmartQuote(v) => v.to_untyped(),
 			Self::Strong(v) => v.to_untyped(),
 			Self::Emph(v) => v.to_untyped(),
+			Self::Highlight(v) => v.to_untyped(),
 			Self::Raw(v) => v.to_untyped(),
 			Self::Link(v) => v.to_untyped(),
 			Self::Label(v) => v.to_untyped(),
@@ -550,6 +555,18 @@ impl<'a> Emph<'a> {
 	}
 }
 
+node! {
+	/// Highlighted content: `==Highlighted==`.
+	Highlight
+}
+
+impl<'a> Highlight<'a> {
+	/// The contents of the highlight node.
+	pub fn body(self) -> Markup<'a> {
+		self.0.cast_first_match().unwrap_or_default()
+	}
+}
+
 node! {
 	/// Raw text with optional syntax highlighting: `` `...` ``.
 	Raw
@@ -949,7 +966,7 @@ node! {
 impl Int<'_> {
 	/// Get the integer value.
 	pub fn get(self) -> i64 {
-		let text = self.0.text();
+		let text = without_separators(self.0.text());
 		if let Some(rest) = text.strip_prefix("0x") {
 			i64::from_str_radix(rest, 16)
 		} else if let Some(rest) = text.strip_prefix("0o") {
@@ -971,7 +988,7 @@ node! {
 impl Float<'_> {
 	/// Get the floating-point value.
 	pub fn get(self) -> f64 {
-		self.0.text().parse().unwrap_or_default()
+		without_separators(self.0.text()).parse().unwrap_or_default()
 	}
 }
 
@@ -991,7 +1008,7 @@ impl Numeric<'_> {
 			.count();
 
 		let split = text.len() - count;
-		let value = text[..split].parse().unwrap_or_default();
+		let value = without_separators(&text[..split]).parse().unwrap_or_default();
 		let unit = match &text[split..] {
 			"pt" => Unit::Pt,
 			"mm" => Unit::Mm,
@@ -1009,6 +1026,15 @@ impl Numeric<'_> {
 	}
 }
 
+/// Remove the digit separators from a number.
+fn without_separators(text: &str) -> Cow<'_, str> {
+	if text.contains('_') {
+		Cow::Owned(text.replace('_', ""))
+	} else {
+		Cow::Borrowed(text)
+	}
+}
+
 /// Unit of a numeric value.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum Unit {
@@ -1559,6 +1585,20 @@ impl<'a> FieldAccess<'a> {
 	pub fn field(self) -> Ident<'a> {
 		self.0.cast_last_match().unwrap_or_default()
 	}
+
+	/// The dotted path of the access, e.g. `a.b.c`, if the target is an
+	/// identifier or itself such a path.
+	pub fn path(self) -> Option<EcoString> {
+		let mut path = match self.target() {
+			Expr::Ident(ident) => ident.get().clone(),
+			Expr::MathIdent(ident) => ident.get().clone(),
+			Expr::FieldAccess(access) => access.path()?,
+			_ => return Option::None,
+		};
+		path.push('.');
+		path.push_str(self.field().as_str());
+		Some(path)
+	}
 }
 
 node! {
@@ -0,0 +1,124 @@
+use ecow::EcoString;
+
+use crate::{LinkedNode, SyntaxKind, SyntaxNode};
+
+/// Collect the doc comments directly above a node.
+///
+/// Doc comments are line comments starting with exactly three slashes and
+/// [doc comments](SyntaxKind::DocComment) starting with `//!`. They document
+/// the node if they are on the lines right before it, a blank line or any
+/// other node in between detaches them. The comment markers and one leading
+/// space per line are stripped.
+///
+/// Typically, the node is a let binding. Returns `None` if the node has no
+/// doc comments or is the root node.
+pub fn docs_for(node: &LinkedNode) -> Option<EcoString> {
+    let parent = node.parent()?;
+    docs_before(&parent.get().children().as_slice()[..node.index()])
+}
+
+/// Collect the doc comments at the end of a list of siblings.
+///
+/// This is like [`docs_for`], but for the node following the given
+/// siblings. In markup, the hash of an embedded expression may separate the
+/// node from its docs.
+pub fn docs_before(siblings: &[SyntaxNode]) -> Option<EcoString> {
+    let mut iter = siblings.iter().rev().peekable();
+    iter.next_if(|node| node.kind() == SyntaxKind::Hash);
+
+    let mut lines = vec![];
+    for node in iter {
+        match node.kind() {
+            SyntaxKind::LineComment => match doc_line(node.text()) {
+                Some(line) => lines.push(line),
+                None => break,
+            },
+            SyntaxKind::DocComment => lines.push(strip_marker(&node.text()[3..])),
+            SyntaxKind::Space if crate::lines(node.text()).nth(2).is_none() => {}
+            _ => break,
+        }
+    }
+
+    if lines.is_empty() {
+        return None;
+    }
+
+    let mut docs = EcoString::new();
+    for (i, line) in lines.iter().rev().enumerate() {
+        if i > 0 {
+            docs.push('\n');
+        }
+        docs.push_str(line);
+    }
+
+    Some(docs)
+}
+
+/// Extract the text of a doc comment line.
+fn doc_line(comment: &str) -> Option<&str> {
+    let line = comment.strip_prefix("///")?;
+    if line.starts_with('/') {
+        return None;
+    }
+    Some(strip_marker(line))
+}
+
+/// Strip the space separating a comment's text from its marker.
+fn strip_marker(line: &str) -> &str {
+    line.strip_prefix(' ').unwrap_or(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast, parse, parse_code};
+
+    #[track_caller]
+    fn test(root: &SyntaxNode, expected: Option<&str>) {
+        let node = LinkedNode::new(root);
+        let binding = find_let(&node).expect("no let binding");
+        assert_eq!(docs_for(&binding).as_deref(), expected);
+    }
+
+    fn find_let<'a>(node: &LinkedNode<'a>) -> Option<LinkedNode<'a>> {
+        if node.is::<ast::LetBinding>() {
+            return Some(node.clone());
+        }
+        node.children().find_map(|child| find_let(&child))
+    }
+
+    #[test]
+    fn test_docs_in_markup() {
+        test(&parse("/// A value.\n#let x = 1"), Some("A value."));
+        test(&parse("/// First.\n///   Second.\n#let x = 1"), Some("First.\n  Second."));
+        test(&parse("///No space.\n#let x = 1"), Some("No space."));
+        test(&parse("#let x = 1"), None);
+    }
+
+    #[test]
+    fn test_docs_in_code() {
+        test(&parse("#{\n  /// Inner.\n  let x = 1\n}"), Some("Inner."));
+        test(&parse_code("/// A value.\nlet x = 1"), Some("A value."));
+        test(&parse_code("x\n/// A value.\nlet x = 1"), Some("A value."));
+    }
+
+    #[test]
+    fn test_docs_detached() {
+        test(&parse("/// Detached.\n\n#let x = 1"), None);
+        test(&parse_code("/// Detached.\n\nlet x = 1"), None);
+        test(&parse("// Regular.\n#let x = 1"), None);
+        test(&parse("//// Not docs.\n#let x = 1"), None);
+        test(&parse("/// Ignored.\n// Regular.\n#let x = 1"), None);
+        test(&parse("/// Ignored.\n/* Block */\n#let x = 1"), None);
+    }
+
+    #[test]
+    fn test_docs_bang() {
+        test(&parse("//! A value.\n#let x = 1"), Some("A value."));
+        test(&parse("//! First.\n/// Second.\n#let x = 1"), Some("First.\nSecond."));
+        test(&parse_code("//!No space.\nlet x = 1"), Some("No space."));
+        test(&parse("#{\n  //! Inner.\n  let x = 1\n}"), Some("Inner."));
+        test(&parse("//! Detached.\n\n#let x = 1"), None);
+        test(&parse_code("//! Detached.\n\nlet x = 1"), None);
+    }
+}
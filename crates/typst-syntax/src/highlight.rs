@@ -152,6 +152,7 @@ pub fn highlight(node: &LinkedNode) -> Option<Tag> {
         SyntaxKind::SmartQuote => None,
         SyntaxKind::Strong => Some(Tag::Strong),
         SyntaxKind::Emph => Some(Tag::Emph),
+        SyntaxKind::Highlight => None,
         SyntaxKind::Raw => Some(Tag::Raw),
         SyntaxKind::RawLang => None,
         SyntaxKind::RawTrimmed => None,
@@ -211,7 +212,10 @@ pub fn highlight(node: &LinkedNode) -> Option<Tag> {
             Some(SyntaxKind::Heading) => None,
             _ => Some(Tag::Operator),
         },
-        SyntaxKind::EqEq => Some(Tag::Operator),
+        SyntaxKind::EqEq => match node.parent_kind() {
+            Some(SyntaxKind::Highlight) => None,
+            _ => Some(Tag::Operator),
+        },
         SyntaxKind::ExclEq => Some(Tag::Operator),
         SyntaxKind::Lt => Some(Tag::Operator),
         SyntaxKind::LtEq => Some(Tag::Operator),
@@ -286,6 +290,7 @@ pub fn highlight(node: &LinkedNode) -> Option<Tag> {
         SyntaxKind::DestructAssignment => None,
 
         SyntaxKind::LineComment => Some(Tag::Comment),
+        SyntaxKind::DocComment => Some(Tag::Comment),
         SyntaxKind::BlockComment => Some(Tag::Comment),
         SyntaxKind::Error => Some(Tag::Error),
         SyntaxKind::End => None,
@@ -0,0 +1,358 @@
+use std::ops::Range;
+
+use crate::is_newline;
+
+/// Converts between byte offsets and line/column positions in a text.
+///
+/// Lines are split at the same [newline characters](is_newline) as by the
+/// lexer, where `\r\n` counts as a single newline. All line and column
+/// indices start at zero. Columns count characters or, with the `_utf16`
+/// methods, UTF-16 code units from the start of the line, as used by the
+/// language server protocol.
+///
+/// The index only stores where lines start, so it doesn't borrow the text.
+/// Instead, methods that need to look at the characters of a line take it
+/// as an argument. It must be the text the index was built for.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct LineIndex {
+    /// Where each line starts. Always contains at least the first line.
+    lines: Vec<Line>,
+    /// The length of the text in bytes.
+    len_bytes: usize,
+    /// The length of the text in UTF-16 code units.
+    len_utf16: usize,
+}
+
+impl LineIndex {
+    /// Build the index for a text.
+    pub fn new(text: &str) -> Self {
+        let lines = std::iter::once(Line { byte_idx: 0, utf16_idx: 0 })
+            .chain(lines_from(0, 0, text))
+            .collect();
+        Self {
+            lines,
+            len_bytes: text.len(),
+            len_utf16: len_utf16(text),
+        }
+    }
+
+    /// Update the index after the `replaced` range of the old text was
+    /// replaced with `with_len` bytes, resulting in the given new `text`.
+    ///
+    /// Only the lines touched by the edit are rescanned. The starts of the
+    /// lines after it are shifted.
+    ///
+    /// The method panics if the `replaced` range is out of bounds.
+    #[track_caller]
+    pub fn edit(&mut self, text: &str, replaced: Range<usize>, with_len: usize) {
+        assert!(replaced.start <= replaced.end && replaced.end <= self.len_bytes);
+
+        // Rescan from the start of the line the edit starts in. A line that
+        // starts exactly at the edit is rescanned, too, since a `\r` before
+        // it may now be followed by a `\n`.
+        let keep = self.lines.partition_point(|line| line.byte_idx < replaced.start);
+        let start = self.lines[keep.max(1) - 1];
+
+        // Lines that start after the edit are terminated by an unchanged
+        // newline, so they still start a line, just at a shifted offset.
+        let tail = self.lines.partition_point(|line| line.byte_idx <= replaced.end);
+        let rest = self.lines.split_off(tail);
+        let end = rest
+            .first()
+            .map_or(text.len(), |line| line.byte_idx - replaced.len() + with_len);
+
+        let scanned = &text[start.byte_idx..end];
+        let utf16_end = start.utf16_idx + len_utf16(scanned);
+        self.lines.truncate(keep.max(1));
+        self.lines.extend(
+            lines_from(start.byte_idx, start.utf16_idx, scanned)
+                .filter(|line| line.byte_idx < end),
+        );
+
+        let old_len_utf16 = self.len_utf16;
+        self.len_bytes = text.len();
+        self.len_utf16 = utf16_end;
+        if let Some(&first) = rest.first() {
+            self.len_utf16 += old_len_utf16 - first.utf16_idx;
+            self.lines.extend(rest.into_iter().map(|line| Line {
+                byte_idx: line.byte_idx - first.byte_idx + end,
+                utf16_idx: line.utf16_idx - first.utf16_idx + utf16_end,
+            }));
+        }
+    }
+
+    /// The number of lines.
+    pub fn len_lines(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// The length of the text in UTF-16 code units.
+    pub fn len_utf16(&self) -> usize {
+        self.len_utf16
+    }
+
+    /// Return the index of the line that contains the given byte offset.
+    ///
+    /// Returns `None` if the offset is past the end of the text.
+    pub fn line(&self, byte_idx: usize) -> Option<usize> {
+        (byte_idx <= self.len_bytes).then(|| {
+            match self.lines.binary_search_by_key(&byte_idx, |line| line.byte_idx) {
+                Ok(i) => i,
+                Err(i) => i - 1,
+            }
+        })
+    }
+
+    /// Return the line and the column in characters at the byte offset.
+    ///
+    /// Returns `None` if the offset is past the end of the text or not at a
+    /// character boundary.
+    pub fn line_col(&self, text: &str, byte_idx: usize) -> Option<(usize, usize)> {
+        let line = self.line(byte_idx)?;
+        let head = text.get(self.lines[line].byte_idx..byte_idx)?;
+        Some((line, head.chars().count()))
+    }
+
+    /// Return the line and the column in UTF-16 code units at the byte
+    /// offset.
+    ///
+    /// Returns `None` if the offset is past the end of the text or not at a
+    /// character boundary.
+    pub fn line_col_utf16(&self, text: &str, byte_idx: usize) -> Option<(usize, usize)> {
+        let line = self.line(byte_idx)?;
+        let head = text.get(self.lines[line].byte_idx..byte_idx)?;
+        Some((line, len_utf16(head)))
+    }
+
+    /// Return the index of the UTF-16 code unit at the byte offset.
+    pub fn byte_to_utf16(&self, text: &str, byte_idx: usize) -> Option<usize> {
+        let line = self.line(byte_idx)?;
+        let head = text.get(self.lines[line].byte_idx..byte_idx)?;
+        Some(self.lines[line].utf16_idx + len_utf16(head))
+    }
+
+    /// Return the byte offset at the UTF-16 code unit.
+    pub fn utf16_to_byte(&self, text: &str, utf16_idx: usize) -> Option<usize> {
+        let line = self.lines.get(
+            match self.lines.binary_search_by_key(&utf16_idx, |line| line.utf16_idx) {
+                Ok(i) => i,
+                Err(i) => i - 1,
+            },
+        )?;
+
+        let mut k = line.utf16_idx;
+        for (i, c) in text[line.byte_idx..].char_indices() {
+            if k >= utf16_idx {
+                return Some(line.byte_idx + i);
+            }
+            k += c.len_utf16();
+        }
+
+        (k == utf16_idx).then_some(text.len())
+    }
+
+    /// Return the byte offset at which the given line starts.
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        self.lines.get(line).map(|line| line.byte_idx)
+    }
+
+    /// Return the byte range of the given line, including the newline that
+    /// terminates it.
+    pub fn line_range(&self, line: usize) -> Option<Range<usize>> {
+        let start = self.line_start(line)?;
+        let end = self.line_start(line + 1).unwrap_or(self.len_bytes);
+        Some(start..end)
+    }
+
+    /// Return the byte offset at the given line and column in characters.
+    ///
+    /// Returns `None` if there is no such line or the column is past its
+    /// end. The end of a line is right before its newline.
+    pub fn offset(&self, text: &str, line: usize, col: usize) -> Option<usize> {
+        let (start, content) = self.line_content(text, line)?;
+        let mut chars = content.char_indices().map(|(i, _)| i).chain([content.len()]);
+        chars.nth(col).map(|i| start + i)
+    }
+
+    /// Return the byte offset at the given line and column in UTF-16 code
+    /// units.
+    ///
+    /// Returns `None` if there is no such line, the column is past its end
+    /// or in the middle of a character.
+    pub fn offset_utf16(&self, text: &str, line: usize, col: usize) -> Option<usize> {
+        let (start, content) = self.line_content(text, line)?;
+        let mut k = 0;
+        for (i, c) in content.char_indices() {
+            if k >= col {
+                return (k == col).then_some(start + i);
+            }
+            k += c.len_utf16();
+        }
+        (k == col).then_some(start + content.len())
+    }
+
+    /// The start and text of a line without its terminating newline.
+    fn line_content<'a>(&self, text: &'a str, line: usize) -> Option<(usize, &'a str)> {
+        let range = self.line_range(line)?;
+        let start = range.start;
+        let mut content = text.get(range)?;
+        if let Some(stripped) = content.strip_suffix("\r\n") {
+            content = stripped;
+        } else if let Some(stripped) = content.strip_suffix(is_newline) {
+            content = stripped;
+        }
+        Some((start, content))
+    }
+}
+
+/// Metadata about a line.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+struct Line {
+    /// The UTF-8 byte offset where the line starts.
+    byte_idx: usize,
+    /// The UTF-16 codepoint offset where the line starts.
+    utf16_idx: usize,
+}
+
+/// Compute a line iterator from an offset.
+fn lines_from(
+    byte_offset: usize,
+    utf16_offset: usize,
+    text: &str,
+) -> impl Iterator<Item = Line> + '_ {
+    let mut s = unscanny::Scanner::new(text);
+    let mut utf16_idx = utf16_offset;
+
+    std::iter::from_fn(move || {
+        s.eat_until(|c: char| {
+            utf16_idx += c.len_utf16();
+            is_newline(c)
+        });
+
+        if s.done() {
+            return None;
+        }
+
+        if s.eat() == Some('\r') && s.eat_if('\n') {
+            utf16_idx += 1;
+        }
+
+        Some(Line { byte_idx: byte_offset + s.cursor(), utf16_idx })
+    })
+}
+
+/// The number of code units this string would use if it was encoded in
+/// UTF16. This runs in linear time.
+fn len_utf16(string: &str) -> usize {
+    string.chars().map(char::len_utf16).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST: &str = "ä\tcde\nf💛g\r\nhi\rjkl";
+
+    #[test]
+    fn test_line_index_new() {
+        let index = LineIndex::new(TEST);
+        assert_eq!(
+            index.lines,
+            [
+                Line { byte_idx: 0, utf16_idx: 0 },
+                Line { byte_idx: 7, utf16_idx: 6 },
+                Line { byte_idx: 15, utf16_idx: 12 },
+                Line { byte_idx: 18, utf16_idx: 15 },
+            ]
+        );
+        assert_eq!(index.len_lines(), 4);
+        assert_eq!(index.len_utf16(), 18);
+    }
+
+    #[test]
+    fn test_line_index_newlines() {
+        let text = "a\r\nb\rc\u{0085}d\u{2028}e\u{2029}f\x0Bg\x0Ch\n";
+        let index = LineIndex::new(text);
+        assert_eq!(index.len_lines(), 9);
+        let starts: Vec<_> =
+            (0..index.len_lines()).map(|i| index.line_start(i).unwrap()).collect();
+        assert_eq!(starts, [0, 3, 5, 8, 12, 16, 18, 20, 22]);
+        assert_eq!(index.line_range(0), Some(0..3));
+        assert_eq!(index.line_range(8), Some(22..22));
+        assert_eq!(index.line_range(9), None);
+
+        // The lexer's lines start at the same offsets.
+        let spans: Vec<_> =
+            crate::lines(text).spanned().map(|(range, _)| range.start).collect();
+        assert_eq!(spans, starts);
+    }
+
+    #[test]
+    fn test_line_index_line_col() {
+        let index = LineIndex::new(TEST);
+        assert_eq!(index.line_col(TEST, 0), Some((0, 0)));
+        assert_eq!(index.line_col(TEST, 2), Some((0, 1)));
+        assert_eq!(index.line_col(TEST, 6), Some((0, 5)));
+        assert_eq!(index.line_col(TEST, 7), Some((1, 0)));
+        assert_eq!(index.line_col(TEST, 12), Some((1, 2)));
+        assert_eq!(index.line_col(TEST, 13), Some((1, 3)));
+        assert_eq!(index.line_col(TEST, 21), Some((3, 3)));
+        assert_eq!(index.line_col(TEST, 22), None);
+        assert_eq!(index.line_col(TEST, 1), None);
+
+        assert_eq!(index.line_col_utf16(TEST, 12), Some((1, 3)));
+        assert_eq!(index.line_col_utf16(TEST, 13), Some((1, 4)));
+        assert_eq!(index.line_col_utf16(TEST, 22), None);
+    }
+
+    #[test]
+    fn test_line_index_offset() {
+        let index = LineIndex::new(TEST);
+        assert_eq!(index.offset(TEST, 0, 0), Some(0));
+        assert_eq!(index.offset(TEST, 0, 1), Some(2));
+        assert_eq!(index.offset(TEST, 0, 5), Some(6));
+        assert_eq!(index.offset(TEST, 0, 6), None);
+        assert_eq!(index.offset(TEST, 1, 2), Some(12));
+        assert_eq!(index.offset(TEST, 1, 3), Some(13));
+        assert_eq!(index.offset(TEST, 1, 4), None);
+        assert_eq!(index.offset(TEST, 3, 3), Some(21));
+        assert_eq!(index.offset(TEST, 4, 0), None);
+
+        assert_eq!(index.offset_utf16(TEST, 1, 2), None);
+        assert_eq!(index.offset_utf16(TEST, 1, 3), Some(12));
+        assert_eq!(index.offset_utf16(TEST, 1, 4), Some(13));
+        assert_eq!(index.offset_utf16(TEST, 1, 5), None);
+
+        for byte_idx in (0..=TEST.len()).filter(|&i| TEST.is_char_boundary(i)) {
+            if let Some((line, col)) = index.line_col(TEST, byte_idx) {
+                if let Some(offset) = index.offset(TEST, line, col) {
+                    assert_eq!(offset, byte_idx);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_line_index_edit() {
+        #[track_caller]
+        fn test(prev: &str, range: Range<usize>, with: &str) {
+            let mut text = prev.to_string();
+            text.replace_range(range.clone(), with);
+            let mut index = LineIndex::new(prev);
+            index.edit(&text, range, with.len());
+            assert_eq!(index, LineIndex::new(&text));
+        }
+
+        test("abc\n", 0..0, "hi\n");
+        test("\nabc", 0..0, "hi\r");
+        test(TEST, 4..16, "❌");
+        test(TEST, 6..7, "");
+        test(TEST, 13..14, "\u{2028}💛");
+        test("abc\ndef", 7..7, "hi");
+        test("abc\ndef\r", 8..8, "\nghi");
+        test("a\rb\nc", 2..2, "\n");
+        test("a\r\nb\nc", 2..3, "x");
+        test("a\nb\nc\nd", 2..3, "\r\n\u{0085}");
+        test(TEST, 0..21, "");
+    }
+}
@@ -15,6 +15,12 @@ pub enum SyntaxKind {
 	Linebreak,
 	/// A paragraph break, indicated by one or multiple blank lines.
 	Parbreak,
+	/// A line comment: `// ...`.
+	LineComment,
+	/// A doc comment: `//! ...`.
+	DocComment,
+	/// A block comment: `/* ... */`.
+	BlockComment,
 	/// An escape sequence: `\#`, `\u{1F5FA}`.
 	Escape,
 	/// A shorthand for a unicode codepoint. For example, `~` for non-breaking
@@ -26,6 +32,8 @@ pub enum SyntaxKind {
 	Strong,
 	/// Emphasized content: `_Emphasized_`.
 	Emph,
+	/// Highlighted content: `==Highlighted==`.
+	Highlight,
 	/// Raw text with optional syntax highlighting: `` `...` ``.
 	Raw,
 	/// A language tag at the start of raw text: ``typ ``.
@@ -122,7 +130,7 @@ pub enum SyntaxKind {
 	Dot,
 	/// The assignment operator: `=`.
 	Eq,
-	/// The equality operator: `==`.
+	/// The equality operator and highlighted text toggle: `==`.
 	EqEq,
 	/// The inequality operator: `!=`.
 	ExclEq,
@@ -267,10 +275,6 @@ pub enum SyntaxKind {
 	/// A destructuring assignment expression: `(x, y) = (1, 2)`.
 	DestructAssignment,
 
-	/// A line comment: `// ...`.
-	LineComment,
-	/// A block comment: `/* ... */`.
-	BlockComment,
 	/// An invalid sequence of characters.
 	Error,
 	/// The end of token stream.
@@ -352,7 +356,11 @@ impl SyntaxKind {
 	pub fn is_trivia(self) -> bool {
 		matches!(
 			self,
-			Self::Space | Self::Parbreak | Self::LineComment | Self::BlockComment
+			Self::Space
+				| Self::Parbreak
+				| Self::LineComment
+				| Self::DocComment
+				| Self::BlockComment
 		)
 	}
 
@@ -369,11 +377,15 @@ impl SyntaxKind {
 			Self::Space => "space",
 			Self::Linebreak => "line break",
 			Self::Parbreak => "paragraph break",
+			Self::LineComment => "line comment",
+			Self::DocComment => "doc comment",
+			Self::BlockComment => "block comment",
 			Self::Escape => "escape sequence",
 			Self::Shorthand => "shorthand",
 			Self::SmartQuote => "smart quote",
 			Self::Strong => "strong content",
 			Self::Emph => "emphasized content",
+			Self::Highlight => "highlighted content",
 			Self::Raw => "raw block",
 			Self::RawLang => "raw language tag",
 			Self::RawTrimmed => "raw trimmed",
@@ -490,8 +502,6 @@ impl SyntaxKind {
 			Self::FuncReturn => "`return` expression",
 			Self::Destructuring => "destructuring pattern",
 			Self::DestructAssignment => "destructuring assignment expression",
-			Self::LineComment => "line comment",
-			Self::BlockComment => "block comment",
 			Self::Error => "syntax error",
 			Self::End => "end of tokens",
 		}
@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 use ecow::{eco_format, EcoString};
 use unicode_ident::{is_xid_continue, is_xid_start};
 use unicode_script::{Script, UnicodeScript};
@@ -80,6 +82,82 @@ impl<'s> Lexer<'s> {
 	}
 }
 
+/// Split a string of source code into a flat sequence of tokens, starting in
+/// the given mode.
+///
+/// Unlike [`parse`](crate::parse), this doesn't build a syntax tree and thus
+/// doesn't switch modes where the parser would, e.g. after a `$` or `#`. The
+/// only exception are raw blocks, whose delimiters, language tag, text, and
+/// trimmed whitespace are yielded as separate tokens. The ranges of all
+/// tokens are contiguous and cover the full text.
+pub fn tokenize(text: &str, mode: TokenizerMode) -> Tokens<'_> {
+	let mode = match mode {
+		TokenizerMode::Markup => LexMode::Markup,
+		TokenizerMode::Math => LexMode::Math,
+		TokenizerMode::Code => LexMode::Code,
+	};
+	Tokens { lexer: Lexer::new(text, mode), mode }
+}
+
+/// The mode in which to [`tokenize`] a text.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum TokenizerMode {
+	/// Text and markup.
+	Markup,
+	/// Math atoms, operators, etc.
+	Math,
+	/// Keywords, literals and operators.
+	Code,
+}
+
+/// A token produced by [`tokenize`].
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Token {
+	/// The kind of the token.
+	pub kind: SyntaxKind,
+	/// The byte range of the token in the text.
+	pub range: Range<usize>,
+	/// The error message if the token is malformed.
+	pub error: Option<EcoString>,
+}
+
+/// An iterator over the tokens of a text, see [`tokenize`].
+#[derive(Clone)]
+pub struct Tokens<'s> {
+	/// The underlying lexer.
+	lexer: Lexer<'s>,
+	/// The mode to return to after a raw block.
+	mode: LexMode,
+}
+
+impl Iterator for Tokens<'_> {
+	type Item = Token;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let start = self.lexer.cursor();
+		let mut kind = self.lexer.next();
+
+		// When the raw segments are used up, continue after the raw block.
+		if kind == SyntaxKind::End && self.lexer.mode() == LexMode::Raw {
+			self.lexer.set_mode(self.mode);
+			kind = self.lexer.next();
+		}
+
+		if kind == SyntaxKind::End {
+			return None;
+		}
+
+		let error = self.lexer.take_error();
+
+		// An opening delimiter is followed by the segments of the raw block.
+		if kind == SyntaxKind::RawDelim && self.lexer.mode() != LexMode::Raw {
+			self.lexer.set_mode(LexMode::Raw);
+		}
+
+		Some(Token { kind, range: start..self.lexer.cursor(), error })
+	}
+}
+
 impl Lexer<'_> {
 	/// Construct a full-positioned syntax error.
 	fn error(&mut self, message: impl Into<EcoString>) -> SyntaxKind {
@@ -128,7 +206,7 @@ impl Lexer<'_> {
 		let more = self.s.eat_while(|c| is_space(c, self.mode));
 		let newlines = match c {
 			' ' if more.is_empty() => 0,
-			_ => count_newlines(self.s.from(start)),
+			_ => NewlineAnalysis::of(self.s.from(start)).count,
 		};
 
 		self.newline = newlines > 0;
@@ -140,8 +218,13 @@ impl Lexer<'_> {
 	}
 
 	fn line_comment(&mut self) -> SyntaxKind {
+		let doc = self.s.eat_if('!');
 		self.s.eat_until(is_newline);
-		SyntaxKind::LineComment
+		if doc {
+			SyntaxKind::DocComment
+		} else {
+			SyntaxKind::LineComment
+		}
 	}
 
 	fn block_comment(&mut self) -> SyntaxKind {
@@ -168,6 +251,17 @@ impl Lexer<'_> {
 
 		SyntaxKind::BlockComment
 	}
+
+	/// Eat the first shorthand from the table that the text at `start` begins
+	/// with.
+	fn shorthand(&mut self, start: usize, table: &[(&str, char)]) -> bool {
+		let text = self.s.get(start..self.s.string().len());
+		let Some((shorthand, _)) = table.iter().find(|(s, _)| text.starts_with(s)) else {
+			return false;
+		};
+		self.s.jump(start + shorthand.len());
+		true
+	}
 }
 
 /// Markup.
@@ -176,18 +270,15 @@ impl Lexer<'_> {
 		match c {
 			'\\' => self.backslash(),
 			'`' => self.raw(),
-			'h' if self.s.eat_if("ttp://") => self.link(),
-			'h' if self.s.eat_if("ttps://") => self.link(),
-			'<' if self.s.at(is_id_continue) => self.label(),
+			'h' if self.s.eat_if("ttp://") => self.link(start),
+			'h' if self.s.eat_if("ttps://") => self.link(start),
+			'<' if self.s.at(is_label_start) => self.label(),
 			'@' => self.ref_marker(),
 
-			'.' if self.s.eat_if("..") => SyntaxKind::Shorthand,
-			'-' if self.s.eat_if("--") => SyntaxKind::Shorthand,
-			'-' if self.s.eat_if('-') => SyntaxKind::Shorthand,
-			'-' if self.s.eat_if('?') => SyntaxKind::Shorthand,
+			_ if self.shorthand(start, MARKUP_SHORTHANDS) => SyntaxKind::Shorthand,
 			'-' if self.s.at(char::is_numeric) => SyntaxKind::Shorthand,
-			'*' if !self.in_word() => SyntaxKind::Star,
-			'_' if !self.in_word() => SyntaxKind::Underscore,
+			'*' if !self.in_word(start) => SyntaxKind::Star,
+			'_' if !self.in_word(start) => SyntaxKind::Underscore,
 
 			'#' => SyntaxKind::Hash,
 			'[' => SyntaxKind::LeftBracket,
@@ -195,11 +286,17 @@ impl Lexer<'_> {
 			'\'' => SyntaxKind::SmartQuote,
 			'"' => SyntaxKind::SmartQuote,
 			'$' => SyntaxKind::Dollar,
-			'~' => SyntaxKind::Shorthand,
 			':' => SyntaxKind::Colon,
 			'=' => {
 				self.s.eat_while('=');
-				if self.space_or_end() {
+				if self.space_or_end() && self.after_space(start) {
+					SyntaxKind::HeadingMarker
+				} else if self.s.from(start) == "=="
+					&& !self.in_word(start)
+					&& !self.s.at('>')
+				{
+					SyntaxKind::EqEq
+				} else if self.space_or_end() {
 					SyntaxKind::HeadingMarker
 				} else {
 					self.text()
@@ -267,28 +364,37 @@ impl Lexer<'_> {
 			}
 		}
 
-		if found != backticks {
-			return self.error("unclosed raw text");
-		}
-
+		// Without a closing delimiter, the raw text extends to the end of the
+		// file.
+		let closed = found == backticks;
 		let end = self.s.cursor();
+		let inner_end = if closed { end - backticks } else { end };
 		if backticks >= 3 {
-			self.blocky_raw(start, end, backticks);
+			self.blocky_raw(start, inner_end, backticks);
 		} else {
-			self.inline_raw(start, end, backticks);
+			self.inline_raw(start, inner_end, backticks);
 		}
 
 		// Closing delimiter.
-		self.push_raw(SyntaxKind::RawDelim);
+		if closed {
+			self.s.jump(end);
+			self.push_raw(SyntaxKind::RawDelim);
+		}
 
 		// The saved tokens will be removed in reverse.
 		self.raw.reverse();
 
-		// Opening delimiter.
+		// Opening delimiter. If the raw text is unclosed, the error is
+		// attached to it.
 		self.s.jump(start + backticks);
+		if !closed {
+			self.error = Some("unclosed raw text".into());
+		}
 		SyntaxKind::RawDelim
 	}
 
+	/// Lex the lines of a raw block with the given opening delimiter, whose
+	/// content ends at `end`.
 	fn blocky_raw(&mut self, start: usize, end: usize, backticks: usize) {
 		// Language tag.
 		self.s.jump(start + backticks);
@@ -300,33 +406,35 @@ impl Lexer<'_> {
 		// Determine inner content between backticks and with trimmed
 		// single spaces (line trimming comes later).
 		self.s.eat_if(' ');
-		let mut inner = self.s.to(end - backticks);
+		let mut inner = self.s.to(end);
 		if inner.trim_end().ends_with('`') {
 			inner = inner.strip_suffix(' ').unwrap_or(inner);
 		}
 
 		// Determine dedent level.
-		let lines = split_newlines(inner);
+		let base = self.s.cursor();
+		let mut lines = lines(inner)
+			.spanned()
+			.map(|(range, _)| (&inner[range.clone()], range));
 		let dedent = lines
-			.iter()
+			.clone()
 			.skip(1)
-			.filter(|line| !line.chars().all(char::is_whitespace))
+			.filter(|(line, _)| !line.chars().all(char::is_whitespace))
 			// The line with the closing ``` is always taken into account
-			.chain(lines.last())
-			.map(|line| line.chars().take_while(|c| c.is_whitespace()).count())
+			.chain(lines.clone().next_back())
+			.map(|(line, _)| line.chars().take_while(|c| c.is_whitespace()).count())
 			.min()
 			.unwrap_or(0);
 
-		let is_whitespace = |line: &&str| line.chars().all(char::is_whitespace);
-		let starts_whitespace = lines.first().is_some_and(is_whitespace);
-		let ends_whitespace = lines.last().is_some_and(is_whitespace);
+		let is_whitespace = |(line, _): (&str, _)| line.chars().all(char::is_whitespace);
+		let starts_whitespace = lines.clone().next().is_some_and(is_whitespace);
+		let ends_whitespace = lines.clone().next_back().is_some_and(is_whitespace);
 
-		let mut lines = lines.into_iter();
 		let mut skipped = false;
 
 		// Trim whitespace + newline at start.
 		if starts_whitespace {
-			self.s.advance(lines.next().unwrap().len());
+			lines.next();
 			skipped = true;
 		}
 		// Trim whitespace + newline at end.
@@ -335,28 +443,28 @@ impl Lexer<'_> {
 		}
 
 		// Add lines.
-		for (i, line) in lines.enumerate() {
+		for (i, (line, range)) in lines.enumerate() {
 			let dedent = if i == 0 && !skipped { 0 } else { dedent };
 			let offset: usize = line.chars().take(dedent).map(char::len_utf8).sum();
-			self.s.eat_newline();
-			self.s.advance(offset);
+			self.s.jump(base + range.start + offset);
 			self.push_raw(SyntaxKind::RawTrimmed);
-			self.s.advance(line.len() - offset);
+			self.s.jump(base + range.end);
 			self.push_raw(SyntaxKind::Text);
 		}
 
 		// Add final trimmed.
-		if self.s.cursor() < end - backticks {
-			self.s.jump(end - backticks);
+		if self.s.cursor() < end {
+			self.s.jump(end);
 			self.push_raw(SyntaxKind::RawTrimmed);
 		}
-		self.s.jump(end);
 	}
 
+	/// Lex the lines of inline raw text with the given opening delimiter,
+	/// whose content ends at `end`.
 	fn inline_raw(&mut self, start: usize, end: usize, backticks: usize) {
 		self.s.jump(start + backticks);
 
-		while self.s.cursor() < end - backticks {
+		while self.s.cursor() < end {
 			if self.s.at(is_newline) {
 				self.push_raw(SyntaxKind::Text);
 				self.s.eat_newline();
@@ -366,8 +474,6 @@ impl Lexer<'_> {
 			self.s.eat();
 		}
 		self.push_raw(SyntaxKind::Text);
-
-		self.s.jump(end);
 	}
 
 	/// Push the current cursor that marks the end of a raw segment of
@@ -377,9 +483,9 @@ impl Lexer<'_> {
 		self.raw.push((kind, end));
 	}
 
-	fn link(&mut self) -> SyntaxKind {
-		let (link, balanced) = link_prefix(self.s.after());
-		self.s.advance(link.len());
+	fn link(&mut self, start: usize) -> SyntaxKind {
+		let (parts, balanced) = split_link(&self.s.string()[start..]).unwrap();
+		self.s.jump(start + parts.path.end);
 
 		if !balanced {
 			return self.error(
@@ -403,18 +509,17 @@ impl Lexer<'_> {
 	}
 
 	fn ref_marker(&mut self) -> SyntaxKind {
-		self.s.eat_while(|c| is_id_continue(c) || matches!(c, ':' | '.'));
+		let target = self.s.eat_while(is_valid_in_label_literal);
 
 		// Don't include the trailing characters likely to be part of text.
-		while matches!(self.s.scout(-1), Some('.' | ':')) {
-			self.s.uneat();
-		}
+		let trailer = target.len() - trim_ref_target(target).len();
+		self.s.jump(self.s.cursor() - trailer);
 
 		SyntaxKind::RefMarker
 	}
 
 	fn label(&mut self) -> SyntaxKind {
-		let label = self.s.eat_while(|c| is_id_continue(c) || matches!(c, ':' | '.'));
+		let label = self.s.eat_while(is_valid_in_label_literal);
 		if label.is_empty() {
 			return self.error("label cannot be empty");
 		}
@@ -440,7 +545,7 @@ impl Lexer<'_> {
 		table! {
 			| ' ' | '\t' | '\n' | '\x0b' | '\x0c' | '\r' | '\\' | '/'
 			| '[' | ']' | '~' | '-' | '.' | '\'' | '"' | '*' | '_'
-			| ':' | 'h' | '`' | '$' | '<' | '>' | '@' | '#'
+			| ':' | 'h' | '`' | '$' | '<' | '>' | '@' | '#' | '='
 		};
 
 		loop {
@@ -456,6 +561,11 @@ impl Lexer<'_> {
 				Some('/') if !s.at(['/', '*']) => {}
 				Some('-') if !s.at(['-', '?']) => {}
 				Some('.') if !s.at("..") => {}
+				Some('=') if !s.at('=') => {}
+				Some('=')
+					if s.eat_if('=')
+						&& !s.at('=') && is_wordy(self.s.scout(-1))
+						&& is_wordy(s.peek()) => {}
 				Some('h') if !s.at("ttp://") && !s.at("ttps://") => {}
 				Some('@') if !s.at(is_id_start) => {}
 				_ => break,
@@ -467,22 +577,17 @@ impl Lexer<'_> {
 		SyntaxKind::Text
 	}
 
-	fn in_word(&self) -> bool {
-		let wordy = |c: Option<char>| {
-			c.is_some_and(|c| {
-				c.is_alphanumeric()
-					&& !matches!(
-						c.script(),
-						Script::Han
-							| Script::Hiragana
-							| Script::Katakana
-							| Script::Hangul
-					)
-			})
-		};
-		let prev = self.s.scout(-2);
-		let next = self.s.peek();
-		wordy(prev) && wordy(next)
+	/// Whether the marker starting at `start` is surrounded by word characters.
+	fn in_word(&self, start: usize) -> bool {
+		let prev = self.s.get(0..start).chars().next_back();
+		is_wordy(prev) && is_wordy(self.s.peek())
+	}
+
+	/// Whether the marker starting at `start` is at the start of the text or
+	/// preceded by whitespace.
+	fn after_space(&self, start: usize) -> bool {
+		let before = self.s.get(0..start);
+		before.is_empty() || before.ends_with(char::is_whitespace)
 	}
 
 	fn space_or_end(&self) -> bool {
@@ -497,42 +602,7 @@ impl Lexer<'_> {
 			'\\' => self.backslash(),
 			'"' => self.string(),
 
-			'-' if self.s.eat_if(">>") => SyntaxKind::Shorthand,
-			'-' if self.s.eat_if('>') => SyntaxKind::Shorthand,
-			'-' if self.s.eat_if("->") => SyntaxKind::Shorthand,
-			':' if self.s.eat_if('=') => SyntaxKind::Shorthand,
-			':' if self.s.eat_if(":=") => SyntaxKind::Shorthand,
-			'!' if self.s.eat_if('=') => SyntaxKind::Shorthand,
-			'.' if self.s.eat_if("..") => SyntaxKind::Shorthand,
-			'[' if self.s.eat_if('|') => SyntaxKind::Shorthand,
-			'<' if self.s.eat_if("==>") => SyntaxKind::Shorthand,
-			'<' if self.s.eat_if("-->") => SyntaxKind::Shorthand,
-			'<' if self.s.eat_if("--") => SyntaxKind::Shorthand,
-			'<' if self.s.eat_if("-<") => SyntaxKind::Shorthand,
-			'<' if self.s.eat_if("->") => SyntaxKind::Shorthand,
-			'<' if self.s.eat_if("<-") => SyntaxKind::Shorthand,
-			'<' if self.s.eat_if("<<") => SyntaxKind::Shorthand,
-			'<' if self.s.eat_if("=>") => SyntaxKind::Shorthand,
-			'<' if self.s.eat_if("==") => SyntaxKind::Shorthand,
-			'<' if self.s.eat_if("~~") => SyntaxKind::Shorthand,
-			'<' if self.s.eat_if('=') => SyntaxKind::Shorthand,
-			'<' if self.s.eat_if('<') => SyntaxKind::Shorthand,
-			'<' if self.s.eat_if('-') => SyntaxKind::Shorthand,
-			'<' if self.s.eat_if('~') => SyntaxKind::Shorthand,
-			'>' if self.s.eat_if("->") => SyntaxKind::Shorthand,
-			'>' if self.s.eat_if(">>") => SyntaxKind::Shorthand,
-			'=' if self.s.eat_if("=>") => SyntaxKind::Shorthand,
-			'=' if self.s.eat_if('>') => SyntaxKind::Shorthand,
-			'=' if self.s.eat_if(':') => SyntaxKind::Shorthand,
-			'>' if self.s.eat_if('=') => SyntaxKind::Shorthand,
-			'>' if self.s.eat_if('>') => SyntaxKind::Shorthand,
-			'|' if self.s.eat_if("->") => SyntaxKind::Shorthand,
-			'|' if self.s.eat_if("=>") => SyntaxKind::Shorthand,
-			'|' if self.s.eat_if(']') => SyntaxKind::Shorthand,
-			'|' if self.s.eat_if('|') => SyntaxKind::Shorthand,
-			'~' if self.s.eat_if("~>") => SyntaxKind::Shorthand,
-			'~' if self.s.eat_if('>') => SyntaxKind::Shorthand,
-			'*' | '-' => SyntaxKind::Shorthand,
+			_ if self.shorthand(start, MATH_SHORTHANDS) => SyntaxKind::Shorthand,
 
 			'#' => SyntaxKind::Hash,
 			'_' => SyntaxKind::Underscore,
@@ -580,7 +650,7 @@ impl Lexer<'_> {
 	fn code(&mut self, start: usize, c: char) -> SyntaxKind {
 		match c {
 			'`' => self.raw(),
-			'<' if self.s.at(is_id_continue) => self.label(),
+			'<' if self.s.at(is_label_start) => self.label(),
 			'0'..='9' => self.number(start, c),
 			'.' if self.s.at(char::is_ascii_digit) => self.number(start, c),
 			'"' => self.string(),
@@ -656,27 +726,36 @@ impl Lexer<'_> {
 		}
 
 		// Read the first part (integer or fractional depending on `first`).
-		self.s.eat_while(if base == 16 {
-			char::is_ascii_alphanumeric
-		} else {
-			char::is_ascii_digit
+		// Underscores may separate digits in all parts of the number.
+		self.s.eat_while(|c: char| {
+			c == '_'
+				|| if base == 16 { c.is_ascii_alphanumeric() } else { c.is_ascii_digit() }
 		});
 
 		// Read the fractional part if not already done.
 		// Make sure not to confuse a range for the decimal separator.
+		let mut fractional = c == '.';
 		if c != '.'
 			&& !self.s.at("..")
 			&& !self.s.scout(1).is_some_and(is_id_start)
 			&& self.s.eat_if('.')
-			&& base == 10
 		{
-			self.s.eat_while(char::is_ascii_digit);
+			self.s.eat_while(is_digit_or_separator);
+			fractional = true;
 		}
 
-		// Read the exponent.
-		if !self.s.at("em") && self.s.eat_if(['e', 'E']) && base == 10 {
-			self.s.eat_if(['+', '-']);
-			self.s.eat_while(char::is_ascii_digit);
+		// Read the exponent. It must contain at least one digit, so that it
+		// isn't confused with a suffix like `em`. In hexadecimal numbers, an
+		// `e` is already consumed as a digit.
+		let mut exponent = false;
+		let mut s = self.s;
+		if s.eat_if(['e', 'E']) {
+			s.eat_if(['+', '-']);
+			if s.at(char::is_ascii_digit) {
+				s.eat_while(is_digit_or_separator);
+				self.s = s;
+				exponent = true;
+			}
 		}
 
 		// Read the suffix.
@@ -688,9 +767,29 @@ impl Lexer<'_> {
 		let number = self.s.get(start..suffix_start);
 		let suffix = self.s.from(suffix_start);
 
-		let kind = if i64::from_str_radix(number, base).is_ok() {
+		if !has_valid_separators(number, base) {
+			return self.error("digit separators must be placed between digits");
+		}
+
+		let digits = number.replace('_', "");
+
+		if base != 10 {
+			let name = match base {
+				2 => "binary",
+				8 => "octal",
+				_ => "hexadecimal",
+			};
+
+			if fractional {
+				return self.error(eco_format!("{name} numbers cannot be fractional"));
+			} else if exponent {
+				return self.error(eco_format!("{name} numbers cannot have an exponent"));
+			}
+		}
+
+		let kind = if i64::from_str_radix(&digits, base).is_ok() {
 			SyntaxKind::Int
-		} else if base == 10 && number.parse::<f64>().is_ok() {
+		} else if base == 10 && digits.parse::<f64>().is_ok() {
 			SyntaxKind::Float
 		} else {
 			return self.error(match base {
@@ -712,6 +811,10 @@ impl Lexer<'_> {
 			return self.error(eco_format!("invalid number suffix: {}", suffix));
 		}
 
+		if base != 10 {
+			return self.error("numbers with a unit must be decimal");
+		}
+
 		SyntaxKind::Numeric
 	}
 
@@ -731,6 +834,21 @@ impl Lexer<'_> {
 	}
 }
 
+/// Whether a character is a decimal digit or a digit separator.
+fn is_digit_or_separator(c: char) -> bool {
+	c.is_ascii_digit() || c == '_'
+}
+
+/// Whether all digit separators in a number are placed between two digits of
+/// the given base.
+fn has_valid_separators(number: &str, base: u32) -> bool {
+	let is_digit = |c: Option<char>| c.is_some_and(|c| c.is_digit(base));
+	number.char_indices().filter(|&(_, c)| c == '_').all(|(i, _)| {
+		is_digit(number[..i].chars().next_back())
+			&& is_digit(number[i + 1..].chars().next())
+	})
+}
+
 /// Try to parse an identifier into a keyword.
 fn keyword(ident: &str) -> Option<SyntaxKind> {
 	Some(match ident {
@@ -779,6 +897,63 @@ impl ScannerExt for Scanner<'_> {
 	}
 }
 
+/// The shorthands in markup mode and the characters they stand for.
+///
+/// Longer shorthands come before their prefixes, so the first matching entry
+/// is the longest one. A minus sign directly before a digit is a shorthand
+/// for `−`, too, but is not listed since it depends on what follows it.
+pub const MARKUP_SHORTHANDS: &[(&str, char)] = &[
+	("---", '\u{2014}'),
+	("...", '…'),
+	("--", '\u{2013}'),
+	("-?", '\u{00AD}'),
+	("~", '\u{00A0}'),
+];
+
+/// The shorthands in math mode and the characters they stand for.
+///
+/// Longer shorthands come before their prefixes, so the first matching entry
+/// is the longest one.
+pub const MATH_SHORTHANDS: &[(&str, char)] = &[
+	("<==>", '⟺'),
+	("<-->", '⟷'),
+	("...", '…'),
+	("::=", '⩴'),
+	("<<<", '⋘'),
+	(">>>", '⋙'),
+	("-->", '⟶'),
+	("|->", '↦'),
+	(">->", '↣'),
+	("->>", '↠'),
+	("<--", '⟵'),
+	("<-<", '↢'),
+	("<<-", '↞'),
+	("<->", '↔'),
+	("~~>", '⟿'),
+	("<~~", '⬳'),
+	("|=>", '⤇'),
+	("==>", '⟹'),
+	("<==", '⟸'),
+	("<=>", '⇔'),
+	("!=", '≠'),
+	(":=", '≔'),
+	("=:", '≕'),
+	("<<", '≪'),
+	(">>", '≫'),
+	("<=", '≤'),
+	(">=", '≥'),
+	("->", '→'),
+	("<-", '←'),
+	("~>", '⇝'),
+	("<~", '⇜'),
+	("=>", '⇒'),
+	("[|", '⟦'),
+	("|]", '⟧'),
+	("||", '‖'),
+	("*", '∗'),
+	("-", '\u{2212}'),
+];
+
 /// Whether a character will become a [`SyntaxKind::Space`] token.
 #[inline]
 fn is_space(character: char, mode: LexMode) -> bool {
@@ -788,6 +963,19 @@ fn is_space(character: char, mode: LexMode) -> bool {
 	}
 }
 
+/// Whether a character is part of a word, in which markup markers are treated
+/// as text.
+#[inline]
+fn is_wordy(character: Option<char>) -> bool {
+	character.is_some_and(|c| {
+		c.is_alphanumeric()
+			&& !matches!(
+				c.script(),
+				Script::Han | Script::Hiragana | Script::Katakana | Script::Hangul
+			)
+	})
+}
+
 /// Whether a character is interpreted as a newline by Typst.
 #[inline]
 pub fn is_newline(character: char) -> bool {
@@ -837,42 +1025,286 @@ pub fn link_prefix(text: &str) -> (&str, bool) {
 	(s.before(), brackets.is_empty())
 }
 
+/// Parse an automatic link at the start of the text into its parts.
+///
+/// Returns `None` if the text doesn't start with a link that the lexer would
+/// accept in markup, i.e. one starting with `http://` or `https://` and
+/// with balanced parentheses and brackets. The link ends like with
+/// [`link_prefix`].
+pub fn parse_link_prefix(text: &str) -> Option<LinkParts> {
+	split_link(text).and_then(|(parts, balanced)| balanced.then_some(parts))
+}
+
+/// Split an automatic link at the start of the text into its parts and
+/// determine whether its parentheses and brackets are balanced.
+fn split_link(text: &str) -> Option<(LinkParts, bool)> {
+	let scheme = ["http", "https"].into_iter().find(|scheme| {
+		text.strip_prefix(scheme).is_some_and(|rest| rest.starts_with("://"))
+	})?;
+
+	let start = scheme.len() + "://".len();
+	let (link, balanced) = link_prefix(&text[start..]);
+	let end = start + link.len();
+	let path = start + link.find(['/', '?', '#']).unwrap_or(link.len());
+	let parts = LinkParts {
+		scheme: 0..scheme.len(),
+		authority: start..path,
+		path: path..end,
+	};
+	Some((parts, balanced))
+}
+
+/// The parts of an automatic link, see [`parse_link_prefix`].
+///
+/// The parts are byte ranges into the parsed text. The link itself ends
+/// where the path ends.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct LinkParts {
+	/// The scheme without the following `://`, e.g. `https`.
+	pub scheme: Range<usize>,
+	/// The authority, e.g. `typst.app` or `localhost:8080`. May be empty.
+	pub authority: Range<usize>,
+	/// The path, query, and fragment, e.g. `/docs?q=1#top`. May be empty.
+	pub path: Range<usize>,
+}
+
 /// Split text at newlines. These newline characters are not kept.
+///
+/// This collects [`lines`], which should be preferred if the lines are only
+/// iterated once.
 pub fn split_newlines(text: &str) -> Vec<&str> {
-	let mut s = Scanner::new(text);
-	let mut lines = Vec::new();
-	let mut start = 0;
-	let mut end = 0;
-
-	while let Some(c) = s.eat() {
-		if is_newline(c) {
-			if c == '\r' {
-				s.eat_if('\n');
-			}
+	lines(text).collect()
+}
+
+/// Split text at newlines, returning the byte range of each line and the
+/// newline that terminates it.
+///
+/// This collects [`Lines::spanned`]. The ranges don't include the
+/// newlines. The last line is always terminated by [`NewlineKind::None`],
+/// so the lines and their terminators concatenate to the original text.
+pub fn split_newlines_with_spans(text: &str) -> Vec<(Range<usize>, NewlineKind)> {
+	lines(text).spanned().collect()
+}
+
+/// Iterate over the lines of a text.
+///
+/// Lines are split like with [`split_newlines`], i.e. at any [newline
+/// character](is_newline), where `\r\n` counts as a single newline. There
+/// is always at least one line, and a trailing newline is followed by an
+/// empty line.
+pub fn lines(text: &str) -> Lines<'_> {
+	Lines {
+		inner: SpannedLines {
+			text,
+			front: 0,
+			back: text.len(),
+			back_kind: NewlineKind::None,
+			done: false,
+		},
+	}
+}
+
+/// An iterator over the lines of a text, see [`lines`].
+#[derive(Debug, Clone)]
+pub struct Lines<'a> {
+	inner: SpannedLines<'a>,
+}
+
+impl<'a> Lines<'a> {
+	/// Yield the byte range of each line and the newline that terminates
+	/// it instead of the line's text.
+	pub fn spanned(self) -> SpannedLines<'a> {
+		self.inner
+	}
+}
+
+impl<'a> Iterator for Lines<'a> {
+	type Item = &'a str;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let (range, _) = self.inner.next()?;
+		Some(&self.inner.text[range])
+	}
+}
 
-			lines.push(&text[start..end]);
-			start = s.cursor();
+impl DoubleEndedIterator for Lines<'_> {
+	fn next_back(&mut self) -> Option<Self::Item> {
+		let (range, _) = self.inner.next_back()?;
+		Some(&self.inner.text[range])
+	}
+}
+
+/// An iterator over the byte ranges of the lines of a text and the newlines
+/// that terminate them, see [`Lines::spanned`].
+#[derive(Debug, Clone)]
+pub struct SpannedLines<'a> {
+	/// The full text.
+	text: &'a str,
+	/// Where the next line from the front starts.
+	front: usize,
+	/// Where the next line from the back ends.
+	back: usize,
+	/// The newline that terminates the next line from the back.
+	back_kind: NewlineKind,
+	/// Whether the last remaining line was yielded.
+	done: bool,
+}
+
+impl SpannedLines<'_> {
+	/// Yield the remaining text as the last line.
+	fn finish(&mut self) -> (Range<usize>, NewlineKind) {
+		self.done = true;
+		(self.front..self.back, self.back_kind)
+	}
+}
+
+impl Iterator for SpannedLines<'_> {
+	type Item = (Range<usize>, NewlineKind);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.done {
+			return None;
+		}
+
+		let rest = &self.text[self.front..self.back];
+		let newline = |(i, c): (usize, char)| NewlineKind::of(c).map(|kind| (i, kind));
+		let Some((i, mut kind)) = rest.char_indices().find_map(newline) else {
+			return Some(self.finish());
+		};
+
+		if kind == NewlineKind::Cr && rest[i + 1..].starts_with('\n') {
+			kind = NewlineKind::CrLf;
 		}
-		end = s.cursor();
+
+		let start = self.front;
+		self.front += i + kind.len_utf8();
+		Some((start..start + i, kind))
 	}
+}
 
-	lines.push(&text[start..]);
-	lines
+impl DoubleEndedIterator for SpannedLines<'_> {
+	fn next_back(&mut self) -> Option<Self::Item> {
+		if self.done {
+			return None;
+		}
+
+		let rest = &self.text[self.front..self.back];
+		let newline = |(i, c): (usize, char)| NewlineKind::of(c).map(|kind| (i, kind));
+		let Some((mut i, mut kind)) = rest.char_indices().rev().find_map(newline) else {
+			return Some(self.finish());
+		};
+
+		let end = self.front + i + kind.len_utf8();
+		if kind == NewlineKind::Lf && rest[..i].ends_with('\r') {
+			kind = NewlineKind::CrLf;
+			i -= 1;
+		}
+
+		let line = (end..self.back, self.back_kind);
+		self.back = self.front + i;
+		self.back_kind = kind;
+		Some(line)
+	}
+}
+
+/// A newline that terminates a line, see [`Lines::spanned`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum NewlineKind {
+	/// A line feed, `\n`.
+	Lf,
+	/// A carriage return followed by a line feed, `\r\n`.
+	CrLf,
+	/// A carriage return, `\r`.
+	Cr,
+	/// A vertical tab, `\x0B`.
+	Vt,
+	/// A form feed, `\x0C`.
+	Ff,
+	/// A next line, `\u{85}`.
+	Nel,
+	/// A line separator, `\u{2028}`.
+	Ls,
+	/// A paragraph separator, `\u{2029}`.
+	Ps,
+	/// No newline, i.e. the end of the text.
+	None,
+}
+
+impl NewlineKind {
+	/// The kind of newline a character starts, if any.
+	///
+	/// For `\r`, this is always [`Cr`](Self::Cr), even if it starts a
+	/// `\r\n`.
+	pub fn of(c: char) -> Option<Self> {
+		Some(match c {
+			'\n' => Self::Lf,
+			'\r' => Self::Cr,
+			'\x0B' => Self::Vt,
+			'\x0C' => Self::Ff,
+			'\u{0085}' => Self::Nel,
+			'\u{2028}' => Self::Ls,
+			'\u{2029}' => Self::Ps,
+			_ => return None,
+		})
+	}
+
+	/// The text of the newline.
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Self::Lf => "\n",
+			Self::CrLf => "\r\n",
+			Self::Cr => "\r",
+			Self::Vt => "\x0B",
+			Self::Ff => "\x0C",
+			Self::Nel => "\u{0085}",
+			Self::Ls => "\u{2028}",
+			Self::Ps => "\u{2029}",
+			Self::None => "",
+		}
+	}
+
+	/// The length of the newline in bytes.
+	pub fn len_utf8(self) -> usize {
+		self.as_str().len()
+	}
+}
+
+/// Facts about the newlines in a text, gathered in a single pass.
+///
+/// The lexer uses this to decide between spaces and paragraph breaks, so
+/// tools that inspect whitespace can rely on the same interpretation of
+/// newlines.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct NewlineAnalysis {
+	/// The number of newlines, where `\r\n` counts as one.
+	pub count: usize,
+	/// The byte offset at which the last line starts, i.e. the end of the
+	/// last newline. Zero if there is no newline.
+	pub last_line_start: usize,
+	/// Whether any newline is a `\r\n`.
+	pub has_crlf: bool,
+	/// Whether any newline is neither `\n` nor `\r\n`, e.g. a lone `\r`
+	/// or a line separator.
+	pub has_exotic: bool,
 }
 
-/// Count the number of newlines in text.
-fn count_newlines(text: &str) -> usize {
-	let mut newlines = 0;
-	let mut s = Scanner::new(text);
-	while let Some(c) = s.eat() {
-		if is_newline(c) {
-			if c == '\r' {
-				s.eat_if('\n');
+impl NewlineAnalysis {
+	/// Analyze the newlines in a text.
+	pub fn of(text: &str) -> Self {
+		let mut analysis = Self::default();
+		let mut s = Scanner::new(text);
+		while let Some(c) = s.eat() {
+			let Some(kind) = NewlineKind::of(c) else { continue };
+			if kind == NewlineKind::Cr && s.eat_if('\n') {
+				analysis.has_crlf = true;
+			} else if kind != NewlineKind::Lf {
+				analysis.has_exotic = true;
 			}
-			newlines += 1;
+			analysis.count += 1;
+			analysis.last_line_start = s.cursor();
 		}
+		analysis
 	}
-	newlines
 }
 
 /// Whether a string is a valid Typst identifier.
@@ -890,6 +1322,70 @@ pub fn is_ident(string: &str) -> bool {
 		.is_some_and(|c| is_id_start(c) && chars.all(is_id_continue))
 }
 
+/// Whether a string can be written as a label literal, e.g. `<intro>`.
+///
+/// Labels can contain the characters of identifiers, `:`, and `.`, but must
+/// not start with one of the latter two. Namespaced labels like
+/// `<eq:euler:1>` are fine. Labels ending with `:` or `.` are valid, but can't
+/// be referenced with a reference literal, see [`is_valid_ref_target`].
+#[inline]
+pub fn is_valid_label(string: &str) -> bool {
+	string.starts_with(is_label_start) && string.chars().all(is_valid_in_label_literal)
+}
+
+/// Whether a string can be referenced with a reference literal, e.g.
+/// `@intro`.
+///
+/// This is like [`is_valid_label`], but the target must not end with `:` or
+/// `.`, as those are left to the surrounding text.
+#[inline]
+pub fn is_valid_ref_target(string: &str) -> bool {
+	!string.is_empty()
+		&& string.chars().all(is_valid_in_label_literal)
+		&& !string.ends_with(is_ref_trailer)
+}
+
+/// Trim the trailing `.` and `:` from a reference target, which are left to
+/// the surrounding text, e.g. in `See @intro.`.
+#[inline]
+pub fn trim_ref_target(string: &str) -> &str {
+	string.trim_end_matches(is_ref_trailer)
+}
+
+/// Turn arbitrary text, e.g. a heading's title, into an identifier.
+///
+/// The text is lowercased and each run of characters that are not allowed
+/// in identifiers is replaced with a single `-`. Leading characters that
+/// can't start an identifier and trailing separators are dropped. Returns
+/// `None` if nothing remains. Since identifiers are valid labels, the result
+/// can always be used as one.
+pub fn sanitize_ident(string: &str) -> Option<EcoString> {
+	let mut ident = EcoString::new();
+	let mut separated = false;
+	for c in string.chars().flat_map(char::to_lowercase) {
+		if c == '-' || !is_id_continue(c) {
+			separated = true;
+		} else if !ident.is_empty() {
+			if separated {
+				ident.push('-');
+			}
+			ident.push(c);
+			separated = false;
+		} else if is_id_start(c) {
+			ident.push(c);
+			separated = false;
+		}
+	}
+	(!ident.is_empty()).then_some(ident)
+}
+
+/// Whether a string is a keyword, which the lexer never turns into an
+/// identifier.
+#[inline]
+pub fn is_keyword(string: &str) -> bool {
+	keyword(string).is_some()
+}
+
 /// Whether a character can start an identifier.
 #[inline]
 pub fn is_id_start(c: char) -> bool {
@@ -902,6 +1398,25 @@ pub fn is_id_continue(c: char) -> bool {
 	is_xid_continue(c) || c == '_' || c == '-'
 }
 
+/// Whether a character can start a label literal.
+#[inline]
+fn is_label_start(c: char) -> bool {
+	is_id_continue(c)
+}
+
+/// Whether a character can appear in a label literal or reference.
+#[inline]
+fn is_valid_in_label_literal(c: char) -> bool {
+	is_id_continue(c) || matches!(c, ':' | '.')
+}
+
+/// Whether a character at the end of a reference is left to the surrounding
+/// text.
+#[inline]
+fn is_ref_trailer(c: char) -> bool {
+	matches!(c, ':' | '.')
+}
+
 /// Whether a character can start an identifier in math.
 #[inline]
 fn is_math_id_start(c: char) -> bool {
@@ -913,3 +1428,787 @@ fn is_math_id_start(c: char) -> bool {
 fn is_math_id_continue(c: char) -> bool {
 	is_xid_continue(c) && c != '_'
 }
+
+#[cfg(test)]
+mod tests {
+	use std::path::Path;
+
+	use super::*;
+	use crate::SyntaxNode;
+
+	/// All short combinations of characters that might trip up the
+	/// splitting.
+	fn texts() -> Vec<String> {
+		let pieces =
+			["a", "ä", " ", "\n", "\r", "\x0B", "\x0C", "\u{85}", "\u{2028}", "\u{2029}"];
+		let mut texts = vec![String::new()];
+		let mut last = texts.clone();
+		for _ in 0..4 {
+			last = last
+				.iter()
+				.flat_map(|text| pieces.iter().map(move |piece| format!("{text}{piece}")))
+				.collect();
+			texts.extend(last.iter().cloned());
+		}
+		texts
+	}
+
+	/// Split lines by scanning forward and collecting them, like
+	/// `split_newlines` did before it was based on [`lines`].
+	fn split_newlines_eagerly(text: &str) -> Vec<&str> {
+		let mut s = Scanner::new(text);
+		let mut lines = Vec::new();
+		let mut start = 0;
+		let mut end = 0;
+
+		while let Some(c) = s.eat() {
+			if is_newline(c) {
+				if c == '\r' {
+					s.eat_if('\n');
+				}
+
+				lines.push(&text[start..end]);
+				start = s.cursor();
+			}
+			end = s.cursor();
+		}
+
+		lines.push(&text[start..]);
+		lines
+	}
+
+	#[test]
+	fn test_is_valid_label() {
+		assert!(is_valid_label("intro"));
+		assert!(is_valid_label("fig:chart.2"));
+		assert!(is_valid_label("eq:euler:1"));
+		assert!(is_valid_label("1st"));
+		assert!(is_valid_label("überblick"));
+		assert!(!is_valid_label(""));
+		assert!(!is_valid_label(":intro"));
+		assert!(is_valid_label("intro."));
+		assert!(!is_valid_label("my intro"));
+		assert!(!is_valid_label("a>b"));
+	}
+
+	#[test]
+	fn test_is_valid_ref_target() {
+		assert!(is_valid_ref_target("intro"));
+		assert!(is_valid_ref_target("fig:chart.2"));
+		assert!(!is_valid_ref_target(""));
+		assert!(!is_valid_ref_target("intro."));
+		assert!(!is_valid_ref_target("intro:"));
+		assert!(is_valid_ref_target("2024"));
+		assert!(is_valid_ref_target("2019smith"));
+		assert!(!is_valid_ref_target("a b"));
+	}
+
+	#[test]
+	fn test_label_validity_matches_lexer() {
+		for text in [
+			"intro",
+			"fig:chart.2",
+			"eq:euler:1",
+			"a.b:",
+			"intro.",
+			"1st",
+			"2024",
+			"_x",
+			"a b",
+			":a",
+			"ä-ö",
+		] {
+			let mut lexer = Lexer::new(&format!("<{text}>"), LexMode::Markup);
+			let is_label = lexer.next() == SyntaxKind::Label;
+			assert_eq!(is_label, is_valid_label(text), "{text}");
+
+			let source = format!("@{text}");
+			let mut lexer = Lexer::new(&source, LexMode::Markup);
+			let is_ref =
+				lexer.next() == SyntaxKind::RefMarker && lexer.cursor() == source.len();
+			assert_eq!(is_ref, is_valid_ref_target(text), "{text}");
+		}
+	}
+
+	#[test]
+	fn test_trim_ref_target() {
+		assert_eq!(trim_ref_target("intro"), "intro");
+		assert_eq!(trim_ref_target("intro."), "intro");
+		assert_eq!(trim_ref_target("eq:euler:1.:"), "eq:euler:1");
+		assert_eq!(trim_ref_target("fig.1"), "fig.1");
+	}
+
+	#[test]
+	fn test_refs_at_sentence_end() {
+		use SyntaxKind::{Colon, RefMarker, Space, Text};
+		test_markup("@intro.", &[(RefMarker, "@intro"), (Text, ".")]);
+		test_markup("@intro:", &[(RefMarker, "@intro"), (Colon, ":")]);
+		test_markup("@fig.1", &[(RefMarker, "@fig.1")]);
+		test_markup(
+			"See @eq:euler:1.",
+			&[(Text, "See"), (Space, " "), (RefMarker, "@eq:euler:1"), (Text, ".")],
+		);
+	}
+
+	#[test]
+	fn test_refs_starting_with_digit() {
+		use SyntaxKind::{RefMarker, Space, Text};
+		test_markup("@2024", &[(RefMarker, "@2024")]);
+		test_markup("@1st.", &[(RefMarker, "@1st"), (Text, ".")]);
+		test_markup(
+			"In @2019smith @a2",
+			&[
+				(Text, "In"),
+				(Space, " "),
+				(RefMarker, "@2019smith"),
+				(Space, " "),
+				(RefMarker, "@a2"),
+			],
+		);
+	}
+
+	#[test]
+	fn test_label_trailers() {
+		for mode in [LexMode::Markup, LexMode::Code] {
+			assert_eq!(
+				lex("<eq:euler:1>", mode),
+				[(SyntaxKind::Label, "<eq:euler:1>", None)]
+			);
+			assert_eq!(lex("<2024>", mode), [(SyntaxKind::Label, "<2024>", None)]);
+			// Trailing dots and colons are only warned about during evaluation.
+			assert_eq!(
+				lex("<sec:intro.>", mode),
+				[(SyntaxKind::Label, "<sec:intro.>", None)]
+			);
+		}
+	}
+
+	#[test]
+	fn test_sanitize_ident() {
+		let test = |text, ident: Option<&str>| {
+			assert_eq!(sanitize_ident(text).as_deref(), ident, "{text}");
+		};
+		test("Introduction", Some("introduction"));
+		test("Über Größe", Some("über-größe"));
+		test("Ελληνικά κείμενα", Some("ελληνικά-κείμενα"));
+		test("你好，世界", Some("你好-世界"));
+		test("1.2 What's new?!", Some("what-s-new"));
+		test("--- (a) & [b] ---", Some("a-b"));
+		test("C++ / Rust: a comparison", Some("c-rust-a-comparison"));
+		test("_private_Name", Some("_private_name"));
+		test("🎉 Party 🎉", Some("party"));
+		test("2024", None);
+		test("?!...", None);
+		test("", None);
+	}
+
+	#[test]
+	fn test_sanitize_ident_keeps_sanitized() {
+		for ident in ["intro", "a-b-c", "x1", "über_alles", "_", "你好-世界"] {
+			assert_eq!(sanitize_ident(ident).as_deref(), Some(ident));
+			assert!(is_ident(ident) && is_valid_label(ident));
+		}
+	}
+
+	#[test]
+	fn test_parse_link_prefix() {
+		let test = |text: &str, parts: Option<[&str; 3]>| {
+			let found = parse_link_prefix(text).map(|parts| {
+				[&text[parts.scheme], &text[parts.authority], &text[parts.path]]
+			});
+			assert_eq!(found, parts, "{text}");
+		};
+		test("https://typst.app", Some(["https", "typst.app", ""]));
+		test("http://a.b/c?d=1#e.", Some(["http", "a.b", "/c?d=1#e"]));
+		test("https://[::1]:8080/(x) rest", Some(["https", "[::1]:8080", "/(x)"]));
+		test("https://a.b?q#f", Some(["https", "a.b", "?q#f"]));
+		test("http://", Some(["http", "", ""]));
+		test("https://a.b/(c", None);
+		test("ftp://a.b", None);
+		test("mailto:hello@typst.app", None);
+	}
+
+	#[test]
+	fn test_parse_link_prefix_matches_lexer_on_corpus() {
+		fn visit(path: &Path, links: &mut Vec<String>) {
+			if path.is_dir() {
+				for entry in std::fs::read_dir(path).unwrap() {
+					visit(&entry.unwrap().path(), links);
+				}
+			} else if path.extension().is_some_and(|ext| ext == "typ") {
+				let text = std::fs::read_to_string(path).unwrap();
+				collect(&crate::parse(&text), links);
+			}
+		}
+
+		fn collect(node: &SyntaxNode, links: &mut Vec<String>) {
+			if node.kind() == SyntaxKind::Link {
+				links.push(node.text().to_string());
+			}
+			for child in node.children() {
+				collect(child, links);
+			}
+		}
+
+		let suite = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../tests/suite");
+		let mut links = vec![];
+		visit(&suite, &mut links);
+		assert!(!links.is_empty());
+
+		for link in &links {
+			let mut lexer = Lexer::new(link, LexMode::Markup);
+			assert_eq!(lexer.next(), SyntaxKind::Link, "{link}");
+			assert_eq!(lexer.cursor(), link.len(), "{link}");
+
+			let parts = parse_link_prefix(link).expect(link);
+			assert_eq!(parts.path.end, link.len(), "{link}");
+			let rebuilt = format!(
+				"{}://{}{}",
+				&link[parts.scheme], &link[parts.authority], &link[parts.path]
+			);
+			assert_eq!(&rebuilt, link);
+		}
+	}
+
+	#[test]
+	fn test_newline_analysis() {
+		let test = |text, count, last_line_start, has_crlf, has_exotic| {
+			let expected =
+				NewlineAnalysis { count, last_line_start, has_crlf, has_exotic };
+			assert_eq!(NewlineAnalysis::of(text), expected, "{text:?}");
+		};
+		test("", 0, 0, false, false);
+		test("  \t", 0, 0, false, false);
+		test(" \n  \n ", 2, 5, false, false);
+		test("a\r\n", 1, 3, true, false);
+		test("\r\n\r\n", 2, 4, true, false);
+		test("a\r", 1, 2, false, true);
+		test("\r \n", 2, 3, false, true);
+		test("\n\u{2028}", 2, 4, false, true);
+		test("\u{2029} ", 1, 3, false, true);
+		test("\x0B\x0C\u{85}", 3, 4, false, true);
+	}
+
+	#[test]
+	fn test_newline_analysis_matches_lines() {
+		for text in &texts() {
+			let spans = split_newlines_with_spans(text);
+			let analysis = NewlineAnalysis::of(text);
+			assert_eq!(analysis.count, spans.len() - 1);
+			assert_eq!(analysis.last_line_start, spans.last().unwrap().0.start);
+			let kinds = || spans.iter().map(|(_, kind)| *kind);
+			assert_eq!(analysis.has_crlf, kinds().any(|kind| kind == NewlineKind::CrLf));
+			assert_eq!(
+				analysis.has_exotic,
+				kinds().any(|kind| !matches!(
+					kind,
+					NewlineKind::Lf | NewlineKind::CrLf | NewlineKind::None
+				)),
+			);
+		}
+	}
+
+	#[test]
+	fn test_lines() {
+		assert_eq!(lines("").collect::<Vec<_>>(), [""]);
+		assert_eq!(lines("a\r\n\rb\n").collect::<Vec<_>>(), ["a", "", "b", ""]);
+		assert_eq!(lines("a\r\n\rb\n").rev().collect::<Vec<_>>(), ["", "b", "", "a"]);
+	}
+
+	#[test]
+	fn test_lines_match_eager_split() {
+		for text in &texts() {
+			let expected = split_newlines_eagerly(text);
+			assert_eq!(lines(text).collect::<Vec<_>>(), expected);
+
+			let mut reversed: Vec<_> = lines(text).rev().collect();
+			reversed.reverse();
+			assert_eq!(reversed, expected);
+
+			// Take some lines from the front and the rest from the back.
+			for k in 0..=expected.len() {
+				let mut iter = lines(text);
+				let mut mixed: Vec<_> = iter.by_ref().take(k).collect();
+				let mut back: Vec<_> = iter.rev().collect();
+				back.reverse();
+				mixed.extend(back);
+				assert_eq!(mixed, expected);
+			}
+		}
+	}
+
+	#[test]
+	fn test_split_newlines_with_spans() {
+		use NewlineKind as Kind;
+		assert_eq!(split_newlines_with_spans(""), [(0..0, Kind::None)]);
+		assert_eq!(
+			split_newlines_with_spans("a\r\nb\r"),
+			[(0..1, Kind::CrLf), (3..4, Kind::Cr), (5..5, Kind::None)],
+		);
+		assert_eq!(
+			split_newlines_with_spans("\n\u{2028}ä"),
+			[(0..0, Kind::Lf), (1..1, Kind::Ls), (4..6, Kind::None)],
+		);
+		assert_eq!(
+			lines("a\r\nb\r").spanned().rev().collect::<Vec<_>>(),
+			[(5..5, Kind::None), (3..4, Kind::Cr), (0..1, Kind::CrLf)],
+		);
+	}
+
+	#[test]
+	fn test_split_newlines_with_spans_reconstructs() {
+		for text in &texts() {
+			let lines = split_newlines_with_spans(text);
+			let mut rebuilt = String::new();
+			for (range, kind) in &lines {
+				assert_eq!(range.start, rebuilt.len());
+				rebuilt.push_str(&text[range.clone()]);
+				rebuilt.push_str(kind.as_str());
+			}
+			assert_eq!(&rebuilt, text);
+			assert_eq!(lines.last().map(|(_, kind)| *kind), Some(NewlineKind::None));
+
+			let plain: Vec<_> =
+				lines.iter().map(|(range, _)| &text[range.clone()]).collect();
+			assert_eq!(plain, split_newlines_eagerly(text));
+		}
+	}
+
+	/// Lex code into tokens and the errors attached to them.
+	fn lex_code(text: &str) -> Vec<(SyntaxKind, &str, Option<EcoString>)> {
+		lex(text, LexMode::Code)
+	}
+
+	/// Lex text in the given mode into tokens and the errors attached to them.
+	fn lex(text: &str, mode: LexMode) -> Vec<(SyntaxKind, &str, Option<EcoString>)> {
+		let mut lexer = Lexer::new(text, mode);
+		let mut tokens = vec![];
+		loop {
+			let start = lexer.cursor();
+			let kind = lexer.next();
+			if kind == SyntaxKind::End {
+				break;
+			}
+			tokens.push((kind, &text[start..lexer.cursor()], lexer.take_error()));
+		}
+		tokens
+	}
+
+	#[track_caller]
+	fn test_number(text: &str, kind: SyntaxKind) {
+		assert_eq!(lex_code(text), [(kind, text, None)], "{text}");
+	}
+
+	#[track_caller]
+	fn test_number_error(text: &str, message: &str) {
+		let expected = [(SyntaxKind::Error, text, Some(message.into()))];
+		assert_eq!(lex_code(text), expected, "{text}");
+	}
+
+	#[test]
+	fn test_number_decimal() {
+		let suffixes = ["pt", "mm", "cm", "in", "deg", "rad", "em", "fr", "%"];
+		let ints = ["0", "1", "12"];
+		let floats = ["1.5", ".5", "1e3", "1E3", "1e+3", "1e-3", "1.5e-3", ".5e3"];
+
+		for (numbers, kind) in
+			[(&ints[..], SyntaxKind::Int), (&floats, SyntaxKind::Float)]
+		{
+			for number in numbers {
+				test_number(number, kind);
+				for suffix in suffixes {
+					test_number(&format!("{number}{suffix}"), SyntaxKind::Numeric);
+				}
+			}
+		}
+
+		test_number_error("1px", "invalid number suffix: px");
+		test_number_error("1e", "invalid number suffix: e");
+	}
+
+	#[test]
+	fn test_number_bases() {
+		for (prefix, digits, name) in
+			[("0b", "101", "binary"), ("0o", "17", "octal"), ("0x", "ff", "hexadecimal")]
+		{
+			let number = format!("{prefix}{digits}");
+			test_number(&number, SyntaxKind::Int);
+			test_number_error(
+				&format!("{number}.1"),
+				&format!("{name} numbers cannot be fractional"),
+			);
+		}
+
+		test_number_error("0b1e1", "binary numbers cannot have an exponent");
+		test_number_error("0o1e-1", "octal numbers cannot have an exponent");
+		test_number("0x1e1", SyntaxKind::Int);
+
+		test_number_error("0b101pt", "numbers with a unit must be decimal");
+		test_number_error("0o17%", "numbers with a unit must be decimal");
+		test_number_error("0xffpt", "invalid hexadecimal number: 0xffpt");
+		test_number_error("0x1p4", "invalid hexadecimal number: 0x1p4");
+		test_number_error("0b102", "invalid binary number: 0b102");
+	}
+
+	#[test]
+	fn test_number_ranges() {
+		use SyntaxKind::{Dot, Dots, Float, Ident, Int};
+		let kinds =
+			|text| lex_code(text).into_iter().map(|(kind, ..)| kind).collect::<Vec<_>>();
+		assert_eq!(kinds("0..10"), [Int, Dots, Int]);
+		assert_eq!(kinds("1.5..2"), [Float, Dots, Int]);
+		assert_eq!(kinds("1e2..3"), [Float, Dots, Int]);
+		assert_eq!(kinds("0x1..0b1"), [Int, Dots, Int]);
+		assert_eq!(kinds("1.abs"), [Int, Dot, Ident]);
+	}
+
+	#[test]
+	fn test_number_separators() {
+		test_number("1_000_000", SyntaxKind::Int);
+		test_number("0xFF_FF", SyntaxKind::Int);
+		test_number("0o7_7", SyntaxKind::Int);
+		test_number("0b1010_0001", SyntaxKind::Int);
+		test_number("3.141_592", SyntaxKind::Float);
+		test_number("1_0e1_0", SyntaxKind::Float);
+		test_number("1_5pt", SyntaxKind::Numeric);
+		test_number("1.2_5%", SyntaxKind::Numeric);
+
+		let message = "digit separators must be placed between digits";
+		for text in
+			["100_", "1__0", "0x_FF", "0b_1", "0o1_", "10_pt", "1_.5", "1_e5", "1.5_"]
+		{
+			test_number_error(text, message);
+		}
+
+		// A leading underscore starts an identifier.
+		assert_eq!(lex_code("_100"), [(SyntaxKind::Ident, "_100", None)]);
+	}
+
+	#[test]
+	fn test_number_separators_in_ranges() {
+		use SyntaxKind::{Dots, Int};
+		let kinds =
+			|text| lex_code(text).into_iter().map(|(kind, ..)| kind).collect::<Vec<_>>();
+		assert_eq!(kinds("1_0..2_0"), [Int, Dots, Int]);
+		assert_eq!(kinds("0x1_0..0b1_0"), [Int, Dots, Int]);
+	}
+
+	/// Lex raw text in markup into tokens and the errors attached to them.
+	fn lex_raw(text: &str) -> Vec<(SyntaxKind, &str, Option<EcoString>)> {
+		let mut lexer = Lexer::new(text, LexMode::Markup);
+		assert_eq!(lexer.next(), SyntaxKind::RawDelim, "{text}");
+		let mut tokens =
+			vec![(SyntaxKind::RawDelim, &text[..lexer.cursor()], lexer.take_error())];
+		lexer.set_mode(LexMode::Raw);
+		loop {
+			let start = lexer.cursor();
+			let kind = lexer.next();
+			if kind == SyntaxKind::End {
+				break;
+			}
+			tokens.push((kind, &text[start..lexer.cursor()], lexer.take_error()));
+		}
+		assert_eq!(lexer.cursor(), text.len(), "{text}");
+		tokens
+	}
+
+	#[test]
+	fn test_raw_closed() {
+		use SyntaxKind::{RawDelim, RawLang, RawTrimmed, Text};
+		assert_eq!(
+			lex_raw("`a b`"),
+			[(RawDelim, "`", None), (Text, "a b", None), (RawDelim, "`", None)]
+		);
+		assert_eq!(
+			lex_raw("```rust\nfn\n```"),
+			[
+				(RawDelim, "```", None),
+				(RawLang, "rust", None),
+				(RawTrimmed, "\n", None),
+				(Text, "fn", None),
+				(RawTrimmed, "\n", None),
+				(RawDelim, "```", None),
+			]
+		);
+	}
+
+	#[test]
+	fn test_raw_unclosed() {
+		use SyntaxKind::{RawDelim, RawLang, RawTrimmed, Text};
+		let error = || Some(EcoString::from("unclosed raw text"));
+		assert_eq!(
+			lex_raw("`endless"),
+			[(RawDelim, "`", error()), (Text, "endless", None)]
+		);
+		assert_eq!(
+			lex_raw("`a\nb"),
+			[
+				(RawDelim, "`", error()),
+				(Text, "a", None),
+				(RawTrimmed, "\n", None),
+				(Text, "b", None),
+			]
+		);
+		assert_eq!(
+			lex_raw("```rust\nfn main() {}\n"),
+			[
+				(RawDelim, "```", error()),
+				(RawLang, "rust", None),
+				(RawTrimmed, "\n", None),
+				(Text, "fn main() {}", None),
+				(RawTrimmed, "\n", None),
+			]
+		);
+		assert_eq!(
+			lex_raw("```rust\nfn\n``"),
+			[
+				(RawDelim, "```", error()),
+				(RawLang, "rust", None),
+				(RawTrimmed, "\n", None),
+				(Text, "fn", None),
+				(RawTrimmed, "\n", None),
+				(Text, "``", None),
+			]
+		);
+	}
+
+	fn tokens(
+		text: &str,
+		mode: TokenizerMode,
+	) -> Vec<(SyntaxKind, &str, Option<EcoString>)> {
+		tokenize(text, mode)
+			.map(|token| (token.kind, &text[token.range], token.error))
+			.collect()
+	}
+
+	#[test]
+	fn test_tokenize_round_trip() {
+		let markup = [
+			"",
+			"Hello *world*!",
+			"= Heading\n\n- List\n+ Enum\n/ Term: Desc",
+			"#let x = 1 <label> @ref https://typst.org",
+			"`inline` and ``` and `` ",
+			"```rust\n    fn main() {\n        x\n    }\n    ```",
+			"```\n  a\n\n  b\n  ``` after",
+			"```typ   \n\tindented\r\n```",
+			"`unclosed\nraw",
+			"```\nendless",
+			"/* comment */ // line\n//! doc\r\nend",
+			"\\u{1F600} \\u{xyz} \\",
+		];
+		let code = [
+			"let f(x, ..y) = x + 1.5em",
+			"if a != b { \"str\\n\" } else [`raw` *c*]",
+			"(a: 1, b: none) => <sec:intro>",
+			"12e 0b102 \"unclosed",
+		];
+		let math = ["x^2 + y_1 = sqrt(z)", "a &= b \\ f'(x) #y \"text\"", "$ -> != ..."];
+
+		for (mode, corpus) in [
+			(TokenizerMode::Markup, &markup[..]),
+			(TokenizerMode::Code, &code[..]),
+			(TokenizerMode::Math, &math[..]),
+		] {
+			for text in corpus {
+				let mut end = 0;
+				for token in tokenize(text, mode) {
+					assert_eq!(token.range.start, end, "{text:?}");
+					end = token.range.end;
+				}
+				assert_eq!(end, text.len(), "{text:?}");
+			}
+		}
+	}
+
+	#[test]
+	fn test_tokenize_raw() {
+		use SyntaxKind::{RawDelim, RawLang, RawTrimmed, Space, Text};
+		assert_eq!(
+			tokens("a ```rs\n  fn\n    x\n  ``` b", TokenizerMode::Markup),
+			[
+				(Text, "a", None),
+				(Space, " ", None),
+				(RawDelim, "```", None),
+				(RawLang, "rs", None),
+				(RawTrimmed, "\n  ", None),
+				(Text, "fn", None),
+				(RawTrimmed, "\n  ", None),
+				(Text, "  x", None),
+				(RawTrimmed, "\n  ", None),
+				(RawDelim, "```", None),
+				(Space, " ", None),
+				(Text, "b", None),
+			]
+		);
+		assert_eq!(
+			tokens("``x", TokenizerMode::Markup),
+			[(RawDelim, "`", None), (RawDelim, "`", None), (Text, "x", None)]
+		);
+		assert_eq!(
+			tokens("{`a`}", TokenizerMode::Code),
+			[
+				(SyntaxKind::LeftBrace, "{", None),
+				(RawDelim, "`", None),
+				(Text, "a", None),
+				(RawDelim, "`", None),
+				(SyntaxKind::RightBrace, "}", None),
+			]
+		);
+	}
+
+	#[test]
+	fn test_tokenize_errors() {
+		use SyntaxKind::{Error, RawDelim, Space, Text};
+		assert_eq!(
+			tokens("`a\nb", TokenizerMode::Markup),
+			[
+				(RawDelim, "`", Some("unclosed raw text".into())),
+				(Text, "a", None),
+				(SyntaxKind::RawTrimmed, "\n", None),
+				(Text, "b", None),
+			]
+		);
+		assert_eq!(
+			tokens("*/ ab", TokenizerMode::Math),
+			[
+				(Error, "*/", Some("unexpected end of block comment".into())),
+				(Space, " ", None),
+				(SyntaxKind::MathIdent, "ab", None),
+			]
+		);
+	}
+
+	#[track_caller]
+	fn test_markup(text: &str, expected: &[(SyntaxKind, &str)]) {
+		let tokens: Vec<_> = lex(text, LexMode::Markup)
+			.into_iter()
+			.map(|(kind, s, _)| (kind, s))
+			.collect();
+		assert_eq!(tokens, expected, "{text}");
+	}
+
+	#[test]
+	fn test_highlight_markers() {
+		use SyntaxKind::{EqEq, Space, Star, Text, Underscore};
+		test_markup("==a==", &[(EqEq, "=="), (Text, "a"), (EqEq, "==")]);
+		test_markup(
+			"x ==y== z",
+			&[
+				(Text, "x"),
+				(Space, " "),
+				(EqEq, "=="),
+				(Text, "y"),
+				(EqEq, "=="),
+				(Space, " "),
+				(Text, "z"),
+			],
+		);
+		test_markup(
+			"*==a==*_",
+			&[
+				(Star, "*"),
+				(EqEq, "=="),
+				(Text, "a"),
+				(EqEq, "=="),
+				(Star, "*"),
+				(Underscore, "_"),
+			],
+		);
+	}
+
+	#[test]
+	fn test_highlight_markers_as_heading() {
+		use SyntaxKind::{EqEq, HeadingMarker, Space, Text};
+		test_markup("== a", &[(HeadingMarker, "=="), (Space, " "), (Text, "a")]);
+		test_markup("==", &[(HeadingMarker, "==")]);
+		test_markup(
+			"a\n== b",
+			&[
+				(Text, "a"),
+				(Space, "\n"),
+				(HeadingMarker, "=="),
+				(Space, " "),
+				(Text, "b"),
+			],
+		);
+		test_markup(
+			"a == b",
+			&[
+				(Text, "a"),
+				(Space, " "),
+				(HeadingMarker, "=="),
+				(Space, " "),
+				(Text, "b"),
+			],
+		);
+		test_markup("==a", &[(EqEq, "=="), (Text, "a")]);
+	}
+
+	#[test]
+	fn test_highlight_markers_before_arrow() {
+		for text in ["a ==> b", "a==>b", "==>"] {
+			let tokens = lex(text, LexMode::Markup);
+			assert!(
+				tokens.iter().all(|(kind, _, _)| *kind != SyntaxKind::EqEq),
+				"{text}"
+			);
+		}
+	}
+
+	#[test]
+	fn test_highlight_markers_in_word() {
+		use SyntaxKind::Text;
+		test_markup("a==b", &[(Text, "a==b")]);
+		test_markup("a==b==c", &[(Text, "a==b==c")]);
+		test_markup("a=b", &[(Text, "a=b")]);
+	}
+
+	#[test]
+	fn test_shorthand_tables() {
+		for (mode, table) in
+			[(LexMode::Markup, MARKUP_SHORTHANDS), (LexMode::Math, MATH_SHORTHANDS)]
+		{
+			for (i, &(text, _)) in table.iter().enumerate() {
+				assert_eq!(
+					lex(text, mode),
+					[(SyntaxKind::Shorthand, text, None)],
+					"{text}"
+				);
+				for &(prefix, _) in &table[..i] {
+					assert!(!text.starts_with(prefix), "{prefix} shadows {text}");
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn test_shorthand_tables_match_ast() {
+		use crate::ast::Shorthand;
+		for entry in MARKUP_SHORTHANDS {
+			assert!(Shorthand::MARKUP_LIST.contains(entry), "{entry:?}");
+		}
+		for entry in MATH_SHORTHANDS {
+			assert!(Shorthand::MATH_LIST.contains(entry), "{entry:?}");
+		}
+	}
+
+	#[test]
+	fn test_comment_kinds() {
+		for mode in [LexMode::Markup, LexMode::Code, LexMode::Math] {
+			for (text, kind) in [
+				("// Regular", SyntaxKind::LineComment),
+				("/// Docs", SyntaxKind::LineComment),
+				("//! Docs", SyntaxKind::DocComment),
+				("//!", SyntaxKind::DocComment),
+				("/* Block */", SyntaxKind::BlockComment),
+			] {
+				for newline in ["", "\n", "\r\n", "\u{85}", "\u{2028}", "\u{2029}"] {
+					let mut lexer = Lexer::new(&format!("{text}{newline}"), mode);
+					assert_eq!(lexer.next(), kind, "{text:?}");
+					assert_eq!(lexer.cursor(), text.len(), "{text:?}");
+				}
+			}
+		}
+	}
+}
@@ -3,8 +3,10 @@
 pub mod ast;
 pub mod package;
 
+mod docs;
 mod file;
 mod highlight;
+mod index;
 mod kind;
 mod lexer;
 mod node;
@@ -15,11 +17,17 @@ mod set;
 mod source;
 mod span;
 
+pub use self::docs::{docs_before, docs_for};
 pub use self::file::FileId;
 pub use self::highlight::{highlight, highlight_html, Tag};
+pub use self::index::LineIndex;
 pub use self::kind::SyntaxKind;
 pub use self::lexer::{
-    is_id_continue, is_id_start, is_ident, is_newline, link_prefix, split_newlines,
+    is_id_continue, is_id_start, is_ident, is_keyword, is_newline, is_valid_label,
+    is_valid_ref_target, lines, link_prefix, parse_link_prefix, sanitize_ident,
+    split_newlines, split_newlines_with_spans, tokenize, trim_ref_target, Lines,
+    LinkParts, NewlineAnalysis, NewlineKind, SpannedLines, Token, TokenizerMode, Tokens,
+    MARKUP_SHORTHANDS, MATH_SHORTHANDS,
 };
 pub use self::node::{LinkedChildren, LinkedNode, Side, SyntaxError, SyntaxNode};
 pub use self::parser::{parse, parse_code, parse_math};
@@ -106,6 +106,7 @@ fn markup_expr(p: &mut Parser, at_start: &mut bool) {
 		SyntaxKind::Space
 		| SyntaxKind::Parbreak
 		| SyntaxKind::LineComment
+		| SyntaxKind::DocComment
 		| SyntaxKind::BlockComment => {
 			p.eat();
 			return;
@@ -122,6 +123,7 @@ fn markup_expr(p: &mut Parser, at_start: &mut bool) {
 		SyntaxKind::Hash => embedded_code_expr(p),
 		SyntaxKind::Star => strong(p),
 		SyntaxKind::Underscore => emph(p),
+		SyntaxKind::EqEq => highlight(p),
 		SyntaxKind::RawDelim => raw(p),
 		SyntaxKind::HeadingMarker if *at_start => heading(p),
 		SyntaxKind::ListMarker if *at_start => list_item(p),
@@ -172,6 +174,32 @@ fn emph(p: &mut Parser) {
 	p.wrap(m, SyntaxKind::Emph);
 }
 
+/// Parses highlighted content: `==Highlighted==`.
+///
+/// The opening marker must not be followed by whitespace and the closing one
+/// must not be preceded by it. Without a matching closing marker, the opening
+/// one is just text, so that prose like `f(x)==y` or `a ==b` stays as it is.
+fn highlight(p: &mut Parser) {
+	const END: SyntaxSet = SyntaxSet::new()
+		.add(SyntaxKind::EqEq)
+		.add(SyntaxKind::Parbreak)
+		.add(SyntaxKind::RightBracket);
+
+	let m = p.marker();
+	p.assert(SyntaxKind::EqEq);
+	if !p.at(SyntaxKind::Space) {
+		markup(p, false, 0, |p| p.at_set(END));
+		if p.at(SyntaxKind::EqEq)
+			&& !p.text[..p.current_start()].ends_with(char::is_whitespace)
+		{
+			p.eat();
+			p.wrap(m, SyntaxKind::Highlight);
+			return;
+		}
+	}
+	p[m].convert_to_kind(SyntaxKind::Text);
+}
+
 /// Parses raw text with optional syntax highlighting: `` `...` ``.
 fn raw(p: &mut Parser) {
 	let m = p.marker();
@@ -183,7 +211,9 @@ fn raw(p: &mut Parser) {
 		p.eat();
 	}
 
-	p.expect(SyntaxKind::RawDelim);
+	// Unclosed raw text has no closing delimiter, the lexer attaches the
+	// error to the opening one instead.
+	p.eat_if(SyntaxKind::RawDelim);
 	p.exit();
 	p.wrap(m, SyntaxKind::Raw);
 }
@@ -618,7 +648,9 @@ fn maybe_wrap_in_math(p: &mut Parser, arg: Marker, named: Option<Marker>) {
 
 /// Parses the contents of a code block.
 fn code(p: &mut Parser, stop: impl FnMut(&Parser) -> bool) {
-	let m = p.marker();
+	// Keep the leading trivia in the code node, so that doc comments above
+	// the first expression are its siblings, like in markup.
+	let m = p.before_trivia();
 	code_exprs(p, stop);
 	p.wrap(m, SyntaxKind::Code);
 }
@@ -1751,6 +1783,10 @@ impl<'s> Parser<'s> {
 		if self.at(SyntaxKind::Error) {
 			let message = self.lexer.take_error().unwrap();
 			self.nodes.push(SyntaxNode::error(message, text));
+		} else if let Some(message) = self.lexer.take_error() {
+			// Some valid tokens still carry an error, like the opening
+			// delimiter of unclosed raw text.
+			self.nodes.push(SyntaxNode::error(message, text));
 		} else {
 			self.nodes.push(SyntaxNode::leaf(self.current, text));
 		}
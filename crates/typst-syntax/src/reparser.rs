@@ -323,4 +323,15 @@ mod tests {
         test("a #while x {\n g(x) \n}  b", 12..12, "//", true);
         test("a#[]b", 3..3, "[hey]", true);
     }
+
+    #[test]
+    fn test_reparse_block_leading_trivia() {
+        test("#{ x }", 2..3, "\n  ", true);
+        test("#{\n  x\n}", 2..5, "", true);
+        test("#{ // hi\n x }", 6..8, "ho", true);
+        test("#{ /* a */ x }", 6..7, "b", true);
+        test("#{/// Docs.\nlet x = 1\n}", 6..10, "Note", true);
+        test("a#{ x }b", 3..3, "/// Docs.\n", true);
+        test("a#{ // x\n y }b", 4..5, "", true);
+    }
 }
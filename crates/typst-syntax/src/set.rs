@@ -53,6 +53,7 @@ pub const MARKUP_EXPR: SyntaxSet = SyntaxSet::new()
     .add(SyntaxKind::Space)
     .add(SyntaxKind::Parbreak)
     .add(SyntaxKind::LineComment)
+    .add(SyntaxKind::DocComment)
     .add(SyntaxKind::BlockComment)
     .add(SyntaxKind::Text)
     .add(SyntaxKind::Linebreak)
@@ -65,6 +66,7 @@ pub const MARKUP_EXPR: SyntaxSet = SyntaxSet::new()
     .add(SyntaxKind::Hash)
     .add(SyntaxKind::Star)
     .add(SyntaxKind::Underscore)
+    .add(SyntaxKind::EqEq)
     .add(SyntaxKind::HeadingMarker)
     .add(SyntaxKind::ListMarker)
     .add(SyntaxKind::EnumMarker)
@@ -0,0 +1,292 @@
+//! Micro-benchmarks for scopes.
+//!
+//! Run with `cargo bench -p typst --bench scopes`. Pass words after `--` to
+//! only run the benchmarks whose names contain one of them, e.g.
+//! `cargo bench -p typst --bench scopes -- library`.
+
+use std::fmt::Write;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use comemo::{Prehashed, Track};
+use ecow::{eco_format, EcoString};
+use indexmap::IndexMap;
+use typst::diag::{FileError, FileResult};
+use typst::engine::Route;
+use typst::eval::Tracer;
+use typst::foundations::{Bytes, Datetime, Scope, Scopes, Value};
+use typst::syntax::{FileId, Source};
+use typst::text::{Font, FontBook};
+use typst::{Library, World};
+
+fn main() {
+    small_scopes();
+    startup();
+    bases();
+    closures();
+    tracing();
+}
+
+/// How many times each benchmark is measured.
+const SAMPLES: usize = 11;
+
+/// Run a closure repeatedly and print the median, fastest, and slowest
+/// average time per run over all samples.
+fn bench(name: &str, runs: u32, mut f: impl FnMut()) {
+    if !selected(name) {
+        return;
+    }
+
+    for _ in 0..runs / 10 {
+        f();
+    }
+
+    let mut samples: Vec<Duration> = (0..SAMPLES)
+        .map(|_| {
+            let start = Instant::now();
+            for _ in 0..runs {
+                f();
+            }
+            start.elapsed() / runs
+        })
+        .collect();
+
+    samples.sort();
+    println!(
+        "{name:<48} {:>12.2?} (min {:.2?}, max {:.2?})",
+        samples[SAMPLES / 2],
+        samples[0],
+        samples[SAMPLES - 1],
+    );
+}
+
+/// Whether a benchmark matches the filters given on the command line.
+fn selected(name: &str) -> bool {
+    let filters: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .collect();
+    filters.is_empty() || filters.iter().any(|filter| name.contains(filter.as_str()))
+}
+
+/// Define and look up bindings in scopes of different sizes, compared with a
+/// plain index map, which is what scopes were backed by before small scopes
+/// were stored inline.
+fn small_scopes() {
+    for n in [1, 4, 100] {
+        let names: Vec<EcoString> = (0..n).map(|i| eco_format!("v{i}")).collect();
+
+        bench(&format!("define and get {n} bindings (scope)"), 100_000, || {
+            let mut scope = Scope::new();
+            for (i, name) in names.iter().enumerate() {
+                scope.define(name.clone(), i as i64);
+            }
+            for name in &names {
+                black_box(scope.get(name));
+            }
+        });
+
+        bench(&format!("define and get {n} bindings (index map)"), 100_000, || {
+            let mut map = IndexMap::new();
+            for (i, name) in names.iter().enumerate() {
+                map.insert(name.clone(), Value::Int(i as i64));
+            }
+            for name in &names {
+                black_box(map.get(name));
+            }
+        });
+    }
+}
+
+/// Build the standard library, as is, prehashed like a world does, and with
+/// its lazy bindings forced like they were before they became lazy.
+fn startup() {
+    bench("build library", 100, || {
+        black_box(Library::default());
+    });
+
+    bench("build and prehash library", 100, || {
+        let library = Prehashed::new(Library::default());
+        let scope = library.global.scope();
+        for name in ["calc", "emoji", "sym"] {
+            assert!(!scope.get_binding(name).unwrap().is_forced());
+        }
+        black_box(library);
+    });
+
+    bench("build library and force lazy bindings", 100, || {
+        let library = Library::default();
+        for name in ["calc", "emoji", "sym"] {
+            black_box(library.global.scope().get(name));
+        }
+    });
+}
+
+/// Look up names from the standard library through the library alone and
+/// with two additional base scopes in front of it, which every lookup has
+/// to pass first.
+fn bases() {
+    let library = Library::default();
+    let mut prelude = Scope::new();
+    prelude.define("title", "Untitled");
+    let mut inputs = Scope::new();
+    inputs.define("draft", true);
+
+    let names = ["rect", "text", "calc", "grid", "emph"];
+    let single = Scopes::new(Some(&library));
+    let layered = Scopes::with_bases(&[&inputs, &prelude], Some(&library));
+
+    bench("look up 5 names (library only)", 100_000, || {
+        for name in names {
+            let _ = black_box(single.get(name));
+        }
+    });
+
+    bench("look up 5 names (2 bases and library)", 100_000, || {
+        for name in names {
+            let _ = black_box(layered.get(name));
+        }
+    });
+}
+
+/// Clone a scope with 500 bindings, like closures and context expressions
+/// do with their environment, compared with cloning the index map that used
+/// to back scopes. Then evaluate a document that creates ten thousand
+/// closures next to 500 bindings.
+fn closures() {
+    let mut scope = Scope::new();
+    let mut map = IndexMap::new();
+    for i in 0..500 {
+        scope.define(eco_format!("v{i}"), i);
+        map.insert(eco_format!("v{i}"), Value::Int(i));
+    }
+
+    bench("clone 500 bindings 10k times (scope)", 10, || {
+        for _ in 0..10_000 {
+            black_box(scope.clone());
+        }
+    });
+
+    bench("clone 500 bindings 10k times (index map)", 10, || {
+        for _ in 0..10_000 {
+            black_box(map.clone());
+        }
+    });
+
+    bench("compare scope with unmodified clone", 100_000, || {
+        black_box(scope == scope.clone());
+    });
+
+    let mut text = String::new();
+    for i in 0..500 {
+        writeln!(text, "#let v{i} = {i}").unwrap();
+    }
+    text.push_str("#for i in range(10000) { let f = x => x + v0 + v499; let y = f(i) }");
+
+    let world = BenchWorld::new(&text);
+    bench("evaluate 10k closures over 500 bindings", 10, || {
+        black_box(world.eval(Tracer::new()));
+    });
+}
+
+/// Evaluate a document that reads and writes variables a lot, without
+/// tracing, with tracing that matches no variable, and with tracing of one of
+/// the variables.
+///
+/// The cases without tracing only pay for the checks whether hooks or traced
+/// variables are present. They only use APIs that predate tracing, so they
+/// can be copied to an older tree to compare against evaluation without the
+/// checks.
+fn tracing() {
+    let world = BenchWorld::new(
+        "#let sum = 0\n#let step = 1\n#for i in range(100000) { sum += i * step }",
+    );
+    let reads = BenchWorld::new(
+        "#let step = 1\n#for i in range(100000) { let _ = step + step + step + step }",
+    );
+
+    bench("evaluate 100k accesses (hooks disabled)", 10, || {
+        black_box(world.eval(Tracer::new()));
+    });
+
+    bench("evaluate 400k reads (hooks disabled)", 10, || {
+        black_box(reads.eval(Tracer::new()));
+    });
+
+    bench("evaluate 100k accesses (tracing other names)", 10, || {
+        let mut tracer = Tracer::new();
+        tracer.trace_vars(["unrelated".into()]);
+        black_box(world.eval(tracer));
+    });
+
+    bench("evaluate 100k accesses (tracing `step`)", 10, || {
+        let mut tracer = Tracer::new();
+        tracer.trace_vars(["step".into()]);
+        black_box(world.eval(tracer));
+    });
+}
+
+/// A world with a single source file and no fonts, enough to evaluate
+/// documents.
+struct BenchWorld {
+    library: Prehashed<Library>,
+    book: Prehashed<FontBook>,
+    main: Source,
+}
+
+impl BenchWorld {
+    fn new(text: &str) -> Self {
+        Self {
+            library: Prehashed::new(Library::default()),
+            book: Prehashed::new(FontBook::new()),
+            main: Source::detached(text),
+        }
+    }
+
+    /// Evaluate the main file from scratch with the given tracer.
+    fn eval(&self, mut tracer: Tracer) -> Tracer {
+        comemo::evict(0);
+        typst::eval::eval(
+            (self as &dyn World).track(),
+            Route::default().track(),
+            tracer.track_mut(),
+            &self.main,
+        )
+        .unwrap();
+        tracer
+    }
+}
+
+impl World for BenchWorld {
+    fn library(&self) -> &Prehashed<Library> {
+        &self.library
+    }
+
+    fn book(&self) -> &Prehashed<FontBook> {
+        &self.book
+    }
+
+    fn main(&self) -> Source {
+        self.main.clone()
+    }
+
+    fn source(&self, id: FileId) -> FileResult<Source> {
+        if id == self.main.id() {
+            Ok(self.main.clone())
+        } else {
+            Err(FileError::NotFound(id.vpath().as_rootless_path().into()))
+        }
+    }
+
+    fn file(&self, id: FileId) -> FileResult<Bytes> {
+        Err(FileError::NotFound(id.vpath().as_rootless_path().into()))
+    }
+
+    fn font(&self, _: usize) -> Option<Font> {
+        None
+    }
+
+    fn today(&self, _: Option<i64>) -> Option<Datetime> {
+        None
+    }
+}
@@ -211,6 +211,8 @@ pub enum Tracepoint {
     Show(EcoString),
     /// A module import.
     Import,
+    /// The definition of a constant that was about to be mutated.
+    Definition(EcoString),
 }
 
 impl Display for Tracepoint {
@@ -228,6 +230,9 @@ impl Display for Tracepoint {
             Tracepoint::Import => {
                 write!(f, "error occurred while importing this module")
             }
+            Tracepoint::Definition(name) => {
+                write!(f, "the constant `{name}` is defined here")
+            }
         }
     }
 }
@@ -1,9 +1,12 @@
-use ecow::eco_format;
+use ecow::{eco_format, eco_vec, EcoVec};
 
-use crate::diag::{bail, At, Hint, SourceResult, Trace, Tracepoint};
+use crate::diag::{
+    bail, At, Hint, HintedString, SourceDiagnostic, SourceResult, Trace, Tracepoint,
+};
 use crate::eval::{Eval, Vm};
-use crate::foundations::{call_method_access, is_accessor_method, Dict, Value};
+use crate::foundations::{call_method_access, is_accessor_method, Dict, MutError, Value};
 use crate::syntax::ast::{self, AstNode};
+use crate::syntax::{Span, Spanned};
 
 /// Access an expression mutably.
 pub(crate) trait Access {
@@ -34,11 +37,23 @@ impl Access for ast::Ident<'_> {
                 vm.trace(value);
             }
         }
-        let value = vm.scopes.get_mut(&self).at(span)?;
-        Ok(value)
+        vm.observe_access(&self, span);
+        vm.scopes.get_mut(&self).map_err(|err| mut_error(err, span))
     }
 }
 
+/// Turn an error about mutating a variable into diagnostics, pointing to the
+/// definition of the variable if it is a constant with a known span.
+fn mut_error(err: MutError, span: Span) -> EcoVec<SourceDiagnostic> {
+    let definition = err
+        .definition()
+        .map(|def| Spanned::new(Tracepoint::Definition(err.var().clone()), def));
+    let HintedString { message, hints } = err.into();
+    let mut diag = SourceDiagnostic::error(span, message).with_hints(hints);
+    diag.trace.extend(definition);
+    eco_vec![diag]
+}
+
 impl Access for ast::Parenthesized<'_> {
     fn access<'a>(self, vm: &'a mut Vm) -> SourceResult<&'a mut Value> {
         self.expr().access(vm)
@@ -99,3 +114,53 @@ pub(crate) fn access_dict<'a>(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::foundations::Scopes;
+    use crate::syntax::Source;
+
+    #[test]
+    fn test_mut_error_points_to_definition() {
+        let source = Source::detached("#let x = 1 #(x = 2)");
+        let children: Vec<_> = source.root().children().collect();
+        let def = children.first().unwrap().span();
+        let use_ = children.last().unwrap().span();
+        assert_ne!(def, use_);
+
+        let mut scopes = Scopes::new(None);
+        scopes.top.define_const_spanned("x", 1, def);
+
+        let errors = mut_error(scopes.get_mut("x").unwrap_err(), use_);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].span, use_);
+        assert_eq!(errors[0].message, "cannot mutate a constant: x");
+        assert_eq!(
+            errors[0].trace.as_slice(),
+            [Spanned::new(Tracepoint::Definition("x".into()), def)]
+        );
+    }
+
+    #[test]
+    fn test_mut_error_shadow_vs_assign() {
+        let def = Source::detached("x").root().span();
+        let mut scopes = Scopes::new(None);
+        scopes.top.define_const_spanned("x", 1, def);
+
+        // Assigning through the constant fails and points to it.
+        let err = scopes.get_mut("x").unwrap_err();
+        assert_eq!(err.definition(), Some(def));
+
+        // Shadowing it with `let` in a nested scope defines a mutable
+        // variable and leaves the constant untouched.
+        scopes.enter();
+        scopes.top.define("x", 2);
+        *scopes.get_mut("x").unwrap() = Value::Int(3);
+        assert_eq!(scopes.get("x"), Ok(&Value::Int(3)));
+        scopes.exit();
+
+        assert_eq!(scopes.get("x"), Ok(&Value::Int(1)));
+        assert_eq!(scopes.get_mut("x").unwrap_err().definition(), Some(def));
+    }
+}
@@ -2,8 +2,9 @@ use std::collections::HashSet;
 
 use crate::diag::{bail, At, SourceResult};
 use crate::eval::{Access, Eval, Vm};
-use crate::foundations::{Array, Dict, Value};
+use crate::foundations::{Array, Dict, Scope, Value};
 use crate::syntax::ast::{self, AstNode};
+use crate::syntax::{docs_before, SyntaxNode};
 
 impl Eval for ast::LetBinding<'_> {
     type Output = Value;
@@ -26,6 +27,43 @@ impl Eval for ast::LetBinding<'_> {
     }
 }
 
+/// Attaches doc comments to the bindings of `let` expressions evaluated in a
+/// sequence of sibling expressions.
+pub(crate) struct DocComments<'a> {
+    siblings: &'a [SyntaxNode],
+    cursor: usize,
+}
+
+impl<'a> DocComments<'a> {
+    /// Look for doc comments among the children of the given node.
+    pub fn new(parent: &'a SyntaxNode) -> Self {
+        Self { siblings: parent.children().as_slice(), cursor: 0 }
+    }
+
+    /// Document the bindings a just evaluated `let` expression defined.
+    ///
+    /// Bindings must be passed in order. The search for each one continues
+    /// where the previous one was found, so that a whole sequence is
+    /// processed in linear time.
+    pub fn attach(&mut self, vm: &mut Vm, binding: ast::LetBinding) {
+        let node = binding.to_untyped();
+        let Some(offset) = self.siblings[self.cursor..]
+            .iter()
+            .position(|sibling| std::ptr::eq(sibling, node))
+        else {
+            return;
+        };
+
+        let index = self.cursor + offset;
+        self.cursor = index + 1;
+
+        let Some(docs) = docs_before(&self.siblings[..index]) else { return };
+        for ident in binding.kind().bindings() {
+            vm.scopes.top.document(&ident, &docs, None);
+        }
+    }
+}
+
 impl Eval for ast::DestructAssignment<'_> {
     type Output = Value;
 
@@ -41,20 +79,50 @@ impl Eval for ast::DestructAssignment<'_> {
 }
 
 /// Destructures a value into a pattern.
+///
+/// The variables are only defined once the whole pattern was destructured.
 pub(crate) fn destructure(
     vm: &mut Vm,
     pattern: ast::Pattern,
     value: Value,
 ) -> SourceResult<()> {
-    destructure_impl(vm, pattern, value, &mut |vm, expr, value| match expr {
-        ast::Expr::Ident(ident) => {
-            vm.define(ident, value);
-            Ok(())
-        }
+    let mut bindings = Scope::new();
+    destructure_into(vm, pattern, value, &mut bindings, "binding")?;
+    vm.define_all(&bindings);
+    Ok(())
+}
+
+/// Destructures a value into a pattern, collecting the bindings in a scope.
+///
+/// Fails if the pattern binds a name that the scope already defines. The
+/// `what` describes the bindings in the error, e.g. "parameter".
+pub(crate) fn destructure_into(
+    vm: &mut Vm,
+    pattern: ast::Pattern,
+    value: Value,
+    bindings: &mut Scope,
+    what: &str,
+) -> SourceResult<()> {
+    destructure_impl(vm, pattern, value, &mut |_, expr, value| match expr {
+        ast::Expr::Ident(ident) => bind_unique(bindings, ident, value, what),
         _ => bail!(expr.span(), "cannot assign to this expression"),
     })
 }
 
+/// Bind a value to an identifier in a scope that must not define it yet.
+pub(crate) fn bind_unique(
+    bindings: &mut Scope,
+    ident: ast::Ident,
+    value: Value,
+    what: &str,
+) -> SourceResult<()> {
+    let span = ident.span();
+    if bindings.try_define_spanned(ident.get().clone(), value, span).is_err() {
+        bail!(span, "duplicate {what}: {}", ident.as_str());
+    }
+    Ok(())
+}
+
 /// Destruct the given value into the pattern and apply the function to each binding.
 fn destructure_impl<F>(
     vm: &mut Vm,
@@ -63,7 +131,7 @@ fn destructure_impl<F>(
     f: &mut F,
 ) -> SourceResult<()>
 where
-    F: Fn(&mut Vm, ast::Expr, Value) -> SourceResult<()>,
+    F: FnMut(&mut Vm, ast::Expr, Value) -> SourceResult<()>,
 {
     match pattern {
         ast::Pattern::Normal(expr) => f(vm, expr, value)?,
@@ -87,7 +155,7 @@ fn destructure_array<F>(
     f: &mut F,
 ) -> SourceResult<()>
 where
-    F: Fn(&mut Vm, ast::Expr, Value) -> SourceResult<()>,
+    F: FnMut(&mut Vm, ast::Expr, Value) -> SourceResult<()>,
 {
     let len = value.as_slice().len();
     let mut i = 0;
@@ -132,7 +200,7 @@ fn destructure_dict<F>(
     f: &mut F,
 ) -> SourceResult<()>
 where
-    F: Fn(&mut Vm, ast::Expr, Value) -> SourceResult<()>,
+    F: FnMut(&mut Vm, ast::Expr, Value) -> SourceResult<()>,
 {
     let mut sink = None;
     let mut used = HashSet::new();
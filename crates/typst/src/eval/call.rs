@@ -1,9 +1,11 @@
 use comemo::{Tracked, TrackedMut};
-use ecow::{eco_format, EcoVec};
+use ecow::{eco_format, EcoString, EcoVec};
 
-use crate::diag::{bail, error, At, HintedStrResult, SourceResult, Trace, Tracepoint};
+use crate::diag::{bail, error, At, SourceResult, Trace, Tracepoint};
 use crate::engine::Engine;
-use crate::eval::{Access, Eval, FlowEvent, Route, Tracer, Vm};
+use crate::eval::{
+    bind_unique, destructure_into, Access, Eval, FlowEvent, Route, Tracer, Vm,
+};
 use crate::foundations::{
     call_method_mut, is_mutating_method, Arg, Args, Bytes, Capturer, Closure, Content,
     Context, Func, IntoValue, NativeElement, Scope, Scopes, Value,
@@ -28,7 +30,7 @@ impl Eval for ast::FuncCall<'_> {
         let args = self.args();
         let trailing_comma = args.trailing_comma();
 
-        if !vm.engine.route.within(Route::MAX_CALL_DEPTH) {
+        if !vm.engine.route.within(vm.world().library().max_call_depth) {
             bail!(span, "maximum function call depth exceeded");
         }
 
@@ -268,6 +270,11 @@ impl Eval for ast::Closure<'_> {
 }
 
 /// Call the function in the context with the arguments.
+///
+/// Closures can't have side effects beyond those recorded by the tracked
+/// arguments: Captured variables are copies and can't be mutated. So calls
+/// are memoized like native functions, keyed by the closure, its arguments,
+/// and the accessed parts of the tracked context.
 #[comemo::memoize]
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn call_closure(
@@ -312,6 +319,9 @@ pub(crate) fn call_closure(
     let num_pos_args = args.to_pos().len();
     let sink_size = num_pos_args.checked_sub(closure.num_pos_params);
 
+    // Collect the parameters first, so that duplicates are caught even if
+    // they shadow the closure's name or a captured variable.
+    let mut bindings = Scope::new();
     let mut sink = None;
     let mut sink_pos_values = None;
     let mut defaults = closure.defaults.iter();
@@ -319,13 +329,16 @@ pub(crate) fn call_closure(
         match p {
             ast::Param::Pos(pattern) => match pattern {
                 ast::Pattern::Normal(ast::Expr::Ident(ident)) => {
-                    vm.define(ident, args.expect::<Value>(&ident)?)
+                    let value = args.expect::<Value>(&ident)?;
+                    bind_unique(&mut bindings, ident, value, "parameter")?;
                 }
                 pattern => {
-                    crate::eval::destructure(
+                    destructure_into(
                         &mut vm,
                         pattern,
                         args.expect::<Value>("pattern parameter")?,
+                        &mut bindings,
+                        "parameter",
                     )?;
                 }
             },
@@ -340,7 +353,7 @@ pub(crate) fn call_closure(
                 let default = defaults.next().unwrap();
                 let value =
                     args.named::<Value>(&name)?.unwrap_or_else(|| default.clone());
-                vm.define(name, value);
+                bind_unique(&mut bindings, name, value, "parameter")?;
             }
         }
     }
@@ -352,15 +365,26 @@ pub(crate) fn call_closure(
             if let Some(sink_pos_values) = sink_pos_values {
                 remaining_args.items.extend(sink_pos_values);
             }
-            vm.define(sink_name, remaining_args);
+            let value = remaining_args.into_value();
+            bind_unique(&mut bindings, sink_name, value, "parameter")?;
         }
     }
 
+    vm.define_all(&bindings);
+
     // Ensure all arguments have been used.
     args.finish()?;
 
-    // Handle control flow.
     let output = body.eval(&mut vm)?;
+
+    // Check for unused bindings, including the parameters. The closure's own
+    // name is exempt, as it is only provided for recursive calls.
+    if !vm.world().library().lints.allow_unused_params {
+        vm.scopes.record_top(name.map(ast::Ident::as_str));
+    }
+    vm.lint_unused();
+
+    // Handle control flow.
     match vm.flow {
         Some(FlowEvent::Return(_, Some(explicit))) => return Ok(explicit),
         Some(FlowEvent::Return(_, None)) => {}
@@ -398,6 +422,17 @@ impl<'a> CapturesVisitor<'a> {
         }
     }
 
+    /// Determine the names of all variables that a syntax node, typically a
+    /// closure, references without binding them itself.
+    ///
+    /// The names are in order of their first use and can be resolved with
+    /// [`Scopes::capture`].
+    pub fn free_names(node: &SyntaxNode) -> Vec<EcoString> {
+        let mut visitor = Self::new(None, Capturer::Function);
+        visitor.visit(node);
+        visitor.finish().names().cloned().collect()
+    }
+
     /// Return the scope of captured variables.
     pub fn finish(self) -> Scope {
         self.captures
@@ -410,10 +445,8 @@ impl<'a> CapturesVisitor<'a> {
             // Identifiers that shouldn't count as captures because they
             // actually bind a new name are handled below (individually through
             // the expressions that contain them).
-            Some(ast::Expr::Ident(ident)) => self.capture(&ident, Scopes::get),
-            Some(ast::Expr::MathIdent(ident)) => {
-                self.capture(&ident, Scopes::get_in_math)
-            }
+            Some(ast::Expr::Ident(ident)) => self.capture(&ident, false),
+            Some(ast::Expr::MathIdent(ident)) => self.capture(&ident, true),
 
             // Code and content blocks create a scope.
             Some(ast::Expr::Code(_) | ast::Expr::Content(_)) => {
@@ -524,21 +557,22 @@ impl<'a> CapturesVisitor<'a> {
     }
 
     /// Capture a variable if it isn't internal.
-    fn capture(
-        &mut self,
-        ident: &str,
-        getter: impl FnOnce(&'a Scopes<'a>, &str) -> HintedStrResult<&'a Value>,
-    ) {
+    fn capture(&mut self, ident: &str, in_math: bool) {
         if self.internal.get(ident).is_err() {
-            let Some(value) = self
-                .external
-                .map(|external| getter(external, ident).ok())
-                .unwrap_or(Some(&Value::None))
-            else {
-                return;
-            };
-
-            self.captures.define_captured(ident, value.clone(), self.capturer);
+            match self.external {
+                Some(external) => external.capture_into(
+                    &mut self.captures,
+                    ident,
+                    self.capturer,
+                    in_math,
+                ),
+                None => self.captures.define_captured(
+                    ident,
+                    Value::None,
+                    self.capturer,
+                    Span::detached(),
+                ),
+            }
         }
     }
 }
@@ -546,7 +580,7 @@ impl<'a> CapturesVisitor<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::syntax::parse;
+    use crate::syntax::{parse, Source};
 
     #[track_caller]
     fn test(text: &str, result: &[&str]) {
@@ -567,6 +601,58 @@ mod tests {
         assert_eq!(names, result);
     }
 
+    #[test]
+    fn test_captures_keep_definition_spans() {
+        let span = Source::detached("x").root().span();
+        let mut scopes = Scopes::new(None);
+        scopes.top.define_spanned("x", 0, span);
+        scopes.top.define("y", 0);
+
+        let mut visitor = CapturesVisitor::new(Some(&scopes), Capturer::Function);
+        visitor.visit(&parse("#(x + y)"));
+
+        let captures = visitor.finish();
+        assert_eq!(captures.get_binding("x").unwrap().span(), Some(span));
+        assert_eq!(captures.get_binding("y").unwrap().span(), None);
+    }
+
+    #[test]
+    fn test_captures_keep_docs() {
+        let mut scopes = Scopes::new(None);
+        scopes.top.define_with_docs("x", 0, "The x.", Some("numbers"));
+        scopes.top.define("y", 0);
+
+        let mut visitor = CapturesVisitor::new(Some(&scopes), Capturer::Function);
+        visitor.visit(&parse("#(x + y)"));
+
+        let captures = visitor.finish();
+        let x = captures.get_binding("x").unwrap();
+        assert_eq!(x.docs(), Some("The x."));
+        assert_eq!(x.doc_category(), Some("numbers"));
+        assert_eq!(captures.get_binding("y").unwrap().docs(), None);
+    }
+
+    #[test]
+    fn test_captures_only_free_names() {
+        let mut scopes = Scopes::new(None);
+        for (i, name) in ('a'..='j').enumerate() {
+            scopes.top.define(name.to_string(), i as i64);
+        }
+
+        let names = CapturesVisitor::free_names(&parse("#((x) => c + x + a + c)"));
+        let names: Vec<_> = names.iter().map(EcoString::as_str).collect();
+        assert_eq!(names, ["c", "a"]);
+
+        let captured = scopes.capture(names, Capturer::Function);
+        assert_eq!(captured.len(), 2);
+        assert_eq!(captured.get("a"), Some(&Value::Int(0)));
+
+        // The captured values are copies.
+        *scopes.get_mut("a").unwrap() = Value::Int(10);
+        assert_eq!(captured.get("a"), Some(&Value::Int(0)));
+        assert!(captured.clone().get_mut("a").unwrap().is_err());
+    }
+
     #[test]
     fn test_captures() {
         // Let binding and function definition.
@@ -1,9 +1,10 @@
 use ecow::{eco_vec, EcoVec};
 
-use crate::diag::{bail, error, At, SourceDiagnostic, SourceResult};
-use crate::eval::{ops, CapturesVisitor, Eval, Vm};
+use crate::diag::{bail, error, At, HintedString, SourceDiagnostic, SourceResult};
+use crate::eval::{ops, CapturesVisitor, DocComments, Eval, Vm};
 use crate::foundations::{
-    Array, Capturer, Closure, Content, ContextElem, Dict, Func, NativeElement, Str, Value,
+    Array, Capturer, Closure, Content, ContextElem, Dict, Func, NativeElement, PathError,
+    Str, Value,
 };
 use crate::syntax::ast::{self, AstNode};
 
@@ -11,7 +12,7 @@ impl Eval for ast::Code<'_> {
     type Output = Value;
 
     fn eval(self, vm: &mut Vm) -> SourceResult<Self::Output> {
-        eval_code(vm, &mut self.exprs())
+        eval_code(vm, &mut self.exprs(), &mut DocComments::new(self.to_untyped()))
     }
 }
 
@@ -19,6 +20,7 @@ impl Eval for ast::Code<'_> {
 fn eval_code<'a>(
     vm: &mut Vm,
     exprs: &mut impl Iterator<Item = ast::Expr<'a>>,
+    docs: &mut DocComments<'a>,
 ) -> SourceResult<Value> {
     let flow = vm.flow.take();
     let mut output = Value::None;
@@ -32,7 +34,7 @@ fn eval_code<'a>(
                     break;
                 }
 
-                let tail = eval_code(vm, exprs)?.display();
+                let tail = eval_code(vm, exprs, docs)?.display();
                 Value::Content(tail.styled_with_map(styles))
             }
             ast::Expr::Show(show) => {
@@ -41,13 +43,18 @@ fn eval_code<'a>(
                     break;
                 }
 
-                let tail = eval_code(vm, exprs)?.display();
+                let tail = eval_code(vm, exprs, docs)?.display();
                 Value::Content(tail.styled_with_recipe(
                     &mut vm.engine,
                     vm.context,
                     recipe,
                 )?)
             }
+            ast::Expr::Let(binding) => {
+                let value = expr.eval(vm)?;
+                docs.attach(vm, binding);
+                value
+            }
             _ => expr.eval(vm)?,
         };
 
@@ -84,6 +91,7 @@ impl Eval for ast::Expr<'_> {
             Self::SmartQuote(v) => v.eval(vm).map(Value::Content),
             Self::Strong(v) => v.eval(vm).map(Value::Content),
             Self::Emph(v) => v.eval(vm).map(Value::Content),
+            Self::Highlight(v) => v.eval(vm).map(Value::Content),
             Self::Raw(v) => v.eval(vm).map(Value::Content),
             Self::Link(v) => v.eval(vm).map(Value::Content),
             Self::Label(v) => v.eval(vm),
@@ -147,7 +155,7 @@ impl Eval for ast::Ident<'_> {
     type Output = Value;
 
     fn eval(self, vm: &mut Vm) -> SourceResult<Self::Output> {
-        vm.scopes.get(&self).cloned().at(self.span())
+        vm.resolve(&self, self.span(), false)
     }
 }
 
@@ -271,9 +279,9 @@ impl Eval for ast::CodeBlock<'_> {
     type Output = Value;
 
     fn eval(self, vm: &mut Vm) -> SourceResult<Self::Output> {
-        vm.scopes.enter();
-        let output = self.body().eval(vm)?;
-        vm.scopes.exit();
+        let mut vm = vm.scoped();
+        let output = self.body().eval(&mut vm)?;
+        vm.finish();
         Ok(output)
     }
 }
@@ -282,10 +290,10 @@ impl Eval for ast::ContentBlock<'_> {
     type Output = Content;
 
     fn eval(self, vm: &mut Vm) -> SourceResult<Self::Output> {
-        vm.scopes.enter();
-        let content = self.body().eval(vm)?;
-        vm.scopes.exit();
-        Ok(content)
+        let mut vm = vm.scoped();
+        let output = self.body().eval(&mut vm)?;
+        vm.finish();
+        Ok(output)
     }
 }
 
@@ -327,6 +335,14 @@ impl Eval for ast::FieldAccess<'_> {
             }
         }
 
+        // Suggest a similarly named definition if a module path doesn't
+        // resolve.
+        if let (Value::Module(_), Some(path)) = (&value, self.path()) {
+            if let Err(err @ PathError::UnknownField { .. }) = vm.scopes.get_path(&path) {
+                return Err(HintedString::from(err)).at(field.span());
+            }
+        }
+
         Err(err)
     }
 }
@@ -109,7 +109,8 @@ impl Eval for ast::ForLoop<'_> {
 
         macro_rules! iter {
             (for $pat:ident in $iterable:expr) => {{
-                vm.scopes.enter();
+                let mut scoped = vm.scoped();
+                let vm = &mut *scoped;
 
                 #[allow(unused_parens)]
                 for value in $iterable {
@@ -130,7 +131,7 @@ impl Eval for ast::ForLoop<'_> {
                     }
                 }
 
-                vm.scopes.exit();
+                scoped.finish();
             }};
         }
 
@@ -1,12 +1,15 @@
 use comemo::TrackedMut;
 use ecow::{eco_format, eco_vec, EcoString};
 
-use crate::diag::{bail, error, warning, At, FileError, SourceResult, Trace, Tracepoint};
+use crate::diag::{
+    bail, error, warning, At, FileError, SourceDiagnostic, SourceResult, Trace,
+    Tracepoint,
+};
 use crate::eval::{eval, Eval, Vm};
-use crate::foundations::{Content, Module, Value};
+use crate::foundations::{ConflictPolicy, Content, MergeConflict, Module, Repr, Value};
 use crate::syntax::ast::{self, AstNode};
 use crate::syntax::package::{PackageManifest, PackageSpec};
-use crate::syntax::{FileId, Span, VirtualPath};
+use crate::syntax::{is_keyword, sanitize_ident, FileId, Span, VirtualPath};
 use crate::World;
 
 impl Eval for ast::ModuleImport<'_> {
@@ -52,12 +55,41 @@ impl Eval for ast::ModuleImport<'_> {
                 // Only import here if there is no rename.
                 if new_name.is_none() {
                     let name: EcoString = source.name().unwrap().into();
-                    vm.scopes.top.define(name, source);
+                    if let Err(message) =
+                        vm.scopes.top.define_checked(name.clone(), source)
+                    {
+                        let rename = match sanitize_ident(&name) {
+                            Some(ident) if !is_keyword(&ident) => {
+                                eco_format!("rename the import, e.g. with `as {ident}`")
+                            }
+                            _ => "rename the import with `as`".into(),
+                        };
+                        bail!(
+                            source_span,
+                            "cannot import module {} under its own name", name.repr();
+                            hint: "{message}";
+                            hint: "{rename}"
+                        );
+                    }
                 }
             }
             Some(ast::Imports::Wildcard) => {
-                for (var, value) in scope.iter() {
-                    vm.scopes.top.define(var.clone(), value.clone());
+                // A wildcard import shadows earlier local definitions, but two
+                // wildcard imports of different values for the same name are
+                // ambiguous.
+                let result = vm.scopes.top.merge_with(scope, |existing, _| {
+                    if existing.is_merged() {
+                        ConflictPolicy::Error
+                    } else {
+                        ConflictPolicy::Shadow
+                    }
+                });
+
+                if let Err(conflicts) = result {
+                    return Err(conflicts
+                        .into_iter()
+                        .map(|conflict| wildcard_conflict(vm, conflict, source_span))
+                        .collect());
                 }
             }
             Some(ast::Imports::Items(items)) => {
@@ -92,6 +124,20 @@ impl Eval for ast::ModuleImport<'_> {
     }
 }
 
+/// The error for a name that is defined by two wildcard imports.
+fn wildcard_conflict(vm: &Vm, conflict: MergeConflict, span: Span) -> SourceDiagnostic {
+    let name = &conflict.name;
+    let mut diag =
+        error!(span, "`{name}` is already defined by an earlier wildcard import");
+    if let Some(location) = conflict.existing.and_then(|def| vm.locate(def, span)) {
+        diag.hint(eco_format!("the earlier definition is {location}"));
+    }
+    if let Some(location) = conflict.incoming.and_then(|def| vm.locate(def, span)) {
+        diag.hint(eco_format!("this import defines it {location}"));
+    }
+    diag.with_hint(eco_format!("import `{name}` explicitly to choose a definition"))
+}
+
 impl Eval for ast::ModuleInclude<'_> {
     type Output = Content;
 
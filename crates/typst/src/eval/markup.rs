@@ -1,5 +1,5 @@
 use crate::diag::{warning, SourceResult};
-use crate::eval::{Eval, Vm};
+use crate::eval::{DocComments, Eval, Vm};
 use crate::foundations::{Content, Label, NativeElement, Smart, Unlabellable, Value};
 use crate::math::EquationElem;
 use crate::model::{
@@ -8,15 +8,17 @@ use crate::model::{
 };
 use crate::symbols::Symbol;
 use crate::syntax::ast::{self, AstNode};
+use crate::syntax::trim_ref_target;
 use crate::text::{
-	LinebreakElem, RawContent, RawElem, SmartQuoteElem, SpaceElem, TextElem,
+	HighlightElem, LinebreakElem, RawContent, RawElem, SmartQuoteElem, SpaceElem,
+	TextElem,
 };
 
 impl Eval for ast::Markup<'_> {
 	type Output = Content;
 
 	fn eval(self, vm: &mut Vm) -> SourceResult<Self::Output> {
-		eval_markup(vm, &mut self.exprs())
+		eval_markup(vm, &mut self.exprs(), &mut DocComments::new(self.to_untyped()))
 	}
 }
 
@@ -24,6 +26,7 @@ impl Eval for ast::Markup<'_> {
 fn eval_markup<'a>(
 	vm: &mut Vm,
 	exprs: &mut impl Iterator<Item = ast::Expr<'a>>,
+	docs: &mut DocComments<'a>,
 ) -> SourceResult<Content> {
 	let flow = vm.flow.take();
 	let mut seq = Vec::with_capacity(exprs.size_hint().1.unwrap_or_default());
@@ -36,7 +39,7 @@ fn eval_markup<'a>(
 					break;
 				}
 
-				seq.push(eval_markup(vm, exprs)?.styled_with_map(styles))
+				seq.push(eval_markup(vm, exprs, docs)?.styled_with_map(styles))
 			}
 			ast::Expr::Show(show) => {
 				let recipe = show.eval(vm)?;
@@ -44,9 +47,14 @@ fn eval_markup<'a>(
 					break;
 				}
 
-				let tail = eval_markup(vm, exprs)?;
+				let tail = eval_markup(vm, exprs, docs)?;
 				seq.push(tail.styled_with_recipe(&mut vm.engine, vm.context, recipe)?)
 			}
+			ast::Expr::Let(binding) => {
+				let value = expr.eval(vm)?;
+				docs.attach(vm, binding);
+				seq.push(value.display().spanned(expr.span()));
+			}
 			expr => match expr.eval(vm)? {
 				Value::Label(label) => {
 					if let Some(elem) =
@@ -163,6 +171,14 @@ impl Eval for ast::Emph<'_> {
 	}
 }
 
+impl Eval for ast::Highlight<'_> {
+	type Output = Content;
+
+	fn eval(self, vm: &mut Vm) -> SourceResult<Self::Output> {
+		Ok(HighlightElem::new(self.body().eval(vm)?).pack())
+	}
+}
+
 impl Eval for ast::Raw<'_> {
 	type Output = Content;
 
@@ -187,8 +203,18 @@ impl Eval for ast::Link<'_> {
 impl Eval for ast::Label<'_> {
 	type Output = Value;
 
-	fn eval(self, _: &mut Vm) -> SourceResult<Self::Output> {
-		Ok(Value::Label(Label::new(self.get())))
+	fn eval(self, vm: &mut Vm) -> SourceResult<Self::Output> {
+		let name = self.get();
+		let target = trim_ref_target(name);
+		if let Some(c) = name[target.len()..].chars().next() {
+			vm.engine.tracer.warn(warning!(
+				self.span(), "label should not end with '{c}'";
+				hint: "a reference like `@{target}{c}` leaves the '{c}' to the surrounding text";
+				hint: "this will become an error in a future version",
+			));
+		}
+
+		Ok(Value::Label(Label::new(name)))
 	}
 }
 
@@ -1,6 +1,6 @@
 use ecow::eco_format;
 
-use crate::diag::{At, SourceResult};
+use crate::diag::SourceResult;
 use crate::eval::{Eval, Vm};
 use crate::foundations::{Content, NativeElement, Value};
 use crate::math::{AlignPointElem, AttachElem, FracElem, LrElem, PrimesElem, RootElem};
@@ -22,7 +22,7 @@ impl Eval for ast::MathIdent<'_> {
     type Output = Value;
 
     fn eval(self, vm: &mut Vm) -> SourceResult<Self::Output> {
-        vm.scopes.get_in_math(&self).cloned().at(self.span())
+        vm.resolve(&self, self.span(), true)
     }
 }
 
@@ -27,7 +27,9 @@ use comemo::{Track, Tracked, TrackedMut};
 
 use crate::diag::{bail, SourceResult};
 use crate::engine::{Engine, Route};
-use crate::foundations::{Cast, Context, Module, NativeElement, Scope, Scopes, Value};
+use crate::foundations::{
+	Cast, Context, Module, NativeElement, Scope, Scopes, ScopesView, Value,
+};
 use crate::introspection::{Introspector, Locator};
 use crate::math::EquationElem;
 use crate::syntax::{ast, parse, parse_code, parse_math, Source, Span};
@@ -75,6 +77,9 @@ pub fn eval(
 	let markup = root.cast::<ast::Markup>().unwrap();
 	let output = markup.eval(&mut vm)?;
 
+	// The top-level bindings are exported, so only nested ones are checked.
+	vm.lint_unused();
+
 	// Handle control flow.
 	if let Some(flow) = vm.flow {
 		bail!(flow.forbidden());
@@ -101,6 +106,39 @@ pub fn eval_string(
 	span: Span,
 	mode: EvalMode,
 	scope: Scope,
+) -> SourceResult<Value> {
+	let mut scopes = Scopes::new(Some(world.library()));
+	scopes.scopes.push(scope);
+	eval_string_with(world, string, span, mode, scopes)
+}
+
+/// Evaluate a string as code with read-only access to the variables visible
+/// through a view, e.g. those of the calling code.
+///
+/// The evaluated code can shadow the variables, but neither define nor
+/// mutate any in the viewed scopes. Unlike [`eval_string`], this is not
+/// memoized.
+///
+/// This is an API for embedders only. The built-in evaluation paths, like
+/// the `eval` function, still evaluate in a fresh environment, because
+/// hiding the caller's variables is part of how they're meant to work.
+pub fn eval_string_in(
+	world: Tracked<dyn World + '_>,
+	string: &str,
+	span: Span,
+	mode: EvalMode,
+	view: ScopesView,
+) -> SourceResult<Value> {
+	eval_string_with(world, string, span, mode, Scopes::nested(view))
+}
+
+/// Evaluate a string as code in the given scopes.
+fn eval_string_with(
+	world: Tracked<dyn World + '_>,
+	string: &str,
+	span: Span,
+	mode: EvalMode,
+	scopes: Scopes,
 ) -> SourceResult<Value> {
 	let mut root = match mode {
 		EvalMode::Code => parse_code(string),
@@ -130,9 +168,7 @@ pub fn eval_string(
 
 	// Prepare VM.
 	let context = Context::none();
-	let scopes = Scopes::new(Some(world.library()));
 	let mut vm = Vm::new(engine, context.track(), scopes, root.span());
-	vm.scopes.scopes.push(scope);
 
 	// Evaluate the code.
 	let output = match mode {
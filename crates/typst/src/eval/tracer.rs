@@ -1,20 +1,24 @@
 use std::collections::HashSet;
 
-use ecow::EcoVec;
+use ecow::{EcoString, EcoVec};
 
 use crate::diag::SourceDiagnostic;
 use crate::foundations::{Styles, Value};
 use crate::syntax::{FileId, Span};
 use crate::util::hash128;
 
-/// Traces warnings and which values existed for an expression at a span.
+/// Traces warnings, which values existed for an expression at a span, and
+/// how selected variables were defined and used.
 #[derive(Default, Clone)]
 pub struct Tracer {
     inspected: Option<Span>,
     warnings: EcoVec<SourceDiagnostic>,
     warnings_set: HashSet<u128>,
+    warned_keys: HashSet<(EcoString, Span)>,
     delayed: EcoVec<SourceDiagnostic>,
     values: EcoVec<(Value, Option<Styles>)>,
+    traced_vars: Option<EcoVec<EcoString>>,
+    var_events: EcoVec<VarEvent>,
 }
 
 impl Tracer {
@@ -46,6 +50,21 @@ impl Tracer {
     pub fn values(self) -> EcoVec<(Value, Option<Styles>)> {
         self.values
     }
+
+    /// Trace all variables whose name matches one of the patterns. A `*` in
+    /// a pattern matches any sequence of characters.
+    ///
+    /// Their definitions and accesses can be retrieved via `var_events`
+    /// later.
+    pub fn trace_vars(&mut self, patterns: impl IntoIterator<Item = EcoString>) {
+        self.traced_vars = Some(patterns.into_iter().collect());
+    }
+
+    /// Get the definitions and accesses of traced variables, in the order in
+    /// which they happened.
+    pub fn var_events(&self) -> &[VarEvent] {
+        &self.var_events
+    }
 }
 
 #[comemo::track]
@@ -64,6 +83,19 @@ impl Tracer {
         }
     }
 
+    /// Add a warning unless one with the same name and span was added
+    /// before.
+    ///
+    /// This reports a problem with something once instead of at every span it
+    /// occurs at. The span identifies the thing together with its name, e.g.
+    /// the definition of a variable, so that unrelated things of the same
+    /// name are reported separately.
+    pub fn warn_once(&mut self, name: EcoString, span: Span, warning: SourceDiagnostic) {
+        if self.warned_keys.insert((name, span)) {
+            self.warn(warning);
+        }
+    }
+
     /// The inspected span if it is part of the given source file.
     pub fn inspected(&self, id: FileId) -> Option<Span> {
         if self.inspected.and_then(Span::id) == Some(id) {
@@ -79,4 +111,96 @@ impl Tracer {
             self.values.push((value, styles));
         }
     }
+
+    /// The patterns of the traced variable names, if variables are traced.
+    pub fn traced_vars(&self) -> Option<EcoVec<EcoString>> {
+        self.traced_vars.clone()
+    }
+
+    /// Trace the definition or access of a variable.
+    pub fn trace_var(&mut self, event: VarEvent) {
+        self.var_events.push(event);
+    }
+}
+
+/// A definition or access of a traced variable.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum VarEvent {
+    /// The variable was defined.
+    Define {
+        /// The name of the variable.
+        name: EcoString,
+        /// The value's `repr`, truncated if it is very long.
+        value: EcoString,
+        /// Where the variable was defined.
+        span: Span,
+    },
+    /// The variable was read or mutated.
+    Access {
+        /// The name of the variable.
+        name: EcoString,
+        /// Where the variable was used.
+        span: Span,
+    },
+}
+
+impl VarEvent {
+    /// The name of the variable.
+    pub fn name(&self) -> &EcoString {
+        match self {
+            Self::Define { name, .. } | Self::Access { name, .. } => name,
+        }
+    }
+
+    /// Where the event happened.
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Define { span, .. } | Self::Access { span, .. } => *span,
+        }
+    }
+}
+
+/// Whether a variable name matches a pattern in which `*` matches any
+/// sequence of characters.
+pub(crate) fn matches_var_pattern(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = parts.next().and_then(|first| name.strip_prefix(first)) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        let Some(i) = rest.find(part) else { return false };
+        rest = &rest[i + part.len()..];
+    }
+
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diag::warning;
+    use crate::syntax::{Source, SyntaxNode};
+
+    #[test]
+    fn test_warn_once_per_name_and_span() {
+        let source = Source::detached("#a #b");
+        let spans: Vec<_> = source.root().children().map(SyntaxNode::span).collect();
+        let (def, other, use_) = (spans[1], spans[4], spans[0]);
+
+        let mut tracer = Tracer::new();
+        tracer.warn_once("a".into(), def, warning!(use_, "first"));
+        tracer.warn_once("a".into(), def, warning!(def, "again"));
+        tracer.warn_once("a".into(), other, warning!(use_, "other definition"));
+        tracer.warn_once("b".into(), def, warning!(use_, "other name"));
+
+        let messages: Vec<_> =
+            tracer.warnings().into_iter().map(|warning| warning.message).collect();
+        assert_eq!(messages, ["first", "other definition", "other name"]);
+    }
 }
@@ -1,8 +1,12 @@
 use comemo::Tracked;
+use ecow::{eco_format, EcoString, EcoVec};
 
+use crate::diag::{warning, At, SourceResult};
 use crate::engine::Engine;
-use crate::eval::FlowEvent;
-use crate::foundations::{Context, IntoValue, Scopes, Value};
+use crate::eval::{matches_var_pattern, FlowEvent, VarEvent};
+use crate::foundations::{
+    BindingSnapshot, Context, Deprecation, IntoValue, Scope, ScopeGuard, Scopes, Value,
+};
 use crate::syntax::ast::{self, AstNode};
 use crate::syntax::Span;
 use crate::World;
@@ -20,6 +24,9 @@ pub struct Vm<'a> {
     pub(crate) scopes: Scopes<'a>,
     /// A span that is currently under inspection.
     pub(crate) inspected: Option<Span>,
+    /// Patterns of the variable names whose definitions and accesses are
+    /// traced.
+    pub(crate) traced: Option<EcoVec<EcoString>>,
     /// Data that is contextually made accessible to code behind the scenes.
     pub(crate) context: Tracked<'a, Context<'a>>,
 }
@@ -29,11 +36,22 @@ impl<'a> Vm<'a> {
     pub fn new(
         engine: Engine<'a>,
         context: Tracked<'a, Context<'a>>,
-        scopes: Scopes<'a>,
+        mut scopes: Scopes<'a>,
         target: Span,
     ) -> Self {
         let inspected = target.id().and_then(|id| engine.tracer.inspected(id));
-        Self { engine, context, flow: None, scopes, inspected }
+        let traced = engine.tracer.traced_vars();
+        if engine.world.library().lints.unused {
+            scopes.track_usage();
+        }
+        Self {
+            engine,
+            context,
+            flow: None,
+            scopes,
+            inspected,
+            traced,
+        }
     }
 
     /// Access the underlying world.
@@ -41,13 +59,176 @@ impl<'a> Vm<'a> {
         self.engine.world
     }
 
+    /// Enter a new scope that is exited when the returned guard is dropped.
+    pub fn scoped(&mut self) -> ScopeGuard<'_, 'a, Self> {
+        ScopeGuard::new(self)
+    }
+
+    /// Resolve a variable in code or in math.
+    ///
+    /// Warns if the variable is deprecated.
+    pub(crate) fn resolve(
+        &mut self,
+        var: &str,
+        span: Span,
+        in_math: bool,
+    ) -> SourceResult<Value> {
+        let (value, binding) = self
+            .scopes
+            .resolve(var, in_math)
+            .map_err(|err| self.scopes.hint_similar(var, in_math, err))
+            .at(span)?;
+
+        let value = value.clone();
+        self.observe_access(var, span);
+        if let Some(binding) = binding {
+            if let Some(deprecation) = binding.deprecation().cloned() {
+                let definition = binding.span().unwrap_or_else(Span::detached);
+                self.warn_deprecated(var, span, definition, &deprecation);
+            }
+        }
+
+        Ok(value)
+    }
+
+    /// Warn about a use of a deprecated variable.
+    ///
+    /// Only the first use of each deprecated binding in a compilation is
+    /// reported. Bindings are told apart by name and definition site.
+    fn warn_deprecated(
+        &mut self,
+        var: &str,
+        span: Span,
+        definition: Span,
+        deprecation: &Deprecation,
+    ) {
+        let mut warning =
+            warning!(span, "`{var}` is deprecated, {}", deprecation.message());
+        if let Some(since) = deprecation.since() {
+            warning.hint(eco_format!("it was deprecated in version {since}"));
+        }
+        self.engine.tracer.warn_once(var.into(), definition, warning);
+    }
+
     /// Define a variable in the current scope.
     pub fn define(&mut self, var: ast::Ident, value: impl IntoValue) {
-        let value = value.into_value();
-        if self.inspected == Some(var.span()) {
+        self.define_spanned(var.get().clone(), value.into_value(), var.span());
+    }
+
+    /// Define all variables of a scope in the current scope, in order.
+    ///
+    /// This is used for bindings that are collected up front, e.g. to check
+    /// them for duplicates.
+    pub(crate) fn define_all(&mut self, bindings: &Scope) {
+        for name in bindings.names() {
+            let Some(binding) = bindings.get_binding(name) else { continue };
+            let span = binding.span().unwrap_or_else(Span::detached);
+            self.define_spanned(name.clone(), binding.read().clone(), span);
+        }
+    }
+
+    /// Define a variable that was bound at the given span.
+    fn define_spanned(&mut self, name: EcoString, value: Value, span: Span) {
+        if self.inspected == Some(span) {
             self.trace(value.clone());
         }
-        self.scopes.top.define(var.get().clone(), value);
+        if self.world().library().lints.shadowing {
+            self.lint_shadowing(&name, span);
+        }
+        self.observe_define(&name, &value, span);
+        self.scopes.top.define_spanned(name, value, span);
+    }
+
+    /// Notify the scope hooks and the tracer about a definition.
+    fn observe_define(&mut self, name: &str, value: &Value, span: Span) {
+        if let Some(hooks) = self.scopes.hooks() {
+            hooks.on_define(name, value, span);
+        }
+        if self.is_traced(name) {
+            self.engine.tracer.trace_var(VarEvent::Define {
+                name: name.into(),
+                value: BindingSnapshot::summarize(value),
+                span,
+            });
+        }
+    }
+
+    /// Notify the scope hooks and the tracer about a read or mutation of a
+    /// variable.
+    pub(crate) fn observe_access(&mut self, name: &str, span: Span) {
+        if let Some(hooks) = self.scopes.hooks() {
+            hooks.on_access(name, span);
+        }
+        if self.is_traced(name) {
+            self.engine
+                .tracer
+                .trace_var(VarEvent::Access { name: name.into(), span });
+        }
+    }
+
+    /// Whether the definitions and accesses of a variable are traced.
+    fn is_traced(&self, name: &str) -> bool {
+        self.traced.as_ref().is_some_and(|patterns| {
+            patterns.iter().any(|pattern| matches_var_pattern(pattern, name))
+        })
+    }
+
+    /// Warn if defining a variable would shadow an existing one.
+    fn lint_shadowing(&mut self, name: &str, span: Span) {
+        if name.starts_with('_') || self.scopes.top.contains(name) {
+            return;
+        }
+
+        let hint = if let Some(shadowed) = self.scopes.shadowed(name) {
+            match self.locate(shadowed, span) {
+                Some(location) => {
+                    eco_format!("`{name}` was previously defined {location}")
+                }
+                None => eco_format!("`{name}` was previously defined in an outer scope"),
+            }
+        } else if self.scopes.bases.iter().any(|scope| scope.contains(name)) {
+            eco_format!("`{name}` is defined in a base scope")
+        } else if self
+            .scopes
+            .base
+            .is_some_and(|base| base.global.scope().contains(name))
+        {
+            eco_format!("`{name}` is defined in the standard library")
+        } else {
+            return;
+        };
+
+        self.engine.tracer.warn(
+            warning!(span, "variable `{name}` shadows an earlier definition")
+                .with_hint(hint)
+                .with_hint("prefix the name with an underscore to silence this warning"),
+        );
+    }
+
+    /// Warn about local bindings that were never used.
+    ///
+    /// Reports the bindings of all scopes exited so far.
+    pub(crate) fn lint_unused(&mut self) {
+        for (name, span) in self.scopes.take_unused() {
+            self.engine.tracer.warn(
+                warning!(span, "variable `{name}` is never used").with_hint(
+                    "prefix the name with an underscore to silence this warning",
+                ),
+            );
+        }
+    }
+
+    /// Describe where a span is located relative to another one, e.g. "at
+    /// line 3" when both are in the same file.
+    pub(crate) fn locate(&self, span: Span, relative_to: Span) -> Option<EcoString> {
+        let id = span.id()?;
+        let source = self.world().source(id).ok()?;
+        let line = source.byte_to_line(source.range(span)?.start)? + 1;
+        Some(if relative_to.id() == Some(id) {
+            eco_format!("at line {line}")
+        } else {
+            eco_format!("at {}:{line}", id.vpath().as_rootless_path().display())
+        })
     }
 
     /// Trace a value.
@@ -58,3 +239,9 @@ impl<'a> Vm<'a> {
             .value(value.clone(), self.context.styles().ok().map(|s| s.to_map()));
     }
 }
+
+impl<'a> AsMut<Scopes<'a>> for Vm<'a> {
+    fn as_mut(&mut self) -> &mut Scopes<'a> {
+        &mut self.scopes
+    }
+}
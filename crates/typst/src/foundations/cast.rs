@@ -337,6 +337,23 @@ impl CastInfo {
         msg.into()
     }
 
+    /// Describe what is expected, e.g. `integer or string`.
+    pub fn describe(&self) -> EcoString {
+        let mut parts = vec![];
+        self.walk(|info| match info {
+            CastInfo::Any => parts.push("anything".into()),
+            CastInfo::Value(value, _) => parts.push(value.repr()),
+            CastInfo::Type(ty) => parts.push(eco_format!("{ty}")),
+            CastInfo::Union(_) => {}
+        });
+
+        if parts.is_empty() {
+            return "nothing".into();
+        }
+
+        repr::separated_list(&parts, "or").into()
+    }
+
     /// Walk all contained non-union infos.
     pub fn walk<F>(&self, mut f: F)
     where
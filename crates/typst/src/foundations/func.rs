@@ -263,6 +263,16 @@ impl Func {
         }
     }
 
+    /// Whether this is a user-defined function, possibly with pre-applied
+    /// arguments.
+    pub fn is_closure(&self) -> bool {
+        match &self.repr {
+            Repr::Closure(_) => true,
+            Repr::With(with) => with.0.is_closure(),
+            _ => false,
+        }
+    }
+
     /// Call the function with the given context and arguments.
     pub fn call<A: IntoArgs>(
         &self,
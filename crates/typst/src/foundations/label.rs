@@ -1,6 +1,6 @@
 use ecow::{eco_format, EcoString};
 
-use crate::foundations::{func, scope, ty, Repr};
+use crate::foundations::{func, scope, ty, Repr, Str};
 use crate::util::PicoStr;
 
 /// A label for an element.
@@ -27,7 +27,9 @@ use crate::util::PicoStr;
 /// # Syntax
 /// This function also has dedicated syntax: You can create a label by enclosing
 /// its name in angle brackets. This works both in markup and code. A label's
-/// name can contain letters, numbers, `_`, `-`, `:`, and `.`.
+/// name can contain letters, numbers, `_`, `-`, `:`, and `.`, but must not
+/// start with `:` or `.`. Ending a label with one of them causes a warning, as
+/// a reference to it would leave that character to the surrounding text.
 ///
 /// Currently, labels can only be attached to elements in markup mode, not in
 /// code mode. This might change in the future.
@@ -62,6 +64,30 @@ impl Label {
     ) -> Label {
         Self(name)
     }
+
+    /// Creates a label from arbitrary text, e.g. a heading's title.
+    ///
+    /// The text is lowercased and each run of characters that can't appear
+    /// in an identifier is replaced with a single `-`. Leading characters
+    /// that can't start an identifier and trailing separators are dropped.
+    /// Returns `{none}` if nothing remains.
+    ///
+    /// ```example
+    /// #set heading(numbering: "1.")
+    /// #let title = "What's new?"
+    /// #heading(title) #label.sanitize(title)
+    /// See @what-s-new.
+    ///
+    /// #repr(label.sanitize("Über Größe")) \
+    /// #repr(label.sanitize("?!"))
+    /// ```
+    #[func]
+    pub fn sanitize(
+        /// The text to turn into a label.
+        text: Str,
+    ) -> Option<Label> {
+        crate::syntax::sanitize_ident(&text).map(|ident| Self::new(&ident))
+    }
 }
 
 impl Repr for Label {
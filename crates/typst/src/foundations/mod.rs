@@ -111,7 +111,7 @@ pub(super) fn define(global: &mut Scope, inputs: Dict) {
 	global.define_func::<assert>();
 	global.define_func::<eval>();
 	global.define_func::<style>();
-	global.define_module(calc::module());
+	global.define_lazy("calc", || calc::module().into_value());
 	global.define_module(sys::module(inputs));
 }
 
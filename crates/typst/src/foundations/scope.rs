@@ -1,14 +1,22 @@
-use std::fmt::{self, Debug, Formatter};
+use std::collections::HashSet;
+use std::fmt::{self, Debug, Display, Formatter};
 use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use ecow::{eco_format, EcoString};
 use indexmap::IndexMap;
+use once_cell::sync::OnceCell;
+use smallvec::SmallVec;
 
 use crate::diag::{bail, HintedStrResult, HintedString, StrResult};
 use crate::foundations::{
-    Element, Func, IntoValue, Module, NativeElement, NativeFunc, NativeFuncData,
-    NativeType, Type, Value,
+    Element, FromValue, Func, IntoValue, Module, NativeElement, NativeFunc,
+    NativeFuncData, NativeType, Repr, Type, Value,
 };
+use crate::syntax::{is_ident, is_keyword, Span};
 use crate::util::Static;
 use crate::Library;
 
@@ -22,14 +30,65 @@ pub struct Scopes<'a> {
     pub top: Scope,
     /// The stack of lower scopes.
     pub scopes: Vec<Scope>,
-    /// The standard library.
+    /// Read-only scopes that are searched after the local ones, nearest
+    /// first, e.g. a prelude or injected inputs.
+    pub bases: Vec<&'a Scope>,
+    /// The standard library, which is searched last.
     pub base: Option<&'a Library>,
+    /// Whether the local bindings defined at a span were accessed before
+    /// their scope was exited. Only present while usage is tracked.
+    usage: Option<IndexMap<Span, (EcoString, bool)>>,
+    /// Callbacks that observe the definitions and accesses of variables.
+    hooks: Option<&'a dyn ScopeHooks>,
 }
 
 impl<'a> Scopes<'a> {
     /// Create a new, empty hierarchy of scopes.
     pub fn new(base: Option<&'a Library>) -> Self {
-        Self { top: Scope::new(), scopes: vec![], base }
+        Self { base, ..Default::default() }
+    }
+
+    /// Create a new, empty hierarchy of scopes for a nested evaluation that
+    /// can read, but not modify, the variables visible through the view.
+    ///
+    /// All scopes of the view become read-only base scopes, which are
+    /// searched before any other bases of the view.
+    ///
+    /// The [hooks](Self::with_hooks) of the viewed scopes are kept.
+    pub fn nested(view: ScopesView<'a>) -> Self {
+        let bases: Vec<_> = view.layers().collect();
+        let mut scopes = Self::with_bases(&bases, view.scopes.base);
+        scopes.hooks = view.scopes.hooks;
+        scopes
+    }
+
+    /// Create a read-only view of these scopes.
+    pub fn view(&self) -> ScopesView<'_> {
+        ScopesView { scopes: self }
+    }
+
+    /// Create a new, empty hierarchy of scopes on top of the given base
+    /// scopes.
+    ///
+    /// The bases are searched in order once no local scope defines a name,
+    /// and the standard library is searched after all of them.
+    pub fn with_bases(bases: &[&'a Scope], base: Option<&'a Library>) -> Self {
+        Self { bases: bases.to_vec(), base, ..Default::default() }
+    }
+
+    /// Install callbacks that observe how variables are defined and used.
+    ///
+    /// The scopes themselves don't know where a variable is used, so the
+    /// hooks are invoked by the evaluator. Without hooks, this costs a single
+    /// check per definition and access.
+    pub fn with_hooks(mut self, hooks: &'a dyn ScopeHooks) -> Self {
+        self.hooks = Some(hooks);
+        self
+    }
+
+    /// The installed hooks, if any.
+    pub fn hooks(&self) -> Option<&'a dyn ScopeHooks> {
+        self.hooks
     }
 
     /// Enter a new scope.
@@ -41,304 +100,2080 @@ impl<'a> Scopes<'a> {
     ///
     /// This panics if no scope was entered.
     pub fn exit(&mut self) {
+        let exited =
+            std::mem::replace(&mut self.top, self.scopes.pop().expect("no pushed scope"));
+        self.record_usage(&exited, None);
+    }
+
+    /// Exit the topmost scope without recording the usage of its bindings.
+    ///
+    /// This is meant for scopes that are left because evaluation failed, as
+    /// bindings aren't unused just because evaluation never got to them.
+    ///
+    /// This panics if no scope was entered.
+    pub fn abandon(&mut self) {
         self.top = self.scopes.pop().expect("no pushed scope");
     }
 
-    /// Try to access a variable immutably.
-    pub fn get(&self, var: &str) -> HintedStrResult<&Value> {
-        std::iter::once(&self.top)
-            .chain(self.scopes.iter().rev())
-            .find_map(|scope| scope.get(var))
-            .or_else(|| {
-                self.base.and_then(|base| match base.global.scope().get(var) {
-                    Some(value) => Some(value),
-                    None if var == "std" => Some(&base.std),
-                    None => None,
-                })
-            })
-            .ok_or_else(|| unknown_variable(var))
+    /// The number of entered scopes that weren't exited yet.
+    ///
+    /// Together with [`restore`](Self::restore), this is a cheap alternative
+    /// to cloning the whole stack to get back to an earlier state.
+    pub fn depth(&self) -> usize {
+        self.scopes.len()
     }
 
-    /// Try to access a variable immutably in math.
-    pub fn get_in_math(&self, var: &str) -> HintedStrResult<&Value> {
-        std::iter::once(&self.top)
-            .chain(self.scopes.iter().rev())
-            .find_map(|scope| scope.get(var))
-            .or_else(|| {
-                self.base.and_then(|base| match base.math.scope().get(var) {
-                    Some(value) => Some(value),
-                    None if var == "std" => Some(&base.std),
-                    None => None,
-                })
-            })
-            .ok_or_else(|| unknown_variable(var))
+    /// Exit scopes until the given [depth](Self::depth) is reached again.
+    ///
+    /// Does nothing if the stack isn't deeper than that.
+    pub fn restore(&mut self, depth: usize) {
+        while self.depth() > depth {
+            self.exit();
+        }
     }
 
-    /// Try to access a variable mutably.
-    pub fn get_mut(&mut self, var: &str) -> HintedStrResult<&mut Value> {
-        std::iter::once(&mut self.top)
-            .chain(&mut self.scopes.iter_mut().rev())
-            .find_map(|scope| scope.get_mut(var))
-            .ok_or_else(|| {
-                match self.base.and_then(|base| base.global.scope().get(var)) {
-                    Some(_) => cannot_mutate_constant(var),
-                    _ if var == "std" => cannot_mutate_constant(var),
-                    _ => unknown_variable(var),
-                }
-            })?
+    /// Start tracking which local bindings are never accessed.
+    ///
+    /// From then on, the bindings of each exited scope are recorded for
+    /// [`take_unused`](Self::take_unused).
+    pub fn track_usage(&mut self) {
+        self.usage.get_or_insert_with(IndexMap::new);
     }
-}
 
-#[cold]
-fn cannot_mutate_constant(var: &str) -> HintedString {
-    eco_format!("cannot mutate a constant: {}", var).into()
-}
+    /// Record the bindings of the active scope as if it was exited, except
+    /// for the given name.
+    pub(crate) fn record_top(&mut self, except: Option<&str>) {
+        let top = std::mem::take(&mut self.top);
+        self.record_usage(&top, except);
+        self.top = top;
+    }
 
-/// The error message when a variable is not found.
-#[cold]
-fn unknown_variable(var: &str) -> HintedString {
-    let mut res = HintedString {
-        message: eco_format!("unknown variable: {}", var),
-        hints: vec![],
-    };
+    /// Record whether the local bindings of an exited scope were accessed.
+    ///
+    /// Only mutable bindings with a known definition site are considered,
+    /// except for those merged from another scope.
+    /// Names starting with an underscore are exempt. A definition that runs
+    /// multiple times, e.g. in a loop, counts as used if any of its bindings
+    /// was accessed.
+    fn record_usage(&mut self, scope: &Scope, except: Option<&str>) {
+        let Some(usage) = &mut self.usage else { return };
+        for (name, binding) in scope.map.iter() {
+            if binding.kind != Kind::Normal
+                || binding.merged
+                || binding.span.is_detached()
+                || name.starts_with('_')
+                || except == Some(name.as_str())
+            {
+                continue;
+            }
 
-    if matches!(var, "none" | "auto" | "false" | "true") {
-        res.hints.push(eco_format!(
-            "if you meant to use a literal, try adding a hash before it"
-        ));
-    } else if var.contains('-') {
-        res.hints.push(eco_format!(
-            "if you meant to use subtraction, try adding spaces around the minus sign",
-        ));
+            let (_, accessed) =
+                usage.entry(binding.span).or_insert_with(|| (name.clone(), false));
+            *accessed |= binding.is_accessed();
+        }
     }
 
-    res
-}
+    /// Take the names and definition spans of all recorded bindings that
+    /// were never accessed.
+    ///
+    /// The record is cleared, but usage remains tracked.
+    pub fn take_unused(&mut self) -> Vec<(EcoString, Span)> {
+        let Some(usage) = &mut self.usage else { return vec![] };
+        usage
+            .drain(..)
+            .filter(|(_, (_, accessed))| !accessed)
+            .map(|(span, (name, _))| (name, span))
+            .collect()
+    }
 
-/// A map from binding names to values.
-#[derive(Default, Clone)]
-pub struct Scope {
-    map: IndexMap<EcoString, Slot>,
-    deduplicate: bool,
-    category: Option<Category>,
-}
+    /// Find the definition that a new binding in the active scope would
+    /// shadow.
+    ///
+    /// Only definitions in lower scopes are considered, as a redefinition in
+    /// the active scope replaces the old binding instead. Returns the span of
+    /// the shadowed definition, which is detached if it isn't known.
+    pub(crate) fn shadowed(&self, var: &str) -> Option<Span> {
+        if self.top.contains(var) {
+            return None;
+        }
 
-impl Scope {
-    /// Create a new empty scope.
-    pub fn new() -> Self {
-        Default::default()
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.map.get(var))
+            .map(|binding| binding.span)
     }
 
-    /// Create a new scope with duplication prevention.
-    pub fn deduplicating() -> Self {
-        Self { deduplicate: true, ..Default::default() }
+    /// Remove a variable from the active scope, returning its value.
+    ///
+    /// Lower scopes and the standard library are never affected, so a
+    /// binding of the same name further out becomes visible again.
+    pub fn remove_top(&mut self, var: &str) -> Option<Value> {
+        self.top.remove(var)
     }
 
-    /// Enter a new category.
-    pub fn category(&mut self, category: Category) {
-        self.category = Some(category);
+    /// Summarize the bindings of all local scopes, e.g. for debugging.
+    ///
+    /// Bindings are listed from the active scope outwards. Those hidden by a
+    /// nearer definition of the same name are marked as shadowed. The
+    /// standard library is not included.
+    pub fn snapshot(&self) -> ScopeSnapshot {
+        let mut seen = HashSet::new();
+        let bindings = std::iter::once(&self.top)
+            .chain(self.scopes.iter().rev())
+            .enumerate()
+            .flat_map(|(layer, scope)| {
+                scope.map.iter().map(move |(name, binding)| (layer, name, binding))
+            })
+            .map(|(layer, name, binding)| {
+                let shadowed = !seen.insert(name);
+                BindingSnapshot::new(name, binding, layer, shadowed)
+            })
+            .collect();
+        ScopeSnapshot { bindings }
     }
 
-    /// Reset the category.
-    pub fn reset_category(&mut self) {
-        self.category = None;
+    /// Enter a new scope that is left when the returned guard is dropped.
+    pub fn scoped(&mut self) -> ScopeGuard<'_, 'a, Self> {
+        ScopeGuard::new(self)
     }
 
-    /// Bind a value to a name.
-    #[track_caller]
-    pub fn define(&mut self, name: impl Into<EcoString>, value: impl IntoValue) {
-        let name = name.into();
+    /// Try to access a variable immutably.
+    ///
+    /// [Math-only](Scope::define_math) bindings are skipped.
+    pub fn get(&self, var: &str) -> HintedStrResult<&Value> {
+        self.resolve(var, false).map(|(value, _)| value)
+    }
 
-        #[cfg(debug_assertions)]
-        if self.deduplicate && self.map.contains_key(&name) {
-            panic!("duplicate definition: {name}");
+    /// Try to access a variable immutably in code or in math, together with
+    /// its binding.
+    ///
+    /// This resolves variables like [`get`](Self::get) or
+    /// [`get_in_math`](Self::get_in_math). Only the `std` module has no
+    /// binding.
+    pub fn resolve(
+        &self,
+        var: &str,
+        in_math: bool,
+    ) -> HintedStrResult<(&Value, Option<&Binding>)> {
+        let binding = std::iter::once(&self.top)
+            .chain(self.scopes.iter().rev())
+            .chain(self.bases.iter().copied())
+            .chain(self.base.map(|base| {
+                if in_math {
+                    base.math.scope()
+                } else {
+                    base.global.scope()
+                }
+            }))
+            .find_map(|scope| {
+                scope.map.get(var).filter(|binding| in_math || !binding.math_only)
+            });
+
+        if let Some(binding) = binding {
+            if self.usage.is_some() {
+                binding.mark_accessed();
+            }
+            return Ok((binding.read(), Some(binding)));
         }
 
-        self.map
-            .insert(name, Slot::new(value.into_value(), Kind::Normal, self.category));
+        match self.base {
+            Some(base) if var == "std" => Ok((&base.std, None)),
+            _ => {
+                let mut error = unknown_variable(var);
+                if !in_math && self.is_math_only(var) {
+                    error
+                        .hints
+                        .push(eco_format!("`{var}` is only available in math mode"));
+                }
+                Err(error)
+            }
+        }
     }
 
-    /// Define a native function through a Rust type that shadows the function.
-    pub fn define_func<T: NativeFunc>(&mut self) {
-        let data = T::data();
-        self.define(data.name, Func::from(data));
+    /// Whether a variable resolves to a [math-only](Scope::define_math)
+    /// binding in math mode.
+    pub fn is_math_only(&self, var: &str) -> bool {
+        std::iter::once(&self.top)
+            .chain(self.scopes.iter().rev())
+            .chain(self.bases.iter().copied())
+            .chain(self.base.map(|base| base.math.scope()))
+            .find_map(|scope| scope.get_binding(var))
+            .is_some_and(Binding::is_math_only)
     }
 
-    /// Define a native function with raw function data.
-    pub fn define_func_with_data(&mut self, data: &'static NativeFuncData) {
-        self.define(data.name, Func::from(data));
+    /// Try to access a variable immutably in math.
+    pub fn get_in_math(&self, var: &str) -> HintedStrResult<&Value> {
+        self.resolve(var, true).map(|(value, _)| value)
     }
 
-    /// Define a native type.
-    pub fn define_type<T: NativeType>(&mut self) {
-        let data = T::data();
-        self.define(data.name, Type::from(data));
+    /// Try to access a variable and cast it to the given type.
+    ///
+    /// The variable is resolved like with [`get`](Self::get). See
+    /// [`Scope::get_as`] for an example.
+    pub fn get_as<T: FromValue>(&self, var: &str) -> Result<T, ScopeGetError> {
+        cast_var(var, self.get(var).ok())
     }
 
-    /// Define a native element.
-    pub fn define_elem<T: NativeElement>(&mut self) {
-        let data = T::data();
-        self.define(data.name, Element::from(data));
+    /// Try to access a variable and cast it to the given type, falling back
+    /// to a default if it isn't defined.
+    ///
+    /// A variable of the wrong type is still an error.
+    pub fn get_or<T: FromValue>(
+        &self,
+        var: &str,
+        default: T,
+    ) -> Result<T, ScopeGetError> {
+        match self.get(var) {
+            Ok(value) => cast_var(var, Some(value)),
+            Err(_) => Ok(default),
+        }
     }
 
-    /// Define a module.
-    pub fn define_module(&mut self, module: Module) {
-        self.define(module.name().clone(), module);
+    /// Capture the given variables in a flat scope, e.g. for a closure.
+    ///
+    /// Each name is resolved like with [`get`](Self::get) and bound to a copy
+    /// of its value, together with its definition span, documentation, and
+    /// deprecation. Names that can't be resolved are skipped. As the values
+    /// are copied, later mutations of the original variables aren't visible
+    /// through the captured ones.
+    pub fn capture<'n>(
+        &self,
+        names: impl IntoIterator<Item = &'n str>,
+        capturer: Capturer,
+    ) -> Scope {
+        let mut captures = Scope::new();
+        for name in names {
+            self.capture_into(&mut captures, name, capturer, false);
+        }
+        captures
     }
 
-    /// Define a captured, immutable binding.
-    pub fn define_captured(
-        &mut self,
-        var: impl Into<EcoString>,
-        value: impl IntoValue,
+    /// Capture the given variables in a flat scope, resolving them like with
+    /// [`get_in_math`](Self::get_in_math).
+    pub fn capture_in_math<'n>(
+        &self,
+        names: impl IntoIterator<Item = &'n str>,
+        capturer: Capturer,
+    ) -> Scope {
+        let mut captures = Scope::new();
+        for name in names {
+            self.capture_into(&mut captures, name, capturer, true);
+        }
+        captures
+    }
+
+    /// Capture a single variable into an existing scope.
+    ///
+    /// The metadata is taken from the binding the value was resolved from,
+    /// so in math, it comes from the math scope of the standard library.
+    pub(crate) fn capture_into(
+        &self,
+        captures: &mut Scope,
+        var: &str,
         capturer: Capturer,
+        in_math: bool,
     ) {
-        self.map.insert(
-            var.into(),
-            Slot::new(value.into_value(), Kind::Captured(capturer), self.category),
-        );
+        let Ok((value, origin)) = self.resolve(var, in_math) else { return };
+        let span = origin.and_then(Binding::span).unwrap_or_else(Span::detached);
+        captures.define_captured(var, value.clone(), capturer, span);
+        if let Some(docs) = origin.and_then(Binding::docs) {
+            captures.document(var, docs, origin.and_then(Binding::doc_category));
+        }
+        if let Some(deprecation) = origin.and_then(|origin| origin.deprecation.clone()) {
+            if let Some(binding) = captures.binding_mut(var) {
+                binding.deprecation = Some(deprecation);
+            }
+        }
     }
 
-    /// Try to access a variable immutably.
-    pub fn get(&self, var: &str) -> Option<&Value> {
-        self.map.get(var).map(Slot::read)
+    /// Collect all variables that are visible from the active scope.
+    ///
+    /// Each name appears once, together with the value it resolves to, i.e.
+    /// the nearest definition wins. Names are ordered from the active scope
+    /// down through the base scopes to the standard library and by definition
+    /// order within a scope.
+    pub fn visible_names(&self) -> Vec<(&EcoString, &Value)> {
+        self.bindings().collect()
     }
 
-    /// Try to access a variable mutably.
-    pub fn get_mut(&mut self, var: &str) -> Option<HintedStrResult<&mut Value>> {
-        self.map
-            .get_mut(var)
-            .map(Slot::write)
-            .map(|res| res.map_err(HintedString::from))
+    /// Iterate over all variables that are visible from the active scope.
+    ///
+    /// This is the lazy version of [`visible_names`](Self::visible_names):
+    /// Each name is yielded once, in the same order and with the value of its
+    /// nearest definition. Like in [`get`](Self::get),
+    /// [math-only](Scope::define_math) bindings are skipped.
+    pub fn bindings(&self) -> impl Iterator<Item = (&EcoString, &Value)> {
+        let mut seen = HashSet::new();
+        std::iter::once(&self.top)
+            .chain(self.scopes.iter().rev())
+            .chain(self.bases.iter().copied())
+            .chain(self.base.map(|base| base.global.scope()))
+            .flat_map(|scope| scope.map.iter())
+            .filter(move |(name, binding)| !binding.math_only && seen.insert(*name))
+            .map(|(name, binding)| (name, binding.read()))
     }
 
-    /// Get the category of a definition.
-    pub fn get_category(&self, var: &str) -> Option<Category> {
-        self.map.get(var)?.category
+    /// Try to access the binding of a variable, including its metadata.
+    ///
+    /// This resolves variables like [`get`](Self::get), so
+    /// [math-only](Scope::define_math) bindings are skipped. It does not
+    /// resolve the `std` module, which has no binding.
+    pub fn get_binding(&self, var: &str) -> Option<&Binding> {
+        std::iter::once(&self.top)
+            .chain(self.scopes.iter().rev())
+            .chain(self.bases.iter().copied())
+            .chain(self.base.map(|base| base.global.scope()))
+            .find_map(|scope| scope.get_binding(var).filter(|binding| !binding.math_only))
     }
 
-    /// Iterate over all definitions.
-    pub fn iter(&self) -> impl Iterator<Item = (&EcoString, &Value)> {
-        self.map.iter().map(|(k, v)| (k, v.read()))
+    /// Try to find a visible variable whose name is similar to the given one.
+    ///
+    /// Names that only differ in casing or that are within a small edit
+    /// distance are considered. The nearest scope wins among equally similar
+    /// names. This is meant for error hints and only runs for failed lookups.
+    /// It stays cheap by considering a bounded number of candidates.
+    ///
+    /// [Math-only](Scope::define_math) bindings are skipped.
+    pub fn suggest(&self, var: &str) -> Option<EcoString> {
+        self.suggest_impl(var, false)
     }
-}
 
-impl Debug for Scope {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        f.write_str("Scope ")?;
-        f.debug_map()
-            .entries(self.map.iter().map(|(k, v)| (k, v.read())))
-            .finish()
+    /// Try to find a variable visible in math whose name is similar to the
+    /// given one.
+    ///
+    /// This works like [`suggest`](Self::suggest), but considers the math
+    /// scope of the standard library instead of the global one.
+    pub fn suggest_in_math(&self, var: &str) -> Option<EcoString> {
+        self.suggest_impl(var, true)
     }
-}
 
-impl Hash for Scope {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        state.write_usize(self.map.len());
-        for item in &self.map {
-            item.hash(state);
+    /// Try to find a similar variable in code or in math.
+    fn suggest_impl(&self, var: &str, in_math: bool) -> Option<EcoString> {
+        suggest_among(
+            std::iter::once(&self.top)
+                .chain(self.scopes.iter().rev())
+                .chain(self.bases.iter().copied())
+                .chain(self.base.map(|base| {
+                    if in_math {
+                        base.math.scope()
+                    } else {
+                        base.global.scope()
+                    }
+                }))
+                .flat_map(|scope| scope.map.iter())
+                .filter(|(_, binding)| in_math || !binding.math_only)
+                .map(|(name, _)| name),
+            var,
+        )
+    }
+
+    /// Resolve a dotted path like `calc.sin` without evaluating anything.
+    ///
+    /// The first segment is looked up like a variable and each further one in
+    /// the module the previous segment resolved to. Bindings are read without
+    /// marking them as accessed, so this is safe to use in tooling.
+    pub fn get_path(&self, path: &str) -> Result<ResolvedPath<'_>, PathError> {
+        let mut segments = path.split('.');
+        let first = segments.next().unwrap_or_default();
+        let start = match self.get_binding(first) {
+            Some(binding) => (binding.read(), Some(binding)),
+            None => match self.base {
+                Some(base) if first == "std" => (&base.std, None),
+                _ => return Err(PathError::Unknown(first.into())),
+            },
+        };
+        resolve_path(first, start, segments)
+    }
+
+    /// Add a "did you mean" hint to an unknown variable error if there is a
+    /// similarly named variable.
+    pub(crate) fn hint_similar(
+        &self,
+        var: &str,
+        in_math: bool,
+        mut error: HintedString,
+    ) -> HintedString {
+        if let Some(name) = self.suggest_impl(var, in_math) {
+            error.hints.push(eco_format!("did you mean `{name}`?"));
         }
-        self.deduplicate.hash(state);
-        self.category.hash(state);
+        error
     }
-}
 
-/// Defines the associated scope of a Rust type.
-pub trait NativeScope {
-    /// The constructor function for the type, if any.
-    fn constructor() -> Option<&'static NativeFuncData>;
+    /// Whether a variable resolves to a binding that was defined through
+    /// [`Scope::define_const`].
+    ///
+    /// This is decided by the binding the variable resolves to, wherever it
+    /// lives, so a local `let` shadowing a constant is not constant. Note
+    /// that bindings in the base scopes and the standard library can't be
+    /// mutated through [`get_mut`](Self::get_mut) either way.
+    pub fn is_const(&self, var: &str) -> bool {
+        match self.get_binding(var) {
+            Some(binding) => binding.is_const(),
+            None => var == "std" && self.base.is_some(),
+        }
+    }
 
-    /// Get the associated scope for the type.
-    fn scope() -> Scope;
+    /// Try to access a variable mutably.
+    pub fn get_mut(&mut self, var: &str) -> Result<&mut Value, MutError> {
+        let tracked = self.usage.is_some();
+        std::iter::once(&mut self.top)
+            .chain(&mut self.scopes.iter_mut().rev())
+            .find_map(|scope| scope.binding_mut(var).filter(|binding| !binding.math_only))
+            .map(|binding| {
+                if tracked {
+                    binding.mark_accessed();
+                }
+                binding.write(var)
+            })
+            .ok_or_else(|| {
+                // Only captures the fields that the local scopes don't
+                // borrow.
+                let definition = self
+                    .bases
+                    .iter()
+                    .copied()
+                    .chain(self.base.map(|base| base.global.scope()))
+                    .find_map(|scope| {
+                        scope.map.get(var).filter(|binding| !binding.math_only)
+                    })
+                    .map(|binding| binding.span)
+                    .or_else(|| {
+                        (var == "std" && self.base.is_some()).then(Span::detached)
+                    });
+                match definition {
+                    Some(span) => MutError::Builtin(var.into(), span),
+                    None => MutError::Unknown(var.into()),
+                }
+            })?
+    }
 }
 
-/// A slot where a value is stored.
-#[derive(Clone, Hash)]
-struct Slot {
-    /// The stored value.
-    value: Value,
-    /// The kind of slot, determines how the value can be accessed.
-    kind: Kind,
-    /// The category of the slot.
-    category: Option<Category>,
+/// Callbacks that observe how variables are defined and used through
+/// [`Scopes`], e.g. for a debugger.
+pub trait ScopeHooks {
+    /// Called when a variable is defined, with its value.
+    fn on_define(&self, name: &str, value: &Value, span: Span);
+
+    /// Called when a variable is read or about to be mutated.
+    fn on_access(&self, name: &str, span: Span);
 }
 
-/// The different kinds of slots.
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
-enum Kind {
-    /// A normal, mutable binding.
-    Normal,
-    /// A captured copy of another variable.
-    Captured(Capturer),
+impl Debug for dyn ScopeHooks + '_ {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.pad("ScopeHooks(..)")
+    }
 }
 
-/// What the variable was captured by.
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
-pub enum Capturer {
-    /// Captured by a function / closure.
-    Function,
-    /// Captured by a context expression.
-    Context,
+/// A read-only view of a stack of scopes.
+///
+/// The view can be handed to code that should see the variables of an
+/// environment without being able to define or mutate any of them, e.g. a
+/// nested evaluation through [`Scopes::nested`] or
+/// [`eval_string_in`](crate::eval::eval_string_in). It is cheap to copy.
+///
+/// The evaluator doesn't create views itself. They are meant for embedders
+/// that evaluate code within an environment.
+#[derive(Debug, Copy, Clone)]
+pub struct ScopesView<'a> {
+    scopes: &'a Scopes<'a>,
 }
 
-impl Slot {
-    /// Create a new slot.
-    fn new(value: Value, kind: Kind, category: Option<Category>) -> Self {
-        Self { value, kind, category }
+impl<'a> ScopesView<'a> {
+    /// Try to access a variable immutably.
+    pub fn get(&self, var: &str) -> HintedStrResult<&'a Value> {
+        self.scopes.get(var)
     }
 
-    /// Read the value.
-    fn read(&self) -> &Value {
-        &self.value
+    /// Try to access a variable immutably in math.
+    pub fn get_in_math(&self, var: &str) -> HintedStrResult<&'a Value> {
+        self.scopes.get_in_math(var)
     }
 
-    /// Try to write to the value.
-    fn write(&mut self) -> StrResult<&mut Value> {
-        match self.kind {
-            Kind::Normal => Ok(&mut self.value),
-            Kind::Captured(capturer) => {
-                bail!(
-                    "variables from outside the {} are \
-                     read-only and cannot be modified",
-                    match capturer {
-                        Capturer::Function => "function",
-                        Capturer::Context => "context expression",
-                    }
-                )
-            }
-        }
+    /// Collect all variables that are visible through the view.
+    ///
+    /// See [`Scopes::visible_names`].
+    pub fn visible_names(&self) -> Vec<(&'a EcoString, &'a Value)> {
+        self.scopes.visible_names()
     }
-}
 
-/// A group of related definitions.
-#[derive(Copy, Clone, Eq, PartialEq, Hash)]
-pub struct Category(Static<CategoryData>);
+    /// Try to find a visible variable whose name is similar to the given one.
+    pub fn suggest(&self, var: &str) -> Option<EcoString> {
+        self.scopes.suggest(var)
+    }
 
-impl Category {
-    /// Create a new category from raw data.
-    pub const fn from_data(data: &'static CategoryData) -> Self {
-        Self(Static(data))
+    /// Try to find a variable visible in math whose name is similar to the
+    /// given one.
+    pub fn suggest_in_math(&self, var: &str) -> Option<EcoString> {
+        self.scopes.suggest_in_math(var)
     }
 
-    /// The category's name.
-    pub fn name(&self) -> &'static str {
-        self.0.name
+    /// Summarize the bindings of all local scopes of the view.
+    pub fn snapshot(&self) -> ScopeSnapshot {
+        self.scopes.snapshot()
     }
 
-    /// The type's title case name, for use in documentation (e.g. `String`).
-    pub fn title(&self) -> &'static str {
-        self.0.title
+    /// The scopes of the view without the standard library, from the
+    /// nearest to the farthest.
+    fn layers(self) -> impl Iterator<Item = &'a Scope> {
+        let scopes = self.scopes;
+        std::iter::once(&scopes.top)
+            .chain(scopes.scopes.iter().rev())
+            .chain(scopes.bases.iter().copied())
     }
+}
 
-    /// Documentation for the category.
-    pub fn docs(&self) -> &'static str {
-        self.0.docs
+impl<'a> From<&'a Scopes<'a>> for ScopesView<'a> {
+    fn from(scopes: &'a Scopes<'a>) -> Self {
+        Self { scopes }
     }
 }
 
-impl Debug for Category {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(f, "Category({})", self.name())
+impl<'a> AsMut<Scopes<'a>> for Scopes<'a> {
+    fn as_mut(&mut self) -> &mut Scopes<'a> {
+        self
+    }
+}
+
+/// Enters a new scope on creation and leaves it again when dropped.
+///
+/// This keeps the stack of scopes balanced even if evaluation bails out
+/// early, e.g. through the `?` operator. The guard dereferences to whatever
+/// holds the scopes, so work can continue through it.
+///
+/// The usage of the scope's bindings is only recorded if the guard is
+/// [finished](Self::finish). Otherwise, the scope is
+/// [abandoned](Scopes::abandon).
+pub struct ScopeGuard<'g, 'a, T: AsMut<Scopes<'a>>> {
+    inner: &'g mut T,
+    finished: bool,
+    marker: PhantomData<Scopes<'a>>,
+}
+
+impl<'g, 'a, T: AsMut<Scopes<'a>>> ScopeGuard<'g, 'a, T> {
+    /// Enter a new scope in the given scopes.
+    pub fn new(inner: &'g mut T) -> Self {
+        inner.as_mut().enter();
+        Self { inner, finished: false, marker: PhantomData }
+    }
+
+    /// Exit the scope after evaluation in it succeeded.
+    pub fn finish(mut self) {
+        self.finished = true;
+    }
+}
+
+impl<'a, T: AsMut<Scopes<'a>>> Deref for ScopeGuard<'_, 'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.inner
+    }
+}
+
+impl<'a, T: AsMut<Scopes<'a>>> DerefMut for ScopeGuard<'_, 'a, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.inner
+    }
+}
+
+impl<'a, T: AsMut<Scopes<'a>>> Drop for ScopeGuard<'_, 'a, T> {
+    fn drop(&mut self) {
+        let scopes = self.inner.as_mut();
+        if self.finished {
+            scopes.exit();
+        } else {
+            scopes.abandon();
+        }
+    }
+}
+
+/// Why a variable couldn't be accessed mutably.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum MutError {
+    /// The variable isn't defined anywhere.
+    Unknown(EcoString),
+    /// The variable is defined in a read-only base scope or the standard
+    /// library. Holds where it was defined, which is detached if it isn't
+    /// known.
+    Builtin(EcoString, Span),
+    /// The variable is defined, but as a constant. Holds where the constant
+    /// was defined, which is detached if it isn't known.
+    Constant(EcoString, Span),
+    /// The variable was captured from outside of a function or context
+    /// expression.
+    Captured(EcoString, Capturer),
+}
+
+impl MutError {
+    /// The name of the variable that couldn't be mutated.
+    pub fn var(&self) -> &EcoString {
+        match self {
+            Self::Unknown(var)
+            | Self::Builtin(var, _)
+            | Self::Constant(var, _)
+            | Self::Captured(var, _) => var,
+        }
+    }
+
+    /// Where the variable that couldn't be mutated was defined, if known.
+    pub fn definition(&self) -> Option<Span> {
+        match self {
+            Self::Builtin(_, span) | Self::Constant(_, span) if !span.is_detached() => {
+                Some(*span)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl From<MutError> for HintedString {
+    fn from(err: MutError) -> Self {
+        match err {
+            MutError::Unknown(var) => unknown_variable(&var),
+            MutError::Builtin(var, _) => HintedString {
+                message: eco_format!("cannot mutate a constant: {var}"),
+                hints: vec![eco_format!(
+                    "shadow it with `let` to define a mutable variable of the same name"
+                )],
+            },
+            MutError::Constant(var, _) => HintedString {
+                message: eco_format!("cannot mutate a constant: {var}"),
+                hints: vec![eco_format!("use a different name for a mutable variable")],
+            },
+            MutError::Captured(_, capturer) => HintedString {
+                message: eco_format!(
+                    "variables from outside the {} are \
+                     read-only and cannot be modified",
+                    match capturer {
+                        Capturer::Function => "function",
+                        Capturer::Context => "context expression",
+                    }
+                ),
+                hints: vec![eco_format!("shadow it with `let` to create a mutable copy")],
+            },
+        }
+    }
+}
+
+/// Why a variable couldn't be accessed as a value of a specific type.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum ScopeGetError {
+    /// The variable isn't defined.
+    Unknown(EcoString),
+    /// The variable is defined, but its value has the wrong type.
+    WrongType {
+        /// The name of the variable.
+        var: EcoString,
+        /// A description of the expected types, e.g. `integer or string`.
+        expected: EcoString,
+        /// The type of the value that was found.
+        found: Type,
+    },
+    /// The variable has a fitting type, but its value can't be converted,
+    /// e.g. because an integer is out of range.
+    Invalid {
+        /// The name of the variable.
+        var: EcoString,
+        /// Why the value can't be converted.
+        message: EcoString,
+    },
+}
+
+impl ScopeGetError {
+    /// The name of the variable that couldn't be accessed.
+    pub fn var(&self) -> &EcoString {
+        match self {
+            Self::Unknown(var)
+            | Self::WrongType { var, .. }
+            | Self::Invalid { var, .. } => var,
+        }
+    }
+}
+
+impl Display for ScopeGetError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Unknown(var) => write!(f, "unknown variable: {var}"),
+            Self::WrongType { var, expected, found } => {
+                write!(f, "expected {expected} for `{var}`, found {found}")
+            }
+            Self::Invalid { var, message } => {
+                write!(f, "invalid value for `{var}`: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScopeGetError {}
+
+impl From<ScopeGetError> for HintedString {
+    fn from(err: ScopeGetError) -> Self {
+        match err {
+            ScopeGetError::Unknown(var) => unknown_variable(&var),
+            err => eco_format!("{err}").into(),
+        }
+    }
+}
+
+/// Cast the value of a variable, if it is defined.
+fn cast_var<T: FromValue>(var: &str, value: Option<&Value>) -> Result<T, ScopeGetError> {
+    let Some(value) = value else {
+        return Err(ScopeGetError::Unknown(var.into()));
+    };
+
+    if !T::castable(value) {
+        return Err(ScopeGetError::WrongType {
+            var: var.into(),
+            expected: T::input().describe(),
+            found: value.ty(),
+        });
+    }
+
+    T::from_value(value.clone())
+        .map_err(|message| ScopeGetError::Invalid { var: var.into(), message })
+}
+
+/// The error message when a variable is not found.
+#[cold]
+fn unknown_variable(var: &str) -> HintedString {
+    let mut res = HintedString {
+        message: eco_format!("unknown variable: {}", var),
+        hints: vec![],
+    };
+
+    if matches!(var, "none" | "auto" | "false" | "true") {
+        res.hints.push(eco_format!(
+            "if you meant to use a literal, try adding a hash before it"
+        ));
+    } else if var.contains('-') {
+        res.hints.push(eco_format!(
+            "if you meant to use subtraction, try adding spaces around the minus sign",
+        ));
+    }
+
+    res
+}
+
+/// Find the name most similar to the given one for a "did you mean" hint.
+///
+/// Names that only differ in casing or that are within a small edit distance
+/// are considered. The first one wins among equally similar names.
+fn suggest_among<'n>(
+    names: impl Iterator<Item = &'n EcoString>,
+    var: &str,
+) -> Option<EcoString> {
+    let max = max_distance(var);
+    let lower = var.to_lowercase();
+    let len = var.chars().count();
+
+    let mut best: Option<(usize, &EcoString)> = None;
+    for name in names.take(MAX_SUGGESTION_CANDIDATES) {
+        if name == var || name.chars().count().abs_diff(len) > max {
+            continue;
+        }
+
+        let distance =
+            if name.to_lowercase() == lower { 0 } else { edit_distance(name, var) };
+
+        if distance <= max && best.map_or(true, |(best, _)| distance < best) {
+            best = Some((distance, name));
+        }
+    }
+
+    best.map(|(_, name)| name.clone())
+}
+
+/// Resolve the remaining segments of a dotted path, starting from the value
+/// and binding of its first segment.
+fn resolve_path<'a, 'p>(
+    first: &'p str,
+    (value, binding): (&'a Value, Option<&'a Binding>),
+    segments: impl Iterator<Item = &'p str>,
+) -> Result<ResolvedPath<'a>, PathError> {
+    let mut resolved = ResolvedPath { value, bindings: vec![binding] };
+    let mut parent = first;
+    for segment in segments {
+        let Value::Module(module) = resolved.value else {
+            return Err(PathError::NotModule {
+                segment: parent.into(),
+                ty: resolved.value.ty(),
+            });
+        };
+
+        let Some(binding) = module.scope().get_binding(segment) else {
+            return Err(PathError::UnknownField {
+                module: module.name().clone(),
+                field: segment.into(),
+                similar: module.scope().suggest(segment),
+            });
+        };
+
+        resolved.value = binding.read();
+        resolved.bindings.push(Some(binding));
+        parent = segment;
+    }
+    Ok(resolved)
+}
+
+/// The result of resolving a dotted path through [`Scopes::get_path`] or
+/// [`Scope::get_path`].
+#[derive(Debug, Clone)]
+pub struct ResolvedPath<'a> {
+    /// The value the path resolves to.
+    pub value: &'a Value,
+    /// The binding of each segment. Only the `std` module has none.
+    pub bindings: Vec<Option<&'a Binding>>,
+}
+
+impl<'a> ResolvedPath<'a> {
+    /// The binding of the last segment.
+    pub fn binding(&self) -> Option<&'a Binding> {
+        self.bindings.last().copied().flatten()
+    }
+
+    /// Where each segment was defined, if known.
+    pub fn spans(&self) -> impl Iterator<Item = Option<Span>> + '_ {
+        self.bindings.iter().map(|binding| binding.and_then(Binding::span))
+    }
+}
+
+/// Why a dotted path couldn't be resolved.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum PathError {
+    /// The first segment isn't a visible variable.
+    Unknown(EcoString),
+    /// A module doesn't contain the next segment.
+    UnknownField {
+        /// The name of the module.
+        module: EcoString,
+        /// The missing segment.
+        field: EcoString,
+        /// A similarly named definition in the module, if any.
+        similar: Option<EcoString>,
+    },
+    /// A segment that is followed by further ones isn't a module.
+    NotModule {
+        /// The offending segment.
+        segment: EcoString,
+        /// The type of the segment's value.
+        ty: Type,
+    },
+}
+
+impl From<PathError> for HintedString {
+    fn from(err: PathError) -> Self {
+        match err {
+            PathError::Unknown(var) => unknown_variable(&var),
+            PathError::UnknownField { module, field, similar } => HintedString {
+                message: eco_format!("module `{module}` does not contain `{field}`"),
+                hints: similar
+                    .map(|name| eco_format!("did you mean `{name}`?"))
+                    .into_iter()
+                    .collect(),
+            },
+            PathError::NotModule { segment, ty } => HintedString {
+                message: eco_format!("`{segment}` is of type {ty}, not a module"),
+                hints: vec![],
+            },
+        }
+    }
+}
+
+/// The maximum number of names that are considered for a suggestion.
+const MAX_SUGGESTION_CANDIDATES: usize = 1024;
+
+/// The maximum edit distance at which a name is suggested for `var`.
+fn max_distance(var: &str) -> usize {
+    match var.chars().count() {
+        0..=2 => 0,
+        3..=5 => 1,
+        _ => 2,
+    }
+}
+
+/// The number of single-character insertions, deletions, substitutions, and
+/// transpositions of adjacent characters needed to turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut before = vec![0; b.len() + 1];
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        curr[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            curr[j] = (prev[j] + 1).min(curr[j - 1] + 1).min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                curr[j] = curr[j].min(before[j - 2] + 1);
+            }
+        }
+        std::mem::swap(&mut before, &mut prev);
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b.len()]
+}
+
+/// Ensure that a name can be referenced from Typst code.
+///
+/// This uses the lexer's rules, so a valid name is always lexed as a single
+/// identifier.
+fn check_name(name: &str) -> StrResult<()> {
+    if is_keyword(name) {
+        bail!("`{name}` is a keyword and can't be used as a name");
+    }
+    if !is_ident(name) {
+        bail!("{} is not a valid identifier", name.repr());
+    }
+    Ok(())
+}
+
+/// Panic in debug builds if a name can't be referenced from Typst code.
+#[track_caller]
+fn debug_check_name(name: &str) {
+    #[cfg(debug_assertions)]
+    if let Err(message) = check_name(name) {
+        panic!("invalid definition: {message}");
+    }
+}
+
+/// A map from binding names to values.
+///
+/// Bindings are kept in definition order. Redefining an existing name
+/// replaces its value, but keeps the position of the original definition.
+///
+/// The bindings are reference-counted, so that cloning a scope is cheap. They
+/// are only copied once a clone is modified.
+#[derive(Default, Clone)]
+pub struct Scope {
+    map: Arc<Bindings>,
+    deduplicate: bool,
+    category: Option<Category>,
+}
+
+impl Scope {
+    /// Create a new empty scope.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Create a new scope with duplication prevention.
+    pub fn deduplicating() -> Self {
+        Self { deduplicate: true, ..Default::default() }
+    }
+
+    /// Enter a new category.
+    pub fn category(&mut self, category: Category) {
+        self.category = Some(category);
+    }
+
+    /// Reset the category.
+    pub fn reset_category(&mut self) {
+        self.category = None;
+    }
+
+    /// Bind a value to a name.
+    ///
+    /// If the name is already bound, the value is replaced in place and the
+    /// previous one is returned.
+    ///
+    /// The name must be an identifier and not a keyword, which is checked in
+    /// debug builds. Use [`define_checked`](Self::define_checked) for names
+    /// that aren't known to be valid, like those of imported modules, and
+    /// [`define_raw`](Self::define_raw) for the rare names that can only be
+    /// reached in other ways, like `sym.in`.
+    #[track_caller]
+    pub fn define(
+        &mut self,
+        name: impl Into<EcoString>,
+        value: impl IntoValue,
+    ) -> Option<Value> {
+        self.insert(name.into(), value.into_value(), Kind::Normal, Span::detached())
+    }
+
+    /// Bind a value to a name, failing if the name isn't a valid identifier
+    /// or is a keyword.
+    ///
+    /// Returns the previous value if the name was already bound.
+    #[track_caller]
+    pub fn define_checked(
+        &mut self,
+        name: impl Into<EcoString>,
+        value: impl IntoValue,
+    ) -> StrResult<Option<Value>> {
+        let name = name.into();
+        check_name(&name)?;
+        Ok(self.insert(name, value.into_value(), Kind::Normal, Span::detached()))
+    }
+
+    /// Bind a value to any name, even one that can't be written as an
+    /// identifier.
+    ///
+    /// Such a binding can't be referenced from Typst code as a variable, but
+    /// is still visible to iteration and [`get`](Self::get). Keywords are
+    /// reachable as module members and in math, e.g. `sym.in` and `$in$`.
+    #[track_caller]
+    pub fn define_raw(
+        &mut self,
+        name: impl Into<EcoString>,
+        value: impl IntoValue,
+    ) -> Option<Value> {
+        self.insert_raw(name.into(), value.into_value(), Kind::Normal, Span::detached())
+    }
+
+    /// Bind a value to a name and remember where the binding was defined.
+    ///
+    /// Returns the previous value if the name was already bound.
+    #[track_caller]
+    pub fn define_spanned(
+        &mut self,
+        name: impl Into<EcoString>,
+        value: impl IntoValue,
+        span: Span,
+    ) -> Option<Value> {
+        self.insert(name.into(), value.into_value(), Kind::Normal, span)
+    }
+
+    /// Bind a value to a name unless it is already bound.
+    ///
+    /// If the name is taken, the existing binding is kept and the value is
+    /// handed back.
+    pub fn try_define(
+        &mut self,
+        name: impl Into<EcoString>,
+        value: impl IntoValue,
+    ) -> Result<(), Value> {
+        self.try_define_spanned(name, value, Span::detached())
+    }
+
+    /// Bind a value to a name unless it is already bound, remembering where
+    /// the binding was defined.
+    pub fn try_define_spanned(
+        &mut self,
+        name: impl Into<EcoString>,
+        value: impl IntoValue,
+        span: Span,
+    ) -> Result<(), Value> {
+        let name = name.into();
+        let value = value.into_value();
+        if self.map.contains_key(&name) {
+            return Err(value);
+        }
+        debug_check_name(&name);
+        let binding = Binding::new(value, Kind::Normal, self.category, span);
+        self.bindings_mut().insert(name, binding);
+        Ok(())
+    }
+
+    /// Bind a value to a name, together with documentation for it.
+    ///
+    /// The category is a free-form name used to group the definition, e.g. in
+    /// autocompletion. It is independent of the scope's current
+    /// [category](Self::category).
+    #[track_caller]
+    pub fn define_with_docs(
+        &mut self,
+        name: impl Into<EcoString>,
+        value: impl IntoValue,
+        docs: &str,
+        category: Option<&str>,
+    ) {
+        let name = name.into();
+        self.define(name.clone(), value);
+        self.document(&name, docs, category);
+    }
+
+    /// Attach documentation to an existing definition.
+    ///
+    /// Replaces earlier documentation of the definition. Does nothing if the
+    /// variable isn't defined in this scope.
+    pub fn document(&mut self, var: &str, docs: &str, category: Option<&str>) {
+        if let Some(binding) = self.binding_mut(var) {
+            binding.docs = Some(Arc::new(Docs {
+                docs: docs.into(),
+                category: category.map(Into::into),
+            }));
+        }
+    }
+
+    /// Bind a value to a name that can't be mutated afterwards.
+    #[track_caller]
+    pub fn define_const(&mut self, name: impl Into<EcoString>, value: impl IntoValue) {
+        self.insert(name.into(), value.into_value(), Kind::Constant, Span::detached());
+    }
+
+    /// Bind a value to a name that can't be mutated afterwards and remember
+    /// where the binding was defined.
+    ///
+    /// Attempts to mutate the binding report the definition site. Returns the
+    /// previous value if the name was already bound.
+    #[track_caller]
+    pub fn define_const_spanned(
+        &mut self,
+        name: impl Into<EcoString>,
+        value: impl IntoValue,
+        span: Span,
+    ) -> Option<Value> {
+        self.insert(name.into(), value.into_value(), Kind::Constant, span)
+    }
+
+    /// Bind a value to a name that still works, but shouldn't be used anymore.
+    ///
+    /// Reading the variable through an identifier warns once per compilation
+    /// with the given message, which should say what to use instead. The
+    /// `since` is the version in which the binding was deprecated.
+    #[track_caller]
+    pub fn define_deprecated(
+        &mut self,
+        name: impl Into<EcoString>,
+        value: impl IntoValue,
+        message: &str,
+        since: Option<&str>,
+    ) {
+        let name = name.into();
+        self.define(name.clone(), value);
+        if let Some(binding) = self.binding_mut(&name) {
+            binding.deprecation = Some(Arc::new(Deprecation {
+                message: message.into(),
+                since: since.map(Into::into),
+            }));
+        }
+    }
+
+    /// Bind a value to a name that only identifiers in math mode resolve.
+    ///
+    /// In code and markup, the variable is treated as if it wasn't defined.
+    /// Field access on a module is not affected, so that `math.thin` keeps
+    /// working.
+    #[track_caller]
+    pub fn define_math(&mut self, name: impl Into<EcoString>, value: impl IntoValue) {
+        let name = name.into();
+        self.insert(name.clone(), value.into_value(), Kind::Normal, Span::detached());
+        if let Some(binding) = self.binding_mut(&name) {
+            binding.math_only = true;
+        }
+    }
+
+    /// Bind a lazily computed value to a name.
+    ///
+    /// The function runs on the first access of the value and its result is
+    /// cached from then on. This is meant for expensive definitions in the
+    /// standard library, so the binding is constant, like one created by
+    /// [`define_const`](Self::define_const).
+    ///
+    /// Iteration, snapshots, and `Debug` output see the computed value,
+    /// forcing the binding if needed. Comparison and hashing instead look at
+    /// the initializer and never force the binding, so a lazy binding is
+    /// never equal to an eager one.
+    #[track_caller]
+    pub fn define_lazy(&mut self, name: impl Into<EcoString>, init: fn() -> Value) {
+        let name = name.into();
+
+        debug_check_name(&name);
+
+        #[cfg(debug_assertions)]
+        if self.deduplicate && self.map.contains_key(&name) {
+            panic!("duplicate definition: {name}");
+        }
+
+        self.bindings_mut().insert(name, Binding::lazy(init, self.category));
+    }
+
+    /// Insert a binding of the given kind, returning the previous value.
+    #[track_caller]
+    fn insert(
+        &mut self,
+        name: EcoString,
+        value: Value,
+        kind: Kind,
+        span: Span,
+    ) -> Option<Value> {
+        debug_check_name(&name);
+        self.insert_raw(name, value, kind, span)
+    }
+
+    /// Insert a binding without validating the name.
+    #[track_caller]
+    fn insert_raw(
+        &mut self,
+        name: EcoString,
+        value: Value,
+        kind: Kind,
+        span: Span,
+    ) -> Option<Value> {
+        #[cfg(debug_assertions)]
+        if self.deduplicate && self.map.contains_key(&name) {
+            panic!("duplicate definition: {name}");
+        }
+
+        self.bindings_mut()
+            .insert(name, Binding::new(value, kind, self.category, span))
+            .map(Binding::into_value)
+    }
+
+    /// Define a native function through a Rust type that shadows the function.
+    pub fn define_func<T: NativeFunc>(&mut self) {
+        let data = T::data();
+        self.define_const(data.name, Func::from(data));
+    }
+
+    /// Define a native function with raw function data.
+    pub fn define_func_with_data(&mut self, data: &'static NativeFuncData) {
+        self.define_const(data.name, Func::from(data));
+    }
+
+    /// Define a native type.
+    pub fn define_type<T: NativeType>(&mut self) {
+        let data = T::data();
+        self.define_const(data.name, Type::from(data));
+    }
+
+    /// Define a native element.
+    pub fn define_elem<T: NativeElement>(&mut self) {
+        let data = T::data();
+        self.define_const(data.name, Element::from(data));
+    }
+
+    /// Define a module.
+    pub fn define_module(&mut self, module: Module) {
+        self.define_const(module.name().clone(), module);
+    }
+
+    /// Define a captured, immutable binding.
+    ///
+    /// The span should point to the definition of the original variable, so
+    /// that it is preserved across the capture.
+    pub fn define_captured(
+        &mut self,
+        var: impl Into<EcoString>,
+        value: impl IntoValue,
+        capturer: Capturer,
+        span: Span,
+    ) {
+        self.insert(var.into(), value.into_value(), Kind::Captured(capturer), span);
+    }
+
+    /// Remove a definition, returning its value.
+    ///
+    /// This also removes constant and captured bindings. The remaining
+    /// definitions keep their order and redefining the name later appends it
+    /// at the end.
+    pub fn remove(&mut self, var: &str) -> Option<Value> {
+        if !self.map.contains_key(var) {
+            return None;
+        }
+        self.bindings_mut().shift_remove(var).map(Binding::into_value)
+    }
+
+    /// Copy all definitions of another scope into this one.
+    ///
+    /// The copies are mutable, like other local definitions, but keep the
+    /// metadata of the original bindings, like their definition spans. They
+    /// are [marked as merged](Binding::is_merged). The
+    /// policy decides what happens to names that are defined in both scopes.
+    /// With [`ConflictPolicy::Error`], all conflicting names are reported and
+    /// left untouched, while the remaining definitions are still merged. A
+    /// name that is bound to equal values in both scopes is never a conflict.
+    pub fn merge(
+        &mut self,
+        other: &Scope,
+        policy: ConflictPolicy,
+    ) -> Result<(), Vec<MergeConflict>> {
+        self.merge_with(other, |_, _| policy)
+    }
+
+    /// Copy all definitions of another scope into this one, deciding on the
+    /// policy per conflict.
+    ///
+    /// The function receives the existing and the incoming binding. Otherwise,
+    /// this behaves like [`merge`](Self::merge).
+    pub fn merge_with(
+        &mut self,
+        other: &Scope,
+        mut policy: impl FnMut(&Binding, &Binding) -> ConflictPolicy,
+    ) -> Result<(), Vec<MergeConflict>> {
+        let mut conflicts = vec![];
+        for (name, incoming) in other.map.iter() {
+            if let Some(existing) = self.map.get(name) {
+                if existing.read() != incoming.read() {
+                    match policy(existing, incoming) {
+                        ConflictPolicy::Shadow => {}
+                        ConflictPolicy::Skip => continue,
+                        ConflictPolicy::Error => {
+                            conflicts.push(MergeConflict {
+                                name: name.clone(),
+                                existing: existing.span(),
+                                incoming: incoming.span(),
+                            });
+                            continue;
+                        }
+                    }
+                }
+            }
+
+            let mut binding = incoming.clone();
+            binding.kind = Kind::Normal;
+            binding.merged = true;
+            self.bindings_mut().insert(name.clone(), binding);
+        }
+
+        if conflicts.is_empty() {
+            Ok(())
+        } else {
+            Err(conflicts)
+        }
+    }
+
+    /// Access the bindings mutably, copying them first if they are shared
+    /// with a clone of this scope.
+    fn bindings_mut(&mut self) -> &mut Bindings {
+        Arc::make_mut(&mut self.map)
+    }
+
+    /// Access a binding mutably.
+    ///
+    /// The bindings are only copied if the variable is actually defined.
+    fn binding_mut(&mut self, var: &str) -> Option<&mut Binding> {
+        if !self.map.contains_key(var) {
+            return None;
+        }
+        self.bindings_mut().get_mut(var)
+    }
+
+    /// Try to access a variable immutably.
+    pub fn get(&self, var: &str) -> Option<&Value> {
+        self.map.get(var).map(Binding::read)
+    }
+
+    /// Try to access a variable and cast it to the given type.
+    ///
+    /// The error tells apart a missing variable from one of the wrong type.
+    /// This is handy to read configuration from the exported scope of a
+    /// document's module:
+    ///
+    /// ```
+    /// # use typst::foundations::{Module, Scope, ScopeGetError, Str};
+    /// # let mut scope = Scope::new();
+    /// # scope.define("columns", 2);
+    /// # let module = Module::new("main", scope);
+    /// // Given a module defining `#let columns = 2`.
+    /// let columns: i64 = module.scope().get_as("columns")?;
+    /// let title = module.scope().get_or("title", Str::from("Untitled"))?;
+    /// assert_eq!(columns, 2);
+    /// assert_eq!(title.as_str(), "Untitled");
+    /// # Ok::<(), ScopeGetError>(())
+    /// ```
+    pub fn get_as<T: FromValue>(&self, var: &str) -> Result<T, ScopeGetError> {
+        cast_var(var, self.get(var))
+    }
+
+    /// Try to access a variable and cast it to the given type, falling back
+    /// to a default if it isn't defined.
+    ///
+    /// A variable of the wrong type is still an error.
+    pub fn get_or<T: FromValue>(
+        &self,
+        var: &str,
+        default: T,
+    ) -> Result<T, ScopeGetError> {
+        match self.get(var) {
+            Some(value) => cast_var(var, Some(value)),
+            None => Ok(default),
+        }
+    }
+
+    /// Try to access the binding of a variable, including its metadata.
+    pub fn get_binding(&self, var: &str) -> Option<&Binding> {
+        self.map.get(var)
+    }
+
+    /// Resolve a dotted path like `colors.primary`, starting in this scope.
+    ///
+    /// See [`Scopes::get_path`].
+    pub fn get_path(&self, path: &str) -> Result<ResolvedPath<'_>, PathError> {
+        let mut segments = path.split('.');
+        let first = segments.next().unwrap_or_default();
+        let binding = self
+            .get_binding(first)
+            .ok_or_else(|| PathError::Unknown(first.into()))?;
+        resolve_path(first, (binding.read(), Some(binding)), segments)
+    }
+
+    /// Try to find a definition whose name is similar to the given one.
+    ///
+    /// See [`Scopes::suggest`].
+    pub fn suggest(&self, var: &str) -> Option<EcoString> {
+        suggest_among(self.names(), var)
+    }
+
+    /// Try to access a variable mutably.
+    pub fn get_mut(&mut self, var: &str) -> Option<Result<&mut Value, MutError>> {
+        self.binding_mut(var).map(|binding| binding.write(var))
+    }
+
+    /// Whether a variable is bound to a constant.
+    ///
+    /// Returns `None` if the variable isn't defined in this scope.
+    pub fn is_const(&self, var: &str) -> Option<bool> {
+        self.map.get(var).map(Binding::is_const)
+    }
+
+    /// Get the category of a definition.
+    pub fn get_category(&self, var: &str) -> Option<Category> {
+        self.map.get(var)?.category
+    }
+
+    /// Summarize the bindings of this scope, e.g. for debugging.
+    pub fn snapshot(&self) -> ScopeSnapshot {
+        let bindings = self
+            .map
+            .iter()
+            .map(|(name, binding)| BindingSnapshot::new(name, binding, 0, false))
+            .collect();
+        ScopeSnapshot { bindings }
+    }
+
+    /// Iterate over all definitions in the order they were defined.
+    pub fn iter(&self) -> impl Iterator<Item = (&EcoString, &Value)> {
+        self.map.iter().map(|(k, v)| (k, v.read()))
+    }
+
+    /// Iterate over the names of all definitions in the order they were
+    /// defined.
+    pub fn names(&self) -> impl Iterator<Item = &EcoString> {
+        self.map.keys()
+    }
+
+    /// The number of definitions.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Whether the scope has no definitions.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Whether a variable is defined in this scope.
+    pub fn contains(&self, var: &str) -> bool {
+        self.map.contains_key(var)
+    }
+
+    /// Compare the bindings of this scope with those of a newer version of
+    /// it.
+    ///
+    /// Values are compared by equality. A value that contains a user-defined
+    /// function or a module is compared by identity instead, so that an
+    /// unequal one is only [maybe changed](ScopeDiff::maybe_changed).
+    pub fn diff(&self, other: &Scope) -> ScopeDiff {
+        let mut diff = ScopeDiff::default();
+        if Arc::ptr_eq(&self.map, &other.map) {
+            return diff;
+        }
+
+        for (name, binding) in self.map.iter() {
+            let Some(new) = other.map.get(name) else {
+                diff.removed.push(name.clone());
+                continue;
+            };
+
+            let (old, new) = (binding.read(), new.read());
+            if old == new {
+                continue;
+            }
+
+            if is_comparable(old) && is_comparable(new) {
+                diff.changed.push(name.clone());
+            } else {
+                diff.maybe_changed.push(name.clone());
+            }
+        }
+
+        diff.added = other
+            .map
+            .keys()
+            .filter(|name| !self.map.contains_key(name))
+            .cloned()
+            .collect();
+
+        diff
+    }
+}
+
+/// Whether equality of a value says whether it changed.
+///
+/// User-defined functions and modules compare unequal whenever they were
+/// evaluated anew, even if their source didn't change.
+fn is_comparable(value: &Value) -> bool {
+    match value {
+        Value::Func(func) => !func.is_closure(),
+        Value::Module(_) => false,
+        Value::Array(array) => array.iter().all(is_comparable),
+        Value::Dict(dict) => dict.iter().all(|(_, value)| is_comparable(value)),
+        _ => true,
+    }
+}
+
+/// The bindings of a scope.
+///
+/// Most scopes only hold a handful of bindings, so these are stored inline
+/// and looked up linearly. Only larger scopes spill into a hash map. Both
+/// representations keep the bindings in definition order.
+#[derive(Clone)]
+enum Bindings {
+    /// Up to [`SMALL_SCOPE`] bindings.
+    Small(SmallVec<[(EcoString, Binding); SMALL_SCOPE]>),
+    /// More than [`SMALL_SCOPE`] bindings.
+    Large(IndexMap<EcoString, Binding>),
+}
+
+/// The maximum number of bindings that are stored inline.
+const SMALL_SCOPE: usize = 8;
+
+impl Bindings {
+    /// Get the binding of a name.
+    fn get(&self, var: &str) -> Option<&Binding> {
+        match self {
+            Self::Small(vec) => vec.iter().find(|(name, _)| name == var).map(|(_, b)| b),
+            Self::Large(map) => map.get(var),
+        }
+    }
+
+    /// Get the binding of a name mutably.
+    fn get_mut(&mut self, var: &str) -> Option<&mut Binding> {
+        match self {
+            Self::Small(vec) => {
+                vec.iter_mut().find(|(name, _)| name == var).map(|(_, b)| b)
+            }
+            Self::Large(map) => map.get_mut(var),
+        }
+    }
+
+    /// Whether a name is bound.
+    fn contains_key(&self, var: &str) -> bool {
+        self.get(var).is_some()
+    }
+
+    /// Bind a name, replacing an existing binding in place.
+    ///
+    /// Returns the replaced binding, if any.
+    fn insert(&mut self, name: EcoString, binding: Binding) -> Option<Binding> {
+        if let Some(slot) = self.get_mut(&name) {
+            return Some(std::mem::replace(slot, binding));
+        }
+
+        match self {
+            Self::Small(vec) if vec.len() < SMALL_SCOPE => vec.push((name, binding)),
+            Self::Small(vec) => {
+                let mut map: IndexMap<_, _> = std::mem::take(vec).into_iter().collect();
+                map.insert(name, binding);
+                *self = Self::Large(map);
+            }
+            Self::Large(map) => {
+                map.insert(name, binding);
+            }
+        }
+
+        None
+    }
+
+    /// Remove the binding of a name, keeping the order of the remaining ones.
+    ///
+    /// A large map stays large, even if few bindings remain.
+    fn shift_remove(&mut self, var: &str) -> Option<Binding> {
+        match self {
+            Self::Small(vec) => {
+                let index = vec.iter().position(|(name, _)| name == var)?;
+                Some(vec.remove(index).1)
+            }
+            Self::Large(map) => map.shift_remove(var),
+        }
+    }
+
+    /// Iterate over the bindings in definition order.
+    fn iter(&self) -> impl Iterator<Item = (&EcoString, &Binding)> {
+        let (small, large) = match self {
+            Self::Small(vec) => (Some(vec.iter().map(|(name, b)| (name, b))), None),
+            Self::Large(map) => (None, Some(map.iter())),
+        };
+        small.into_iter().flatten().chain(large.into_iter().flatten())
+    }
+
+    /// Iterate over the bound names in definition order.
+    fn keys(&self) -> impl Iterator<Item = &EcoString> {
+        self.iter().map(|(name, _)| name)
+    }
+
+    /// The number of bindings.
+    fn len(&self) -> usize {
+        match self {
+            Self::Small(vec) => vec.len(),
+            Self::Large(map) => map.len(),
+        }
+    }
+
+    /// Whether there are no bindings.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self::Small(SmallVec::new())
+    }
+}
+
+impl Debug for Scope {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str("Scope ")?;
+        f.debug_map()
+            .entries(self.map.iter().map(|(k, v)| (k, v.read())))
+            .finish()
+    }
+}
+
+impl PartialEq for Scope {
+    /// Scopes are equal if they bind the same names to equal bindings in the
+    /// same order. This is cheap for a scope and an unmodified clone of it.
+    fn eq(&self, other: &Self) -> bool {
+        let same = Arc::ptr_eq(&self.map, &other.map)
+            || (self.map.len() == other.map.len()
+                && self.map.iter().zip(other.map.iter()).all(|(x, y)| x == y));
+        same && self.deduplicate == other.deduplicate && self.category == other.category
+    }
+}
+
+impl Hash for Scope {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.map.len());
+        for item in self.map.iter() {
+            item.hash(state);
+        }
+        self.deduplicate.hash(state);
+        self.category.hash(state);
+    }
+}
+
+/// How to handle a name that is defined in both scopes when
+/// [merging](Scope::merge) them.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ConflictPolicy {
+    /// The incoming definition replaces the existing one.
+    Shadow,
+    /// The existing definition is kept.
+    Skip,
+    /// The existing definition is kept and the conflict is reported.
+    Error,
+}
+
+/// A name that is defined differently in two [merged](Scope::merge) scopes.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct MergeConflict {
+    /// The conflicting name.
+    pub name: EcoString,
+    /// Where the existing definition is located, if known.
+    pub existing: Option<Span>,
+    /// Where the incoming definition is located, if known.
+    pub incoming: Option<Span>,
+}
+
+/// The differences between two versions of a [scope](Scope::diff).
+///
+/// All lists are in definition order: Added names in the order of the new
+/// scope and all others in the order of the old one.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Hash)]
+pub struct ScopeDiff {
+    /// Names that are only defined in the new scope.
+    pub added: Vec<EcoString>,
+    /// Names that are only defined in the old scope.
+    pub removed: Vec<EcoString>,
+    /// Names that are bound to different values.
+    pub changed: Vec<EcoString>,
+    /// Names that are bound to values which can't be compared meaningfully
+    /// and might be different.
+    pub maybe_changed: Vec<EcoString>,
+}
+
+impl ScopeDiff {
+    /// Whether the scopes have the same bindings.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.maybe_changed.is_empty()
+    }
+}
+
+/// A summary of the bindings in one or multiple scopes.
+///
+/// Values are only represented through their type and a truncated `repr`.
+/// With the `scope-snapshots` feature, snapshots can be serialized with serde.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "scope-snapshots", derive(serde::Serialize))]
+pub struct ScopeSnapshot {
+    /// The summarized bindings.
+    pub bindings: Vec<BindingSnapshot>,
+}
+
+/// A summary of a single binding in a [`ScopeSnapshot`].
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "scope-snapshots", derive(serde::Serialize))]
+pub struct BindingSnapshot {
+    /// The name of the binding.
+    pub name: EcoString,
+    /// The name of the value's type.
+    #[cfg_attr(feature = "scope-snapshots", serde(rename = "type"))]
+    pub ty: &'static str,
+    /// The value's `repr`, truncated if it is very long.
+    pub repr: EcoString,
+    /// Whether the binding can't be mutated.
+    pub constant: bool,
+    /// The scope the binding lives in, counted from the active scope.
+    pub layer: usize,
+    /// Whether the binding is hidden by a nearer one with the same name.
+    pub shadowed: bool,
+}
+
+impl BindingSnapshot {
+    /// The maximum number of characters of a value's `repr`.
+    const MAX_REPR_LEN: usize = 80;
+
+    /// Summarize a binding.
+    fn new(name: &EcoString, binding: &Binding, layer: usize, shadowed: bool) -> Self {
+        Self {
+            name: name.clone(),
+            ty: binding.read().ty().short_name(),
+            repr: Self::summarize(binding.read()),
+            constant: binding.is_const(),
+            layer,
+            shadowed,
+        }
+    }
+
+    /// The `repr` of a value, truncated if it is very long.
+    pub(crate) fn summarize(value: &Value) -> EcoString {
+        let mut repr = value.repr();
+        if repr.chars().count() > Self::MAX_REPR_LEN {
+            repr = repr.chars().take(Self::MAX_REPR_LEN - 1).collect();
+            repr.push('…');
+        }
+        repr
+    }
+}
+
+/// Defines the associated scope of a Rust type.
+pub trait NativeScope {
+    /// The constructor function for the type, if any.
+    fn constructor() -> Option<&'static NativeFuncData>;
+
+    /// Get the associated scope for the type.
+    fn scope() -> Scope;
+}
+
+/// A value bound to a name, together with metadata about the binding.
+#[derive(Debug, Clone)]
+pub struct Binding {
+    /// The stored value. Always initialized unless the binding is lazy.
+    value: OnceCell<Value>,
+    /// Computes the value of a lazy binding on first access.
+    init: Option<fn() -> Value>,
+    /// The kind of binding, determines how the value can be accessed.
+    kind: Kind,
+    /// The category of the binding.
+    category: Option<Category>,
+    /// Where the binding was defined, detached if unknown.
+    span: Span,
+    /// Documentation attached to the binding.
+    docs: Option<Arc<Docs>>,
+    /// Whether the binding was accessed through [`Scopes`] while usage was
+    /// tracked. Atomic, so that reading needs no mutable access.
+    accessed: Accessed,
+    /// Whether the binding was copied from another scope.
+    merged: bool,
+    /// Whether the binding can only be resolved in math mode.
+    math_only: bool,
+    /// Why the binding shouldn't be used anymore, if it is deprecated.
+    deprecation: Option<Arc<Deprecation>>,
+}
+
+/// Documentation of a binding.
+#[derive(Debug, Clone, PartialEq, Hash)]
+struct Docs {
+    /// The documentation text.
+    docs: EcoString,
+    /// A free-form category name.
+    category: Option<EcoString>,
+}
+
+/// Information about a deprecated binding.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Deprecation {
+    /// What to do instead, e.g. "use `new` instead".
+    message: EcoString,
+    /// The version in which the binding was deprecated.
+    since: Option<EcoString>,
+}
+
+impl Deprecation {
+    /// What to use instead of the deprecated binding.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The version in which the binding was deprecated, if known.
+    pub fn since(&self) -> Option<&str> {
+        self.since.as_deref()
+    }
+}
+
+/// Whether a binding was accessed.
+#[derive(Debug, Default)]
+struct Accessed(AtomicBool);
+
+impl Accessed {
+    fn get(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn set(&self) {
+        // Avoid writing to bindings that are read a lot, e.g. in the shared
+        // standard library.
+        if !self.get() {
+            self.0.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Clone for Accessed {
+    fn clone(&self) -> Self {
+        Self(AtomicBool::new(self.get()))
+    }
+}
+
+/// The different kinds of bindings.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+enum Kind {
+    /// A normal, mutable binding.
+    Normal,
+    /// A constant binding that can't be mutated.
+    Constant,
+    /// A captured copy of another variable.
+    Captured(Capturer),
+}
+
+/// What the variable was captured by.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Capturer {
+    /// Captured by a function / closure.
+    Function,
+    /// Captured by a context expression.
+    Context,
+}
+
+impl Binding {
+    /// Create a new binding.
+    fn new(value: Value, kind: Kind, category: Option<Category>, span: Span) -> Self {
+        Self {
+            value: OnceCell::with_value(value),
+            init: None,
+            kind,
+            category,
+            span,
+            docs: None,
+            accessed: Accessed::default(),
+            merged: false,
+            math_only: false,
+            deprecation: None,
+        }
+    }
+
+    /// Create a new lazy binding.
+    fn lazy(init: fn() -> Value, category: Option<Category>) -> Self {
+        Self {
+            value: OnceCell::new(),
+            init: Some(init),
+            kind: Kind::Constant,
+            category,
+            span: Span::detached(),
+            docs: None,
+            accessed: Accessed::default(),
+            merged: false,
+            math_only: false,
+            deprecation: None,
+        }
+    }
+
+    /// Read the value, computing it first if the binding is lazy.
+    pub fn read(&self) -> &Value {
+        self.value
+            .get_or_init(|| (self.init.expect("binding is not initialized"))())
+    }
+
+    /// Mark the binding as accessed.
+    fn mark_accessed(&self) {
+        self.accessed.set();
+    }
+
+    /// Extract the value, computing it first if the binding is lazy.
+    fn into_value(self) -> Value {
+        match (self.value.into_inner(), self.init) {
+            (Some(value), _) => value,
+            (None, Some(init)) => init(),
+            (None, None) => unreachable!("binding is not initialized"),
+        }
+    }
+
+    /// Whether the value is available without computing it, i.e. the binding
+    /// is not lazy or was already accessed.
+    pub fn is_forced(&self) -> bool {
+        self.value.get().is_some()
+    }
+
+    /// Whether the binding was resolved through [`Scopes`] while
+    /// [usage was tracked](Scopes::track_usage), even if it couldn't be
+    /// mutated.
+    ///
+    /// Accesses through a single [`Scope`], metadata queries, and iteration
+    /// don't count, so that the shared bindings of the standard library are
+    /// never written to.
+    pub fn is_accessed(&self) -> bool {
+        self.accessed.get()
+    }
+
+    /// Where the binding was defined.
+    ///
+    /// This is `None` for bindings that were defined from Rust, e.g. in the
+    /// standard library.
+    pub fn span(&self) -> Option<Span> {
+        (!self.span.is_detached()).then_some(self.span)
+    }
+
+    /// The category of the binding.
+    pub fn category(&self) -> Option<Category> {
+        self.category
+    }
+
+    /// The documentation attached to the binding.
+    ///
+    /// This is `None` unless the binding was defined with
+    /// [`Scope::define_with_docs`], documented with [`Scope::document`], or
+    /// defined by a `let` binding with doc comments.
+    pub fn docs(&self) -> Option<&str> {
+        self.docs.as_ref().map(|docs| docs.docs.as_str())
+    }
+
+    /// The category given together with the binding's documentation.
+    pub fn doc_category(&self) -> Option<&str> {
+        self.docs.as_ref()?.category.as_deref()
+    }
+
+    /// Whether the binding was copied from another scope by
+    /// [`Scope::merge`], e.g. through a wildcard import.
+    pub fn is_merged(&self) -> bool {
+        self.merged
+    }
+
+    /// Why the binding shouldn't be used anymore, if it was defined through
+    /// [`Scope::define_deprecated`].
+    pub fn deprecation(&self) -> Option<&Deprecation> {
+        self.deprecation.as_deref()
+    }
+
+    /// Whether the binding was defined through [`Scope::define_math`] and can
+    /// thus only be resolved by identifiers in math mode.
+    pub fn is_math_only(&self) -> bool {
+        self.math_only
+    }
+
+    /// Whether the binding can't be mutated.
+    pub fn is_const(&self) -> bool {
+        self.kind == Kind::Constant
+    }
+
+    /// Try to write to the value.
+    fn write(&mut self, var: &str) -> Result<&mut Value, MutError> {
+        match self.kind {
+            Kind::Normal => {
+                self.read();
+                Ok(self.value.get_mut().unwrap())
+            }
+            Kind::Constant => Err(MutError::Constant(var.into(), self.span)),
+            Kind::Captured(capturer) => Err(MutError::Captured(var.into(), capturer)),
+        }
+    }
+}
+
+impl PartialEq for Binding {
+    /// Compares the same fields as the `Hash` implementation, so that the
+    /// two agree. Whether the binding was accessed is not compared.
+    fn eq(&self, other: &Self) -> bool {
+        let same_value = match (self.init, other.init) {
+            (Some(a), Some(b)) => a as usize == b as usize,
+            (None, None) => self.read() == other.read(),
+            _ => false,
+        };
+        same_value
+            && self.kind == other.kind
+            && self.category == other.category
+            && self.span == other.span
+            && self.docs == other.docs
+            && self.merged == other.merged
+            && self.math_only == other.math_only
+            && self.deprecation == other.deprecation
+    }
+}
+
+impl Hash for Binding {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Lazy bindings are identified by their initializer, so that hashing
+        // them, e.g. when prehashing the library, doesn't force them.
+        match self.init {
+            Some(init) => state.write_usize(init as usize),
+            None => self.read().hash(state),
+        }
+        self.kind.hash(state);
+        self.category.hash(state);
+        self.span.hash(state);
+        self.docs.hash(state);
+        self.merged.hash(state);
+        self.math_only.hash(state);
+        self.deprecation.hash(state);
+    }
+}
+
+/// A group of related definitions.
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Category(Static<CategoryData>);
+
+impl Category {
+    /// Create a new category from raw data.
+    pub const fn from_data(data: &'static CategoryData) -> Self {
+        Self(Static(data))
+    }
+
+    /// The category's name.
+    pub fn name(&self) -> &'static str {
+        self.0.name
+    }
+
+    /// The type's title case name, for use in documentation (e.g. `String`).
+    pub fn title(&self) -> &'static str {
+        self.0.title
+    }
+
+    /// Documentation for the category.
+    pub fn docs(&self) -> &'static str {
+        self.0.docs
+    }
+}
+
+impl Debug for Category {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "Category({})", self.name())
     }
 }
 
@@ -349,3 +2184,1120 @@ pub struct CategoryData {
     pub title: &'static str,
     pub docs: &'static str,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diag::StrResult;
+    use crate::foundations::{panic, Array, Closure, Content, NativeElement, Str};
+    use crate::syntax::{Source, SyntaxNode};
+    use crate::text::TextElem;
+
+    fn names(scope: &Scope) -> Vec<&str> {
+        scope.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_scope_iter_in_definition_order() {
+        let mut scope = Scope::new();
+        scope.define("c", 1);
+        scope.define("a", 2);
+        scope.define("b", 3);
+        assert_eq!(names(&scope), ["c", "a", "b"]);
+    }
+
+    #[test]
+    fn test_scope_redefine_keeps_position() {
+        let mut scope = Scope::new();
+        scope.define("a", 1);
+        scope.define("b", 2);
+        scope.define("c", 3);
+        scope.define("b", 4);
+        assert_eq!(names(&scope), ["a", "b", "c"]);
+        assert_eq!(scope.get("b"), Some(&Value::Int(4)));
+    }
+
+    #[test]
+    fn test_scope_matches_index_map() {
+        // Compare the scope against a plain index map, which it used to be, on
+        // a stream of pseudo-random definitions and removals. The number of
+        // names is chosen such that scopes grow past the inline capacity.
+        let mut state = 0x2545_f491_u32;
+        let mut next = move |bound: u32| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state % bound
+        };
+
+        for _ in 0..50 {
+            let mut scope = Scope::new();
+            let mut model = IndexMap::<EcoString, Value>::new();
+            for _ in 0..100 {
+                let name = eco_format!("v{}", next(20));
+                if next(4) == 0 {
+                    assert_eq!(scope.remove(&name), model.shift_remove(&name));
+                } else {
+                    let value = Value::Int(next(1000).into());
+                    scope.define(name.clone(), value.clone());
+                    model.insert(name, value);
+                }
+
+                assert_eq!(scope.len(), model.len());
+                assert!(scope.iter().eq(model.iter()));
+                for name in model.keys() {
+                    assert_eq!(scope.get(name), model.get(name));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_scope_spill_keeps_metadata() {
+        let span = Source::detached("x").root().span();
+        let mut scope = Scope::new();
+        scope.define_const("a", 1);
+        scope.define_spanned("b", 2, span);
+        for i in 0..SMALL_SCOPE - 1 {
+            assert!(matches!(*scope.map, Bindings::Small(_)));
+            scope.define(eco_format!("c{i}"), i as i64);
+        }
+
+        assert!(matches!(*scope.map, Bindings::Large(_)));
+        assert_eq!(names(&scope)[..3], ["a", "b", "c0"]);
+        assert_eq!(scope.is_const("a"), Some(true));
+        assert_eq!(scope.get_binding("b").unwrap().span(), Some(span));
+    }
+
+    #[test]
+    fn test_scope_define_returns_previous() {
+        let mut scope = Scope::new();
+        assert_eq!(scope.define("a", 1), None);
+        assert_eq!(scope.define("a", 2), Some(Value::Int(1)));
+        assert_eq!(scope.try_define("a", 3), Err(Value::Int(3)));
+        assert_eq!(scope.try_define("b", 4), Ok(()));
+        assert_eq!(scope.get("a"), Some(&Value::Int(2)));
+        assert_eq!(scope.get("b"), Some(&Value::Int(4)));
+        assert_eq!(names(&scope), ["a", "b"]);
+    }
+
+    #[test]
+    fn test_scope_define_checked() {
+        let mut scope = Scope::new();
+        assert_eq!(scope.define_checked("my-var", 1), Ok(None));
+        assert_eq!(scope.define_checked("my-var", 2), Ok(Some(Value::Int(1))));
+        assert_eq!(
+            scope.define_checked("my var", 3),
+            Err("\"my var\" is not a valid identifier".into())
+        );
+        assert_eq!(
+            scope.define_checked("", 3),
+            Err("\"\" is not a valid identifier".into())
+        );
+        assert_eq!(
+            scope.define_checked("let", 3),
+            Err("`let` is a keyword and can't be used as a name".into())
+        );
+        assert_eq!(names(&scope), ["my-var"]);
+
+        // The escape hatch skips validation.
+        assert_eq!(scope.define_raw("my var", 4), None);
+        assert_eq!(scope.get("my var"), Some(&Value::Int(4)));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "invalid definition: \"my var\"")]
+    fn test_scope_define_invalid_panics() {
+        Scope::new().define("my var", 1);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "invalid definition: `in` is a keyword")]
+    fn test_scope_define_keyword_panics() {
+        Scope::new().define("in", 1);
+    }
+
+    #[test]
+    fn test_scope_define_keyword() {
+        let mut scope = Scope::new();
+        assert!(scope.define_checked("in", 1).is_err());
+        assert_eq!(scope.define_raw("in", 2), None);
+        assert_eq!(scope.get("in"), Some(&Value::Int(2)));
+    }
+
+    #[test]
+    fn test_library_definitions_are_valid() {
+        // Building the library runs the debug checks on all definitions.
+        // Symbols like `sym.in` are keywords, which go through `define_raw`.
+        let library = Library::default();
+        let sym: Module = library.global.scope().get_as("sym").unwrap();
+        assert!(sym.scope().get("in").is_some());
+        assert!(library.math.scope().get("in").is_some());
+    }
+
+    #[test]
+    fn test_scope_diff() {
+        let mut old = Scope::new();
+        old.define("a", 1);
+        old.define("b", 2);
+        old.define("c", 3);
+        old.define("d", 4);
+
+        let mut new = old.clone();
+        assert!(old.diff(&new).is_empty());
+
+        new.define("d", 5);
+        new.remove("b");
+        new.define("f", 6);
+        new.define("e", 7);
+        new.define("a", 8);
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.added, ["f", "e"]);
+        assert_eq!(diff.removed, ["b"]);
+        assert_eq!(diff.changed, ["a", "d"]);
+        assert!(diff.maybe_changed.is_empty());
+
+        let diff = new.diff(&old);
+        assert_eq!(diff.added, ["b"]);
+        assert_eq!(diff.removed, ["f", "e"]);
+        assert_eq!(diff.changed, ["a", "d"]);
+    }
+
+    #[test]
+    fn test_scope_diff_incomparable_values() {
+        let closure = |text: &str| {
+            Func::from(Closure {
+                node: Source::detached(text).root().clone(),
+                defaults: vec![],
+                captured: Scope::new(),
+                num_pos_params: 0,
+            })
+        };
+
+        let module = Module::new("m", Scope::new());
+        let mut old = Scope::new();
+        old.define("f", closure("1"));
+        old.define("g", closure("2"));
+        old.define("m", module.clone());
+        old.define("n", module);
+        old.define("list", Array::from_iter([Value::Int(1)]));
+
+        let mut new = old.clone();
+        new.define("f", closure("3"));
+        new.define("n", Module::new("m", Scope::new()));
+        new.define(
+            "list",
+            Array::from_iter([Module::new("m", Scope::new()).into_value()]),
+        );
+
+        // Identical values are unchanged even if they aren't comparable.
+        let diff = old.diff(&new);
+        assert!(diff.changed.is_empty());
+        assert_eq!(diff.maybe_changed, ["f", "n", "list"]);
+    }
+
+    #[test]
+    fn test_scope_clone_on_write() {
+        let mut scope = Scope::new();
+        for i in 0..20 {
+            scope.define(eco_format!("v{i}"), i);
+        }
+
+        let mut clone = scope.clone();
+        assert!(Arc::ptr_eq(&scope.map, &clone.map));
+        assert_eq!(scope, clone);
+
+        // Failed lookups and removals don't copy the bindings.
+        assert!(clone.get_mut("missing").is_none());
+        assert_eq!(clone.remove("missing"), None);
+        assert!(Arc::ptr_eq(&scope.map, &clone.map));
+
+        *clone.get_mut("v0").unwrap().unwrap() = Value::Int(100);
+        assert!(!Arc::ptr_eq(&scope.map, &clone.map));
+        assert_ne!(scope, clone);
+        assert_eq!(scope.get("v0"), Some(&Value::Int(0)));
+        assert_eq!(clone.get("v0"), Some(&Value::Int(100)));
+
+        let mut other = scope.clone();
+        other.define("new", 1);
+        other.remove("v1");
+        assert_eq!(scope.len(), 20);
+        assert_eq!(scope.get("new"), None);
+        assert_eq!(scope.get("v1"), Some(&Value::Int(1)));
+        assert_eq!(other.len(), 20);
+
+        let mut equal = Scope::new();
+        for i in 0..20 {
+            equal.define(eco_format!("v{i}"), i);
+        }
+        assert_eq!(scope, equal);
+    }
+
+    #[test]
+    fn test_scope_eq_and_hash_agree() {
+        let source = Source::detached("a b");
+        let spans: Vec<_> = source.root().children().map(SyntaxNode::span).collect();
+        let scope = |span, docs: &str| {
+            let mut scope = Scope::new();
+            scope.define_const_spanned("x", 1, span);
+            scope.document("x", docs, None);
+            scope
+        };
+
+        let base = scope(spans[0], "The x.");
+        for other in [scope(spans[2], "The x."), scope(spans[0], "Another x.")] {
+            assert_ne!(base, other);
+            assert_ne!(crate::util::hash128(&base), crate::util::hash128(&other));
+        }
+
+        let same = scope(spans[0], "The x.");
+        assert_eq!(base, same);
+        assert_eq!(crate::util::hash128(&base), crate::util::hash128(&same));
+    }
+
+    #[test]
+    fn test_scope_define_deprecated() {
+        let mut scope = Scope::new();
+        scope.define("new", 1);
+        scope.define_deprecated("old", 1, "use `new` instead", Some("0.11"));
+
+        let deprecation = scope.get_binding("old").and_then(Binding::deprecation);
+        assert_eq!(deprecation.map(Deprecation::message), Some("use `new` instead"));
+        assert_eq!(deprecation.and_then(Deprecation::since), Some("0.11"));
+        assert!(scope.get_binding("new").unwrap().deprecation().is_none());
+        assert_eq!(scope.get("old"), Some(&Value::Int(1)));
+
+        // Captures keep the deprecation.
+        let mut scopes = Scopes::new(None);
+        scopes.top = scope;
+        let captured = scopes.capture(["old"], Capturer::Function);
+        assert!(captured.get_binding("old").unwrap().deprecation().is_some());
+    }
+
+    #[test]
+    fn test_scope_introspection() {
+        let mut scope = Scope::new();
+        assert!(scope.is_empty());
+        scope.define("a", 1);
+        scope.define("b", 2);
+        assert_eq!(scope.len(), 2);
+        assert!(scope.contains("a"));
+        assert!(!scope.contains("c"));
+        assert_eq!(scope.names().collect::<Vec<_>>(), ["a", "b"]);
+    }
+
+    #[test]
+    fn test_scopes_visible_names() {
+        let mut scopes = Scopes::new(None);
+        scopes.top.define("a", 1);
+        scopes.top.define("b", 2);
+        scopes.enter();
+        scopes.top.define("c", 3);
+        scopes.top.define("a", 4);
+        let visible: Vec<_> = scopes
+            .visible_names()
+            .into_iter()
+            .map(|(name, value)| (name.as_str(), value.clone()))
+            .collect();
+        assert_eq!(
+            visible,
+            [("c", Value::Int(3)), ("a", Value::Int(4)), ("b", Value::Int(2))]
+        );
+    }
+
+    #[test]
+    fn test_scopes_bindings() {
+        let library = Library::default();
+        let mut scopes = Scopes::new(Some(&library));
+        scopes.top.define("a", 1);
+        scopes.enter();
+        scopes.top.define("a", 2);
+        scopes.top.define("rect", 3);
+
+        let bindings: Vec<_> = scopes.bindings().collect();
+        assert_eq!(bindings.iter().filter(|(name, _)| *name == "a").count(), 1);
+        assert_eq!(bindings.iter().filter(|(name, _)| *name == "rect").count(), 1);
+        assert_eq!(bindings[0], (&"a".into(), &Value::Int(2)));
+        assert_eq!(bindings[1], (&"rect".into(), &Value::Int(3)));
+        assert_eq!(bindings.len(), library.global.scope().len() + 1);
+        assert!(bindings[2..]
+            .iter()
+            .all(|(name, _)| library.global.scope().contains(name)));
+
+        scopes.exit();
+        let names: Vec<_> = scopes.bindings().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names[0], "a");
+        assert_eq!(scopes.bindings().next().unwrap().1, &Value::Int(1));
+        assert!(names[1..].iter().all(|name| library.global.scope().contains(name)));
+    }
+
+    #[test]
+    fn test_scope_remove() {
+        let mut scope = Scope::new();
+        scope.define("a", 1);
+        scope.define_const("b", 2);
+        scope.define("c", 3);
+        assert_eq!(scope.remove("b"), Some(Value::Int(2)));
+        assert_eq!(scope.remove("b"), None);
+        assert_eq!(names(&scope), ["a", "c"]);
+        scope.define("b", 4);
+        assert_eq!(names(&scope), ["a", "c", "b"]);
+        assert_eq!(scope.is_const("b"), Some(false));
+    }
+
+    #[test]
+    fn test_scopes_remove_top_falls_through() {
+        let mut scopes = Scopes::new(None);
+        scopes.top.define("x", 1);
+        scopes.enter();
+        scopes.top.define("x", 2);
+        assert_eq!(scopes.remove_top("x"), Some(Value::Int(2)));
+        assert_eq!(scopes.get("x"), Ok(&Value::Int(1)));
+        assert_eq!(scopes.remove_top("x"), None);
+        assert_eq!(scopes.get("x"), Ok(&Value::Int(1)));
+    }
+
+    #[test]
+    fn test_scope_snapshot() {
+        let content = TextElem::packed("Hi");
+        let mut scope = Scope::new();
+        scope.define("int", 1);
+        scope.define("str", "a".repeat(100));
+        scope.define_func::<panic>();
+        scope.define("body", content.clone());
+
+        let snapshot = scope.snapshot();
+        let summary: Vec<_> = snapshot
+            .bindings
+            .iter()
+            .map(|binding| (binding.name.as_str(), binding.ty, binding.constant))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("int", "int", false),
+                ("str", "str", false),
+                ("panic", "function", true),
+                ("body", "content", false),
+            ]
+        );
+        assert_eq!(snapshot.bindings[0].repr, "1");
+        assert_eq!(snapshot.bindings[1].repr.chars().count(), 80);
+        assert!(snapshot.bindings[1].repr.ends_with('…'));
+        assert_eq!(snapshot.bindings[2].repr, "panic");
+        assert_eq!(snapshot.bindings[3].repr, Content::repr(&content));
+    }
+
+    #[test]
+    fn test_scopes_snapshot_shadowing() {
+        let mut scopes = Scopes::new(None);
+        scopes.top.define("x", 1);
+        scopes.top.define("y", 2);
+        scopes.enter();
+        scopes.top.define("x", 3);
+
+        let snapshot = scopes.snapshot();
+        let summary: Vec<_> = snapshot
+            .bindings
+            .iter()
+            .map(|b| (b.name.as_str(), b.repr.as_str(), b.layer, b.shadowed))
+            .collect();
+        assert_eq!(
+            summary,
+            [("x", "3", 0, false), ("x", "1", 1, true), ("y", "2", 1, false)]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "scope-snapshots")]
+    fn test_scope_snapshot_json() {
+        let content = TextElem::packed("Hi");
+        let mut scope = Scope::new();
+        scope.define("x", 1);
+        scope.define("s", "hello");
+        scope.define_func::<panic>();
+        scope.define("body", content.clone());
+        let json = serde_json::to_value(scope.snapshot()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "bindings": [
+                    {
+                        "name": "x",
+                        "type": "int",
+                        "repr": "1",
+                        "constant": false,
+                        "layer": 0,
+                        "shadowed": false,
+                    },
+                    {
+                        "name": "s",
+                        "type": "str",
+                        "repr": "\"hello\"",
+                        "constant": false,
+                        "layer": 0,
+                        "shadowed": false,
+                    },
+                    {
+                        "name": "panic",
+                        "type": "function",
+                        "repr": "panic",
+                        "constant": true,
+                        "layer": 0,
+                        "shadowed": false,
+                    },
+                    {
+                        "name": "body",
+                        "type": "content",
+                        "repr": Content::repr(&content).as_str(),
+                        "constant": false,
+                        "layer": 0,
+                        "shadowed": false,
+                    },
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_scope_binding_spans() {
+        let span = Source::detached("x").root().span();
+        let mut scopes = Scopes::new(None);
+        scopes.top.define("a", 1);
+        scopes.top.define_spanned("b", 2, span);
+        scopes.enter();
+        let scopes = scopes.clone();
+        assert_eq!(scopes.get_binding("a").unwrap().span(), None);
+        assert_eq!(scopes.get_binding("b").unwrap().span(), Some(span));
+        assert_eq!(scopes.get_binding("b").unwrap().read(), &Value::Int(2));
+        assert!(scopes.get_binding("c").is_none());
+    }
+
+    #[test]
+    fn test_scopes_track_usage() {
+        let source = Source::detached("#a #b");
+        let spans: Vec<_> = source.root().children().map(SyntaxNode::span).collect();
+        let mut scopes = Scopes::new(None);
+        scopes.track_usage();
+        scopes.enter();
+        scopes.top.define_spanned("a", 1, spans[1]);
+        scopes.top.define_spanned("b", 2, spans[4]);
+        scopes.top.define_spanned("_c", 3, spans[0]);
+        scopes.top.define("d", 4);
+        assert!(scopes.get("a").is_ok());
+        assert!(scopes.top.get("b").is_some());
+        assert!(scopes.get_binding("a").unwrap().is_accessed());
+        assert!(!scopes.get_binding("b").unwrap().is_accessed());
+
+        scopes.exit();
+        assert_eq!(scopes.take_unused(), [(EcoString::from("b"), spans[4])]);
+        assert!(scopes.take_unused().is_empty());
+    }
+
+    #[test]
+    fn test_scopes_untracked_usage() {
+        let mut scopes = Scopes::new(None);
+        scopes.top.define("a", 1);
+        assert!(scopes.get("a").is_ok());
+        assert!(scopes.get_mut("a").is_ok());
+        assert!(!scopes.get_binding("a").unwrap().is_accessed());
+    }
+
+    #[test]
+    fn test_scope_merge_shadow() {
+        let mut scope = Scope::new();
+        scope.define("a", 1);
+        scope.define("b", 2);
+        let mut other = Scope::new();
+        other.define("b", 3);
+        other.define("c", 4);
+        assert!(scope.merge(&other, ConflictPolicy::Shadow).is_ok());
+        assert_eq!(names(&scope), ["a", "b", "c"]);
+        assert_eq!(scope.get("b"), Some(&Value::Int(3)));
+        assert!(scope.get_binding("c").unwrap().is_merged());
+    }
+
+    #[test]
+    fn test_scope_merge_skip() {
+        let mut scope = Scope::new();
+        scope.define("a", 1);
+        scope.define("b", 2);
+        let mut other = Scope::new();
+        other.define("b", 3);
+        other.define("c", 4);
+        assert!(scope.merge(&other, ConflictPolicy::Skip).is_ok());
+        assert_eq!(scope.get("b"), Some(&Value::Int(2)));
+        assert_eq!(scope.get("c"), Some(&Value::Int(4)));
+        assert!(!scope.get_binding("b").unwrap().is_merged());
+    }
+
+    #[test]
+    fn test_scope_merge_error() {
+        let span = Source::detached("x").root().span();
+        let mut scope = Scope::new();
+        scope.define("a", 1);
+        scope.define("b", 2);
+        let mut other = Scope::new();
+        other.define_spanned("b", 3, span);
+        other.define("c", 4);
+        other.define("a", 1);
+
+        // Equal values for `a` are not a conflict.
+        let conflicts = scope.merge(&other, ConflictPolicy::Error).unwrap_err();
+        assert_eq!(
+            conflicts,
+            [MergeConflict {
+                name: "b".into(),
+                existing: None,
+                incoming: Some(span)
+            }]
+        );
+        assert_eq!(scope.get("b"), Some(&Value::Int(2)));
+        assert_eq!(scope.get("c"), Some(&Value::Int(4)));
+    }
+
+    #[test]
+    fn test_scope_merge_makes_mutable() {
+        let mut other = Scope::new();
+        other.define_const("pi", 3);
+        let mut scope = Scope::new();
+        assert!(scope.merge(&other, ConflictPolicy::Error).is_ok());
+        assert_eq!(scope.is_const("pi"), Some(false));
+    }
+
+    #[test]
+    fn test_scope_docs() {
+        let mut scope = Scope::new();
+        scope.define("a", 1);
+        scope.define_with_docs("b", 2, "The b.", None);
+        scope.define_with_docs("c", 3, "The c.", Some("letters"));
+        assert_eq!(scope.get_binding("a").unwrap().docs(), None);
+        assert_eq!(scope.get_binding("a").unwrap().doc_category(), None);
+        assert_eq!(scope.get_binding("b").unwrap().docs(), Some("The b."));
+        assert_eq!(scope.get_binding("b").unwrap().doc_category(), None);
+        assert_eq!(scope.get_binding("c").unwrap().doc_category(), Some("letters"));
+
+        // Redefining drops the docs, documenting an unknown name does nothing.
+        scope.define("b", 4);
+        scope.document("d", "The d.", None);
+        assert_eq!(scope.get_binding("b").unwrap().docs(), None);
+        assert!(!scope.contains("d"));
+    }
+
+    #[test]
+    fn test_scope_define_lazy() {
+        fn init() -> Value {
+            Value::Str("computed".into())
+        }
+
+        let mut scope = Scope::new();
+        scope.define_lazy("x", init);
+        assert!(!scope.get_binding("x").unwrap().is_forced());
+
+        assert_eq!(scope.get("x"), Some(&init()));
+        assert!(scope.get_binding("x").unwrap().is_forced());
+        assert_eq!(scope.is_const("x"), Some(true));
+        assert_eq!(
+            scope.get_mut("x").unwrap().unwrap_err(),
+            MutError::Constant("x".into(), Span::detached())
+        );
+
+        // Comparing and hashing look at the initializer without forcing.
+        let mut fresh = Scope::new();
+        fresh.define_lazy("x", init);
+        assert_eq!(crate::util::hash128(&fresh), crate::util::hash128(&scope));
+        assert_eq!(fresh, scope);
+        assert!(!fresh.get_binding("x").unwrap().is_forced());
+
+        let mut eager = Scope::new();
+        eager.define_const("x", init());
+        assert_ne!(fresh, eager);
+        assert!(!fresh.get_binding("x").unwrap().is_forced());
+
+        let mut fresh = Scope::new();
+        fresh.define_lazy("x", init);
+        assert_eq!(fresh.remove("x"), Some(init()));
+    }
+
+    #[test]
+    fn test_scope_define_const() {
+        let mut scope = Scope::new();
+        scope.define("a", 1);
+        scope.define_const("b", 2);
+        assert_eq!(scope.is_const("a"), Some(false));
+        assert_eq!(scope.is_const("b"), Some(true));
+        assert_eq!(scope.is_const("c"), None);
+        assert!(scope.get_mut("a").unwrap().is_ok());
+        assert_eq!(
+            scope.get_mut("b").unwrap().unwrap_err(),
+            MutError::Constant("b".into(), Span::detached())
+        );
+    }
+
+    #[test]
+    fn test_scope_define_const_spanned() {
+        let span = Source::detached("x").root().span();
+        let mut scope = Scope::new();
+        assert_eq!(scope.define_const_spanned("x", 1, span), None);
+        assert_eq!(scope.is_const("x"), Some(true));
+        assert_eq!(scope.get_binding("x").unwrap().span(), Some(span));
+
+        let err = scope.get_mut("x").unwrap().unwrap_err();
+        assert_eq!(err, MutError::Constant("x".into(), span));
+        assert_eq!(err.definition(), Some(span));
+        assert_eq!(MutError::Unknown("y".into()).definition(), None);
+    }
+
+    #[test]
+    fn test_scopes_shadowed_spanned_const() {
+        let span = Source::detached("x").root().span();
+        let mut scopes = Scopes::new(None);
+        scopes.top.define_const_spanned("x", 1, span);
+        assert_eq!(scopes.get_mut("x").unwrap_err().definition(), Some(span));
+
+        // A new `let` in the same scope replaces the constant.
+        scopes.top.define("x", 2);
+        assert!(!scopes.is_const("x"));
+        *scopes.get_mut("x").unwrap() = Value::Int(3);
+        assert_eq!(scopes.get("x"), Ok(&Value::Int(3)));
+    }
+
+    #[test]
+    fn test_scopes_is_const_in_library() {
+        let library = Library::default();
+        let mut scopes = Scopes::new(Some(&library));
+        assert!(scopes.is_const("rect"));
+        assert!(scopes.is_const("std"));
+        assert!(!scopes.is_const("unknown"));
+
+        scopes.top.define("rect", 1);
+        assert!(!scopes.is_const("rect"));
+    }
+
+    #[test]
+    fn test_scopes_shadowed_const_is_mutable() {
+        let mut scopes = Scopes::new(None);
+        scopes.top.define_const("x", 1);
+        scopes.enter();
+        scopes.top.define("x", 2);
+        assert!(!scopes.is_const("x"));
+        *scopes.get_mut("x").unwrap() = Value::Int(3);
+        assert_eq!(scopes.get("x"), Ok(&Value::Int(3)));
+        scopes.exit();
+        assert!(scopes.is_const("x"));
+        assert!(scopes.get_mut("x").is_err());
+    }
+
+    #[test]
+    fn test_scopes_bases_shadowing() {
+        let mut prelude = Scope::new();
+        prelude.define("x", 1);
+        prelude.define("y", 2);
+        let mut inputs = Scope::new();
+        inputs.define("x", 3);
+        inputs.define("z", 4);
+
+        let mut scopes = Scopes::with_bases(&[&inputs, &prelude], None);
+        assert_eq!(scopes.get("x"), Ok(&Value::Int(3)));
+        assert_eq!(scopes.get("y"), Ok(&Value::Int(2)));
+        assert_eq!(scopes.get_in_math("z"), Ok(&Value::Int(4)));
+        assert_eq!(scopes.get_binding("y").map(Binding::read), Some(&Value::Int(2)));
+        assert_eq!(scopes.suggest("zz").as_deref(), Some("z"));
+
+        scopes.top.define("y", 5);
+        let visible: Vec<_> = scopes
+            .visible_names()
+            .into_iter()
+            .map(|(name, value)| (name.as_str(), value.clone()))
+            .collect();
+        assert_eq!(
+            visible,
+            [("y", Value::Int(5)), ("x", Value::Int(3)), ("z", Value::Int(4))]
+        );
+    }
+
+    #[test]
+    fn test_scopes_bases_mut_error_definition() {
+        let def = Source::detached("y").root().span();
+        let mut inputs = Scope::new();
+        inputs.define_spanned("y", 2, def);
+
+        let mut scopes = Scopes::with_bases(&[&inputs], None);
+        let err = scopes.get_mut("y").unwrap_err();
+        assert_eq!(err, MutError::Builtin("y".into(), def));
+        assert_eq!(err.definition(), Some(def));
+    }
+
+    #[test]
+    fn test_scopes_bases_are_read_only() {
+        let mut prelude = Scope::new();
+        prelude.define_const("x", 1);
+        let mut inputs = Scope::new();
+        inputs.define("y", 2);
+
+        let mut scopes = Scopes::with_bases(&[&inputs, &prelude], None);
+        assert!(scopes.is_const("x"));
+        assert!(!scopes.is_const("y"));
+        assert_eq!(
+            scopes.get_mut("x"),
+            Err(MutError::Builtin("x".into(), Span::detached()))
+        );
+        assert_eq!(
+            scopes.get_mut("y"),
+            Err(MutError::Builtin("y".into(), Span::detached()))
+        );
+        assert_eq!(scopes.get_mut("z"), Err(MutError::Unknown("z".into())));
+
+        scopes.top.define("x", 3);
+        assert!(!scopes.is_const("x"));
+        *scopes.get_mut("x").unwrap() = Value::Int(4);
+        assert_eq!(scopes.get("x"), Ok(&Value::Int(4)));
+        assert_eq!(prelude.get("x"), Some(&Value::Int(1)));
+    }
+
+    #[test]
+    fn test_scopes_math_only() {
+        let mut scopes = Scopes::new(None);
+        scopes.top.define_math("norm", 1);
+        assert!(scopes.is_math_only("norm"));
+        assert_eq!(scopes.get_in_math("norm"), Ok(&Value::Int(1)));
+        assert_eq!(scopes.top.get("norm"), Some(&Value::Int(1)));
+
+        let error = scopes.get("norm").unwrap_err();
+        assert_eq!(error.message, "unknown variable: norm");
+        assert_eq!(error.hints, [eco_format!("`norm` is only available in math mode")]);
+        assert!(scopes.get_binding("norm").is_none());
+        assert!(!scopes.is_const("norm"));
+        assert_eq!(scopes.bindings().count(), 0);
+        assert_eq!(scopes.get_mut("norm"), Err(MutError::Unknown("norm".into())));
+
+        scopes.enter();
+        scopes.top.define("norm", 2);
+        assert!(!scopes.is_math_only("norm"));
+        assert_eq!(scopes.get("norm"), Ok(&Value::Int(2)));
+        assert_eq!(scopes.get_in_math("norm"), Ok(&Value::Int(2)));
+        assert_eq!(scopes.get_binding("norm").map(Binding::read), Some(&Value::Int(2)));
+    }
+
+    #[test]
+    fn test_scopes_math_only_shadowing() {
+        // A math-only binding doesn't hide an outer one outside of math.
+        let mut scopes = Scopes::new(None);
+        scopes.top.define("thin", 1);
+        scopes.enter();
+        scopes.top.define_math("thin", 2);
+        assert_eq!(scopes.get("thin"), Ok(&Value::Int(1)));
+        assert_eq!(scopes.get_in_math("thin"), Ok(&Value::Int(2)));
+        assert_eq!(scopes.get_binding("thin").map(Binding::read), Some(&Value::Int(1)));
+        let bindings: Vec<_> = scopes.bindings().map(|(_, value)| value).collect();
+        assert_eq!(bindings, [&Value::Int(1)]);
+        *scopes.get_mut("thin").unwrap() = Value::Int(3);
+        assert_eq!(scopes.get("thin"), Ok(&Value::Int(3)));
+        assert_eq!(scopes.get_in_math("thin"), Ok(&Value::Int(2)));
+    }
+
+    #[test]
+    fn test_scopes_suggest_in_math() {
+        let library = Library::default();
+        let mut scopes = Scopes::new(Some(&library));
+        assert_eq!(scopes.suggest("alpah"), None);
+        assert_eq!(scopes.suggest_in_math("alpah").as_deref(), Some("alpha"));
+
+        scopes.top.define_math("norm", 1);
+        assert_eq!(scopes.suggest("nrom"), None);
+        assert_eq!(scopes.suggest_in_math("nrom").as_deref(), Some("norm"));
+    }
+
+    #[test]
+    fn test_scopes_nested_evaluation_is_read_only() {
+        let mut outer = Scopes::new(None);
+        outer.top.define("x", 1);
+        outer.enter();
+        outer.top.define("y", 2);
+
+        {
+            let view = outer.view();
+            assert_eq!(view.get("y"), Ok(&Value::Int(2)));
+            assert_eq!(view.suggest("yy").as_deref(), Some("y"));
+
+            let mut nested = Scopes::nested(view);
+            assert_eq!(nested.get("x"), Ok(&Value::Int(1)));
+            assert_eq!(nested.get("y"), Ok(&Value::Int(2)));
+            assert_eq!(
+                nested.get_mut("x"),
+                Err(MutError::Builtin("x".into(), Span::detached()))
+            );
+            assert_eq!(
+                nested.get_mut("y"),
+                Err(MutError::Builtin("y".into(), Span::detached()))
+            );
+
+            // Nested definitions only shadow the outer ones.
+            nested.top.define("y", 3);
+            nested.top.define("z", 4);
+            assert_eq!(nested.get("y"), Ok(&Value::Int(3)));
+            assert_eq!(view.get("y"), Ok(&Value::Int(2)));
+        }
+
+        assert_eq!(outer.get("y"), Ok(&Value::Int(2)));
+        assert!(outer.get("z").is_err());
+        assert_eq!(names(&outer.top), ["y"]);
+        outer.exit();
+        assert_eq!(names(&outer.top), ["x"]);
+    }
+
+    #[test]
+    fn test_scopes_get_path_in_library() {
+        let library = Library::default();
+        let scopes = Scopes::new(Some(&library));
+
+        let resolved = scopes.get_path("calc.pi").unwrap();
+        assert_eq!(resolved.value, &Value::Float(std::f64::consts::PI));
+        assert_eq!(resolved.bindings.len(), 2);
+
+        let resolved = scopes.get_path("std.calc.pi").unwrap();
+        assert_eq!(resolved.value, &Value::Float(std::f64::consts::PI));
+        assert!(resolved.bindings[0].is_none());
+
+        assert_eq!(
+            scopes.get_path("calc.pie").unwrap_err(),
+            PathError::UnknownField {
+                module: "calc".into(),
+                field: "pie".into(),
+                similar: Some("pi".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_scopes_get_path_in_nested_modules() {
+        let span = Source::detached("x").root().span();
+        let mut inner = Scope::new();
+        inner.define_spanned("primary", 1, span);
+        let mut outer = Scope::new();
+        outer.define_module(Module::new("colors", inner));
+
+        let mut scopes = Scopes::new(None);
+        scopes.top.define_module(Module::new("pkg", outer));
+
+        let resolved = scopes.get_path("pkg.colors.primary").unwrap();
+        assert_eq!(resolved.value, &Value::Int(1));
+        assert_eq!(resolved.spans().collect::<Vec<_>>(), [None, None, Some(span)]);
+        assert_eq!(resolved.binding().and_then(Binding::span), Some(span));
+        assert!(scopes.top.get_path("pkg.colors").is_ok());
+
+        assert_eq!(
+            scopes.get_path("pkg.colors.primary.light").unwrap_err(),
+            PathError::NotModule { segment: "primary".into(), ty: Type::of::<i64>() }
+        );
+        assert_eq!(
+            HintedString::from(scopes.get_path("pkg.primary").unwrap_err()).message,
+            "module `pkg` does not contain `primary`"
+        );
+        assert_eq!(
+            scopes.get_path("missing.x").unwrap_err(),
+            PathError::Unknown("missing".into())
+        );
+    }
+
+    #[test]
+    fn test_scopes_nested_shadowing() {
+        let mut scopes = Scopes::new(None);
+        scopes.top.define("x", 1);
+        scopes.enter();
+        assert_eq!(scopes.get("x"), Ok(&Value::Int(1)));
+        scopes.top.define("x", 2);
+        scopes.enter();
+        scopes.top.define("x", 3);
+        assert_eq!(scopes.get("x"), Ok(&Value::Int(3)));
+        scopes.exit();
+        assert_eq!(scopes.get("x"), Ok(&Value::Int(2)));
+        scopes.exit();
+        assert_eq!(scopes.get("x"), Ok(&Value::Int(1)));
+    }
+
+    #[test]
+    fn test_scopes_depth_and_restore() {
+        let mut scopes = Scopes::new(None);
+        scopes.top.define("x", 1);
+        let depth = scopes.depth();
+        for i in 0..3 {
+            scopes.enter();
+            scopes.top.define("x", i + 2);
+        }
+        assert_eq!(scopes.depth(), depth + 3);
+        assert_eq!(scopes.get("x"), Ok(&Value::Int(4)));
+        scopes.restore(depth);
+        assert_eq!(scopes.depth(), depth);
+        assert_eq!(scopes.get("x"), Ok(&Value::Int(1)));
+        scopes.restore(depth);
+        assert_eq!(names(&scopes.top), ["x"]);
+    }
+
+    #[test]
+    fn test_scopes_capture_only_named() {
+        let mut scopes = Scopes::new(None);
+        for i in 0..1000 {
+            scopes.top.define(eco_format!("v{i}"), i);
+        }
+        for i in 0..1000_i64 {
+            let (a, b) = (eco_format!("v{i}"), eco_format!("v{}", 999 - i));
+            let captured = scopes.capture([a.as_str(), b.as_str()], Capturer::Function);
+            assert_eq!(captured.len(), if a == b { 1 } else { 2 });
+            assert_eq!(captured.get(&a), Some(&Value::Int(i)));
+        }
+    }
+
+    #[test]
+    fn test_scopes_capture_before_shadow() {
+        let mut scopes = Scopes::new(None);
+        scopes.top.define("x", 1);
+        scopes.enter();
+        scopes.top.define("x", 2);
+        let captured = scopes.capture(["x"], Capturer::Function);
+        scopes.top.define("x", 3);
+        assert_eq!(captured.get("x"), Some(&Value::Int(2)));
+        scopes.exit();
+        assert_eq!(captured.get("x"), Some(&Value::Int(2)));
+    }
+
+    #[test]
+    fn test_scopes_capture_builtin() {
+        let library = Library::default();
+        let scopes = Scopes::new(Some(&library));
+        let captured = scopes.capture(["calc", "unknown"], Capturer::Function);
+        assert_eq!(names(&captured), ["calc"]);
+        assert_eq!(captured.get("calc"), library.global.scope().get("calc"));
+    }
+
+    #[test]
+    fn test_scopes_capture_in_math_keeps_math_docs() {
+        let mut global = Scope::new();
+        global.define_with_docs("x", 1, "The global x.", None);
+        let mut math = Scope::new();
+        math.define_with_docs("x", 2, "The math x.", None);
+
+        let mut library = Library::default();
+        library.global = Module::new("global", global);
+        library.math = Module::new("math", math);
+        let scopes = Scopes::new(Some(&library));
+
+        let captured = scopes.capture_in_math(["x"], Capturer::Function);
+        let binding = captured.get_binding("x").unwrap();
+        assert_eq!(binding.read(), &Value::Int(2));
+        assert_eq!(binding.docs(), Some("The math x."));
+
+        let captured = scopes.capture(["x"], Capturer::Function);
+        let binding = captured.get_binding("x").unwrap();
+        assert_eq!(binding.read(), &Value::Int(1));
+        assert_eq!(binding.docs(), Some("The global x."));
+    }
+
+    #[test]
+    fn test_scope_guard_exits_on_error() {
+        fn define_and_fail(scopes: &mut Scopes) -> StrResult<()> {
+            let mut scopes = scopes.scoped();
+            scopes.top.define("x", 2);
+            scopes.top.define("y", 3);
+            Err("failed".into())
+        }
+
+        let mut scopes = Scopes::new(None);
+        scopes.top.define("x", 1);
+        assert!(define_and_fail(&mut scopes).is_err());
+        assert!(scopes.scopes.is_empty());
+        assert_eq!(scopes.get("x"), Ok(&Value::Int(1)));
+        assert!(scopes.get("y").is_err());
+    }
+
+    #[test]
+    fn test_scope_guard_records_usage_on_success() {
+        let source = Source::detached("#a #b");
+        let spans: Vec<_> = source.root().children().map(SyntaxNode::span).collect();
+        let mut scopes = Scopes::new(None);
+        scopes.track_usage();
+
+        // A failed scope doesn't report bindings that it didn't get to use.
+        {
+            let mut scopes = scopes.scoped();
+            scopes.top.define_spanned("a", 1, spans[1]);
+        }
+        assert!(scopes.scopes.is_empty());
+        assert!(scopes.take_unused().is_empty());
+
+        let mut guard = scopes.scoped();
+        guard.top.define_spanned("b", 2, spans[4]);
+        guard.finish();
+        assert!(scopes.scopes.is_empty());
+        assert_eq!(scopes.take_unused(), [(EcoString::from("b"), spans[4])]);
+    }
+
+    #[test]
+    fn test_scope_get_as() {
+        let mut scope = Scope::new();
+        scope.define("cols", 3);
+        scope.define("title", "Report");
+        scope.define("big", 300);
+        assert_eq!(scope.get_as::<i64>("cols"), Ok(3));
+        assert_eq!(scope.get_as::<Str>("title"), Ok("Report".into()));
+        assert_eq!(
+            scope.get_as::<i64>("rows"),
+            Err(ScopeGetError::Unknown("rows".into()))
+        );
+        assert_eq!(
+            scope.get_as::<i64>("title"),
+            Err(ScopeGetError::WrongType {
+                var: "title".into(),
+                expected: "integer".into(),
+                found: Type::of::<Str>(),
+            })
+        );
+        assert!(matches!(
+            scope.get_as::<u8>("big"),
+            Err(ScopeGetError::Invalid { var, .. }) if var == "big"
+        ));
+    }
+
+    #[test]
+    fn test_scope_get_as_message() {
+        let mut scope = Scope::new();
+        scope.define("title", "Report");
+        let err = scope.get_as::<i64>("title").unwrap_err();
+        assert_eq!(err.to_string(), "expected integer for `title`, found string");
+        let err = scope.get_as::<Option<i64>>("title").unwrap_err();
+        assert!(err.to_string().starts_with("expected integer or none for `title`"));
+    }
+
+    #[test]
+    fn test_scope_get_or() {
+        let mut scope = Scope::new();
+        scope.define("cols", 3);
+        assert_eq!(scope.get_or("cols", 1_i64), Ok(3));
+        assert_eq!(scope.get_or("rows", 1_i64), Ok(1));
+        assert!(matches!(
+            scope.get_or("cols", Str::from("none")),
+            Err(ScopeGetError::WrongType { .. })
+        ));
+    }
+
+    #[test]
+    fn test_scopes_get_as() {
+        let mut scopes = Scopes::new(None);
+        scopes.top.define("cols", 3);
+        scopes.enter();
+        scopes.top.define("title", "Report");
+        assert_eq!(scopes.get_as::<i64>("cols"), Ok(3));
+        assert_eq!(scopes.get_or::<i64>("rows", 2), Ok(2));
+        assert_eq!(
+            scopes.get_as::<bool>("rows"),
+            Err(ScopeGetError::Unknown("rows".into()))
+        );
+        assert!(matches!(
+            scopes.get_or::<bool>("title", false),
+            Err(ScopeGetError::WrongType { found, .. }) if found == Type::of::<Str>()
+        ));
+    }
+}
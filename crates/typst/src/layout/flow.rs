@@ -13,7 +13,7 @@ use crate::layout::{
 	Fragment, Frame, FrameItem, LayoutMultiple, LayoutSingle, PlaceElem, Point, Regions,
 	Rel, Size, Spacing, VElem,
 };
-use crate::model::{FootnoteElem, FootnoteEntry, InlineElem};
+use crate::model::{FootnoteElem, FootnoteEntry, InlineElem, LeadingDistribution};
 use crate::util::Numeric;
 
 /// Arranges spacing, paragraphs and block-level elements into a flow.
@@ -44,38 +44,29 @@ impl LayoutMultiple for Packed<FlowElem> {
 
 		let mut layouter = FlowLayouter::new(regions, styles);
 		for mut child in self.children().iter() {
-			println!("CHILD! :D");
 			let outer = styles;
 			let mut styles = styles;
 			if let Some(styled) = child.to_packed::<StyledElem>() {
 				child = &styled.child;
 				styles = outer.chain(&styled.styles);
-				println!("(Styled)");
 			}
 
 			if let Some(layoutable) = child.with::<dyn LayoutSingle>() {
-				println!("(LayoutSingle)");
 				layouter.layout_single(engine, layoutable, styles)?;
 			} else if child.can::<dyn LayoutMultiple>() {
-				println!("(LayoutMultiple)");
 				layouter.layout_multiple(engine, child, styles)?;
 			}else if child.is::<MetaElem>() {
-				println!("(Meta)");
 				layouter.layout_meta(styles);
 			} else if let Some(elem) = child.to_packed::<VElem>() {
-				println!("(VElem)");
 				layouter.layout_spacing(engine, elem, styles)?;
 			} else if let Some(placed) = child.to_packed::<PlaceElem>() {
-				println!("(PlaceElem)");
 				layouter.layout_placed(engine, placed, styles)?;
 			} else if child.is::<ColbreakElem>() {
-				println!("(ColbreakElem)");
 				if !layouter.regions.backlog.is_empty() || layouter.regions.last.is_some()
 				{
 					layouter.finish_region(engine, true)?;
 				}
 			} else if let Some(elem) = child.to_packed::<InlineElem>() {
-				println!("(inlineElem)");
 				layouter.layout_inline(engine, elem, styles)?;
 			} else {
 				bail!(child.span(), "unexpected flow child");
@@ -244,11 +235,13 @@ impl<'a> FlowLayouter<'a> {
 	) -> SourceResult<()> {
 		let align = AlignElem::alignment_in(styles).resolve(styles);
 		let leading = InlineElem::leading_in(styles);
-		println!("Inside layout_inline");
+		let distribution = InlineElem::leading_distribution_in(styles);
+		let consecutive = self.last_was_par;
 		let lines = inline
 			.layout(
 				engine,
 				styles,
+				consecutive,
 				self.regions.base(),
 				self.regions.expand.x,
 			)?
@@ -280,7 +273,7 @@ impl<'a> FlowLayouter<'a> {
 		}
 
 		for (i, frame) in lines.into_iter().enumerate() {
-			if i > 0 {
+			if i > 0 && distribution == LeadingDistribution::Between {
 				self.layout_item(engine, FlowItem::Absolute(leading, true))?;
 			}
 
@@ -290,7 +283,7 @@ impl<'a> FlowLayouter<'a> {
 			)?;
 		}
 
-		self.last_was_par = false;
+		self.last_was_par = true;
 		Ok(())
 	}
 
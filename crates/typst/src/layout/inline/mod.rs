@@ -21,10 +21,10 @@ use crate::foundations::{Content, Packed, Resolve, Smart, StyleChain, StyledElem
 use crate::introspection::{Introspector, Locator, MetaElem};
 use crate::layout::{
 	Abs, AlignElem, Axes, BoxElem, Dir, Em, FixedAlignment, Fr, Fragment, Frame, HElem,
-	Point, Regions, Size, Sizing, Spacing,
+	Point, Ratio, Regions, Size, Sizing, Spacing,
 };
 use crate::math::{EquationElem, MathParItem};
-use crate::model::{Linebreaks, InlineElem};
+use crate::model::{InlineElem, LeadingDistribution, Linebreaks, ParElem};
 use crate::syntax::Span;
 use crate::text::{
 	Lang, LinebreakElem, SmartQuoteElem, SmartQuoter, SmartQuotes, SpaceElem, TextElem,
@@ -37,9 +37,24 @@ pub(crate) fn layout_inline(
 	children: &[Content],
 	engine: &mut Engine,
 	styles: StyleChain,
+	consecutive: bool,
 	region: Size,
 	expand: bool,
 ) -> SourceResult<Fragment> {
+	layout_inline_with_metrics(children, engine, styles, consecutive, region, expand)
+		.map(|(fragment, _)| fragment)
+}
+
+/// Layouts content inline and additionally reports the metrics of each
+/// produced line.
+pub(crate) fn layout_inline_with_metrics(
+	children: &[Content],
+	engine: &mut Engine,
+	styles: StyleChain,
+	consecutive: bool,
+	region: Size,
+	expand: bool,
+) -> SourceResult<(Fragment, Vec<LineMetrics>)> {
 	#[comemo::memoize]
 	#[allow(clippy::too_many_arguments)]
 	fn cached(
@@ -50,9 +65,10 @@ pub(crate) fn layout_inline(
 		locator: Tracked<Locator>,
 		tracer: TrackedMut<Tracer>,
 		styles: StyleChain,
+		consecutive: bool,
 		region: Size,
 		expand: bool,
-	) -> SourceResult<Fragment> {
+	) -> SourceResult<(Fragment, Vec<LineMetrics>)> {
 		let mut locator = Locator::chained(locator);
 		let mut engine = Engine {
 			world,
@@ -64,7 +80,7 @@ pub(crate) fn layout_inline(
 
 		// Collect all text into one string for BiDi analysis.
 		let (text, segments, spans) =
-			collect(children, &mut engine, &styles, region)?;
+			collect(children, &mut engine, &styles, region, consecutive)?;
 
 		// Perform BiDi analysis and then prepare paragraph layout by building a
 		// representation on which we can do line breaking without layouting
@@ -79,7 +95,7 @@ pub(crate) fn layout_inline(
 		finalize(&mut engine, &p, &lines, region, expand, shrink)
 	}
 
-	let fragment = cached(
+	let (fragment, metrics) = cached(
 		children,
 		engine.world,
 		engine.introspector,
@@ -87,12 +103,44 @@ pub(crate) fn layout_inline(
 		engine.locator.track(),
 		TrackedMut::reborrow_mut(&mut engine.tracer),
 		styles,
+		consecutive,
 		region,
 		expand,
 	)?;
 
 	engine.locator.visit_frames(&fragment);
-	Ok(fragment)
+	Ok((fragment, metrics))
+}
+
+/// Metrics of a single line produced by inline layout.
+///
+/// These describe the lines exactly as they were committed to frames, which
+/// makes it possible to inspect typographic decisions like justification and
+/// hyphenation without going through rendered output.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct LineMetrics {
+	/// The width of the line's content after justification.
+	pub width: Abs,
+	/// The natural width of the line's content before justification.
+	pub natural_width: Abs,
+	/// The number of spaces in the line.
+	pub spaces: usize,
+	/// Whether the line ends in a hyphen inserted by hyphenation.
+	pub hyphenated: bool,
+	/// The position of the line's baseline, measured from the top of the
+	/// first line.
+	pub baseline: Abs,
+	/// The size of the line's frame.
+	pub size: Size,
+	/// The byte range the line spans in the paragraph's collected text.
+	///
+	/// This is the text that `collect` assembles from the children for
+	/// layout, not the source text: Spacing and inline-level elements are
+	/// represented by a single placeholder character each, and things like
+	/// smart quotes are already resolved.
+	pub range: std::ops::Range<usize>,
+	/// Whether the line was justified.
+	pub justified: bool,
 }
 
 /// Range of a substring of text.
@@ -125,14 +173,26 @@ struct Preparation<'a> {
 	align: FixedAlignment,
 	/// Whether to justify the paragraph.
 	justify: bool,
+	/// Whether to also justify the last line.
+	justify_last: bool,
+	/// How much of their shrinkability spaces may use when justifying.
+	min_shrink: Ratio,
+	/// How much of their stretchability spaces may use when justifying.
+	max_stretch: Smart<Ratio>,
 	/// The paragraph's hanging indent.
 	hang: Abs,
+	/// The minimum number of lines to keep at the start of a paragraph.
+	orphans: usize,
+	/// The minimum number of lines to keep at the end of a paragraph.
+	widows: usize,
 	/// Whether to add spacing between CJK and Latin characters.
 	cjk_latin_spacing: bool,
 	/// Whether font fallback is enabled for this paragraph.
 	fallback: bool,
 	/// The leading of the paragraph.
 	leading: Abs,
+	/// How the leading is distributed around lines.
+	leading_distribution: LeadingDistribution,
 	/// How to determine line breaks.
 	linebreaks: Smart<Linebreaks>,
 	/// The text size.
@@ -140,6 +200,18 @@ struct Preparation<'a> {
 }
 
 impl<'a> Preparation<'a> {
+	/// Whether a line with the given remaining space and stretchability would
+	/// need to stretch its spaces more than allowed to be justified.
+	fn overstretched(&self, remaining: Abs, stretch: Abs) -> bool {
+		match self.max_stretch {
+			Smart::Custom(max) => {
+				remaining > Abs::zero()
+					&& !(stretch > Abs::zero() && remaining / stretch <= max.get())
+			}
+			Smart::Auto => false,
+		}
+	}
+
 	/// Find the item that contains the given `text_offset`.
 	fn find(&self, text_offset: usize) -> Option<&Item<'a>> {
 		let mut cursor = 0;
@@ -329,6 +401,8 @@ struct Line<'a> {
 	/// Whether the line ends with a hyphen or dash, either naturally or through
 	/// hyphenation.
 	dash: bool,
+	/// Whether the line ends with a hyphen inserted through hyphenation.
+	hyphenated: bool,
 }
 
 impl<'a> Line<'a> {
@@ -382,6 +456,14 @@ impl<'a> Line<'a> {
 		count
 	}
 
+	/// How many spaces are in the line.
+	fn spaces(&self) -> usize {
+		self.items()
+			.filter_map(Item::text)
+			.map(|shaped| shaped.glyphs.iter().filter(|g| g.is_space()).count())
+			.sum()
+	}
+
 	/// How much can the line stretch
 	fn stretchability(&self) -> Abs {
 		self.items().filter_map(Item::text).map(|s| s.stretchability()).sum()
@@ -411,6 +493,7 @@ fn collect<'a>(
 	engine: &mut Engine<'_>,
 	styles: &'a StyleChain<'a>,
 	region: Size,
+	consecutive: bool,
 ) -> SourceResult<(String, Vec<(Segment<'a>, StyleChain<'a>)>, SpanMapper)> {
 	let mut full = String::new();
 	let mut quoter = SmartQuoter::new();
@@ -420,6 +503,30 @@ fn collect<'a>(
 
 	let outer_dir = TextElem::dir_in(*styles);
 
+	// Only consecutive paragraphs are indented, unless all of them should be.
+	// Paragraphs that aren't aligned to the start of the text have no
+	// meaningful first line indent.
+	let indent = ParElem::first_line_indent_in(*styles)
+		.unwrap_or_else(|| Em::one().into())
+		.resolve(*styles);
+	if !indent.is_zero()
+		&& (consecutive || ParElem::always_indent_first_line_in(*styles))
+		&& AlignElem::alignment_in(*styles).resolve(*styles).x == outer_dir.start().into()
+	{
+		full.push(SPACING_REPLACE);
+		spans.push(SPACING_REPLACE.len_utf8(), Span::detached());
+		segments.push((Segment::Spacing(indent.into()), *styles));
+	}
+
+	// All lines are shifted by the hanging indent, so the first one is moved
+	// back by the same amount.
+	let hang = ParElem::hanging_indent_in(*styles);
+	if !hang.is_zero() {
+		full.push(SPACING_REPLACE);
+		spans.push(SPACING_REPLACE.len_utf8(), Span::detached());
+		segments.push((Segment::Spacing((-hang).into()), *styles));
+	}
+
 	while let Some(mut child) = iter.next() {
 		let outer = styles;
 		let mut styles = *styles;
@@ -535,7 +642,6 @@ fn collect<'a>(
 		segments.push((segment, styles));
 	}
 
-	// println!("{full}");
 	Ok((full, segments, spans))
 }
 
@@ -579,7 +685,6 @@ fn prepare<'a>(
 				}
 			},
 			Segment::Equation(par_items) => {
-				println!("Equation");
 				for item in par_items {
 					match item {
 						MathParItem::Space(s) => items.push(Item::Absolute(s)),
@@ -627,10 +732,16 @@ fn prepare<'a>(
 		lang: shared_get(styles, children, TextElem::lang_in),
 		align: AlignElem::alignment_in(styles).resolve(styles).x,
 		justify: InlineElem::justify_in(styles),
-		hang: InlineElem::hanging_indent_in(styles),
+		justify_last: InlineElem::justify_last_in(styles),
+		min_shrink: InlineElem::min_shrink_in(styles),
+		max_stretch: InlineElem::max_stretch_in(styles),
+		hang: ParElem::hanging_indent_in(styles),
+		orphans: ParElem::orphans_in(styles),
+		widows: ParElem::widows_in(styles),
 		cjk_latin_spacing,
 		fallback: TextElem::fallback_in(styles),
 		leading: InlineElem::leading_in(styles),
+		leading_distribution: InlineElem::leading_distribution_in(styles),
 		linebreaks: InlineElem::linebreaks_in(styles),
 		size: TextElem::size_in(styles),
 	})
@@ -869,7 +980,6 @@ fn linebreak_optimized<'a>(
 	const DEFAULT_RUNT_COST: Cost = 0.5;
 	const CONSECUTIVE_DASH_COST: Cost = 0.3;
 	const MAX_COST: Cost = 1_000_000.0;
-	const MIN_RATIO: f64 = -1.0;
 
 	let hyph_cost = DEFAULT_HYPH_COST * p.costs.hyphenation().get();
 	let runt_cost = DEFAULT_RUNT_COST * p.costs.runt().get();
@@ -923,7 +1033,7 @@ fn linebreak_optimized<'a>(
 			}
 
 			// Determine the cost of the line.
-			let min_ratio = if p.justify { MIN_RATIO } else { 0.0 };
+			let min_ratio = if p.justify { -p.min_shrink.get() } else { 0.0 };
 			let mut cost = if ratio < min_ratio {
 				// The line is overfull. This is the case if
 				// - justification is on, but we'd need to shrink too much
@@ -955,6 +1065,12 @@ fn linebreak_optimized<'a>(
 				ratio.powi(3).abs()
 			};
 
+			// Avoid lines whose spaces would need to stretch too much. If
+			// there is no better alternative, the line will be ragged.
+			if attempt.justify && p.overstretched(delta, attempt.stretchability()) {
+				cost = MAX_COST;
+			}
+
 			// Penalize runts.
 			if k == i + 1 && is_end {
 				cost += runt_cost;
@@ -1012,6 +1128,10 @@ fn line<'a>(
 	let mut justify =
 		p.justify && end < p.bidi.text.len() && breakpoint != Breakpoint::Mandatory;
 
+	// The last line is only justified on request, and only if it has spaces
+	// to stretch, so that a single word isn't spread apart.
+	let justify_last = p.justify && p.justify_last && end == p.bidi.text.len();
+
 	if range.is_empty() {
 		return Line {
 			bidi: &p.bidi,
@@ -1023,6 +1143,7 @@ fn line<'a>(
 			width: Abs::zero(),
 			justify,
 			dash: false,
+			hyphenated: false,
 		};
 	}
 
@@ -1033,6 +1154,7 @@ fn line<'a>(
 	// Reshape the last item if it's split in half or hyphenated.
 	let mut last = None;
 	let mut dash = false;
+	let mut hyphenated = false;
 	if let Some((Item::Text(shaped), before)) = inner.split_last() {
 		// Compute the range we want to shape, trimming whitespace at the
 		// end of the line.
@@ -1047,7 +1169,8 @@ fn line<'a>(
 		// Deal with hyphens, dashes and justification.
 		let shy = trimmed.ends_with('\u{ad}');
 		let hyphen = breakpoint == Breakpoint::Hyphen;
-		dash = hyphen || shy || trimmed.ends_with(['-', '–', '—']);
+		hyphenated = hyphen || shy;
+		dash = hyphenated || trimmed.ends_with(['-', '–', '—']);
 		justify |= text.ends_with('\u{2028}');
 
 		// Deal with CJK punctuation at line ends.
@@ -1164,7 +1287,7 @@ fn line<'a>(
 		width += item.width();
 	}
 
-	Line {
+	let mut line = Line {
 		bidi: &p.bidi,
 		trimmed: range,
 		end,
@@ -1174,7 +1297,14 @@ fn line<'a>(
 		width,
 		justify,
 		dash,
+		hyphenated,
+	};
+
+	if justify_last && !line.justify {
+		line.justify = line.spaces() > 0;
 	}
+
+	line
 }
 
 /// Combine layouted lines into on
@@ -1185,7 +1315,7 @@ fn finalize(
 	region: Size,
 	expand: bool,
 	shrink: bool,
-) -> SourceResult<Fragment> {
+) -> SourceResult<(Fragment, Vec<LineMetrics>)> {
 	// Determine the paragraph's width: Full width of the region if we
 	// should expand or there's fractional spacing, fit-to-width otherwise.
 	let width = if !region.x.is_finite()
@@ -1198,32 +1328,58 @@ fn finalize(
 		region.x
 	};
 
+	// With half-leading, each line carries half of the leading above and
+	// below itself, so no additional gap is needed between lines.
+	let half = p.leading_distribution == LeadingDistribution::Half;
+	let gap = if half { Abs::zero() } else { p.leading };
+
 	// Stack the lines into one frame per region.
-	let mut frames: Vec<Frame> = lines
-		.iter()
-		.map(|line| commit(engine, p, line, width, region.y, shrink))
-		.collect::<SourceResult<_>>()?;
+	let mut frames = Vec::with_capacity(lines.len());
+	let mut metrics = Vec::with_capacity(lines.len());
+	let mut y = Abs::zero();
+	for line in lines {
+		let (mut frame, mut line_metrics) =
+			commit(engine, p, line, width, region.y, shrink)?;
+		if half {
+			frame.size_mut().y += p.leading;
+			frame.translate(Point::with_y(p.leading / 2.0));
+		}
+		line_metrics.baseline = y + frame.baseline();
+		line_metrics.size = frame.size();
+		y += frame.height() + gap;
+		frames.push(frame);
+		metrics.push(line_metrics);
+	}
 
 	// Positive ratios enable prevention, while zero and negative ratios disable it.
 	if p.costs.orphan().get() > 0.0 {
-		// Prevent orphans.
-		if frames.len() >= 2 && !frames[1].is_empty() {
-			let second = frames.remove(1);
-			let first = &mut frames[0];
-			merge(first, second, p.leading);
+		// Prevent orphans by keeping the first lines together.
+		let count = p.orphans.min(frames.len());
+		if count >= 2 && !frames[1].is_empty() {
+			let rest: Vec<_> = frames.drain(1..count).collect();
+			merge_all(&mut frames[0], rest, gap);
 		}
 	}
 	if p.costs.widow().get() > 0.0 {
-		// Prevent widows.
+		// Prevent widows by keeping the last lines together. If the first
+		// lines were already merged and there are too few lines for both,
+		// this merges the whole paragraph, so that it moves as a unit.
 		let len = frames.len();
-		if len >= 2 && !frames[len - 2].is_empty() {
-			let second = frames.pop().unwrap();
-			let first = frames.last_mut().unwrap();
-			merge(first, second, p.leading);
+		let count = p.widows.min(len);
+		if count >= 2 && !frames[len - 2].is_empty() {
+			let rest: Vec<_> = frames.drain(len - count + 1..).collect();
+			merge_all(frames.last_mut().unwrap(), rest, gap);
 		}
 	}
 
-	Ok(Fragment::frames(frames))
+	Ok((Fragment::frames(frames), metrics))
+}
+
+/// Merge multiple line frames into the first one.
+fn merge_all(first: &mut Frame, rest: Vec<Frame>, leading: Abs) {
+	for frame in rest {
+		merge(first, frame, leading);
+	}
 }
 
 /// Merge two line frames
@@ -1235,6 +1391,8 @@ fn merge(first: &mut Frame, second: Frame, leading: Abs) {
 }
 
 /// Commit to a line and build its frame.
+///
+/// The baseline in the returned metrics is relative to the line's frame.
 fn commit(
 	engine: &mut Engine,
 	p: &Preparation,
@@ -1242,7 +1400,7 @@ fn commit(
 	width: Abs,
 	full: Abs,
 	shrink: bool,
-) -> SourceResult<Frame> {
+) -> SourceResult<(Frame, LineMetrics)> {
 	let mut remaining = width - line.width - p.hang;
 	let mut offset = Abs::zero();
 
@@ -1292,9 +1450,10 @@ fn commit(
 	let stretch = line.stretchability();
 	if remaining < Abs::zero() && shrinkability > Abs::zero() && shrink {
 		// Attempt to reduce the length of the line, using shrinkability.
-		justification_ratio = (remaining / shrinkability).max(-1.0);
-		remaining = (remaining + shrinkability).min(Abs::zero());
-	} else if line.justify && fr.is_zero() {
+		let min_shrink = p.min_shrink.get();
+		justification_ratio = (remaining / shrinkability).max(-min_shrink);
+		remaining = (remaining + shrinkability * min_shrink).min(Abs::zero());
+	} else if line.justify && fr.is_zero() && !p.overstretched(remaining, stretch) {
 		// Attempt to increase the length of the line, using stretchability.
 		if stretch > Abs::zero() {
 			justification_ratio = (remaining / stretch).min(1.0);
@@ -1309,6 +1468,12 @@ fn commit(
 		}
 	}
 
+	// The width the line's content ends up with after justification.
+	let adjustability = if justification_ratio < 0.0 { shrinkability } else { stretch };
+	let justified = line.width
+		+ adjustability * justification_ratio
+		+ extra_justification * line.justifiables() as f64;
+
 	let mut top = Abs::zero();
 	let mut bottom = Abs::zero();
 
@@ -1368,7 +1533,18 @@ fn commit(
 		output.push_frame(Point::new(x, y), frame);
 	}
 
-	Ok(output)
+	let metrics = LineMetrics {
+		width: justified,
+		natural_width: line.width,
+		spaces: line.spaces(),
+		hyphenated: line.hyphenated,
+		baseline: top,
+		size,
+		range: line.trimmed.start..line.end,
+		justified: line.justify,
+	};
+
+	Ok((output, metrics))
 }
 
 /// Return a line's items in visual order.
@@ -50,6 +50,7 @@ pub use self::fragment::*;
 pub use self::frame::*;
 pub use self::grid::*;
 pub use self::hide::*;
+pub use self::inline::LineMetrics;
 pub use self::layout_::*;
 pub use self::length::*;
 pub use self::measure_::*;
@@ -85,22 +85,37 @@ use crate::visualize::Color;
 /// Requires a mutable reference to a tracer. Such a tracer can be created with
 /// `Tracer::new()`. Independently of whether compilation succeeded, calling
 /// `tracer.warnings()` after compilation will return all compiler warnings.
-#[typst_macros::time(name = "compile")]
 pub fn compile(world: &dyn World, tracer: &mut Tracer) -> SourceResult<Document> {
+    compile_with_module(world, tracer).0
+}
+
+/// Compile a source file into a fully layouted document, like [`compile`],
+/// and also return the module the main file evaluated to.
+///
+/// The module is `None` if evaluation failed. It is still returned if
+/// typesetting failed afterwards.
+#[typst_macros::time(name = "compile")]
+pub fn compile_with_module(
+    world: &dyn World,
+    tracer: &mut Tracer,
+) -> (SourceResult<Document>, Option<Module>) {
     // Call `track` on the world just once to keep comemo's ID stable.
     let world = world.track();
 
     // Try to evaluate the source file into a module.
-    let module = crate::eval::eval(
+    let module = match crate::eval::eval(
         world,
         Route::default().track(),
         tracer.track_mut(),
         &world.main(),
-    )
-    .map_err(deduplicate)?;
+    ) {
+        Ok(module) => module,
+        Err(errors) => return (Err(deduplicate(errors)), None),
+    };
 
     // Typeset the module's content, relayouting until convergence.
-    typeset(world, tracer, &module.content()).map_err(deduplicate)
+    let document = typeset(world, tracer, &module.content()).map_err(deduplicate);
+    (document, Some(module))
 }
 
 /// Relayout until introspection converges.
@@ -300,6 +315,10 @@ pub struct Library {
     /// The standard library as a value.
     /// Used to provide the `std` variable.
     pub std: Value,
+    /// Opt-in lints that are checked during evaluation.
+    pub lints: Lints,
+    /// The maximum nesting depth of function calls.
+    pub max_call_depth: usize,
 }
 
 impl Library {
@@ -322,6 +341,8 @@ impl Default for Library {
 #[derive(Debug, Clone, Default)]
 pub struct LibraryBuilder {
     inputs: Option<Dict>,
+    lints: Lints,
+    max_call_depth: Option<usize>,
 }
 
 impl LibraryBuilder {
@@ -331,16 +352,56 @@ impl LibraryBuilder {
         self
     }
 
+    /// Configure which opt-in lints are checked during evaluation.
+    pub fn with_lints(mut self, lints: Lints) -> Self {
+        self.lints = lints;
+        self
+    }
+
+    /// Configure the maximum nesting depth of function calls.
+    ///
+    /// Deeper calls fail with an error instead of overflowing the stack. The
+    /// default is [`Route::MAX_CALL_DEPTH`]. A much higher limit may need a
+    /// larger stack for the thread that compiles.
+    pub fn with_max_call_depth(mut self, depth: usize) -> Self {
+        self.max_call_depth = Some(depth);
+        self
+    }
+
     /// Consumes the builder and returns a `Library`.
     pub fn build(self) -> Library {
         let math = math::module();
         let inputs = self.inputs.unwrap_or_default();
         let global = global(math.clone(), inputs);
         let std = Value::Module(global.clone());
-        Library { global, math, styles: Styles::new(), std }
+        Library {
+            global,
+            math,
+            styles: Styles::new(),
+            std,
+            lints: self.lints,
+            max_call_depth: self.max_call_depth.unwrap_or(Route::MAX_CALL_DEPTH),
+        }
     }
 }
 
+/// Opt-in lints that report warnings for suspicious, but valid code.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Lints {
+    /// Warn when a binding shadows a variable from an enclosing scope or the
+    /// standard library.
+    pub shadowing: bool,
+    /// Warn when a local binding is never used.
+    ///
+    /// Bindings are checked when their scope is exited. The top-level bindings
+    /// of a module are its exports and thus never reported. Names starting
+    /// with an underscore are exempt.
+    pub unused: bool,
+    /// Don't report unused function parameters as part of the
+    /// [`unused`](Self::unused) lint.
+    pub allow_unused_params: bool,
+}
+
 /// Construct the module with global definitions.
 fn global(math: Module, inputs: Dict) -> Module {
     let mut global = Scope::deduplicating();
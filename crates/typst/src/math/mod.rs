@@ -207,7 +207,7 @@ pub fn module() -> Module {
     op::define(&mut math);
     spacing::define(&mut math);
     for (name, symbol) in crate::symbols::SYM {
-        math.define(*name, symbol.clone());
+        crate::symbols::define_symbol(&mut math, name, symbol);
     }
 
     Module::new("math", math)
@@ -12,11 +12,11 @@ pub(super) const WIDE: Em = Em::new(2.0);
 
 /// Hook up all spacings.
 pub(super) fn define(math: &mut Scope) {
-    math.define("thin", HElem::new(THIN.into()).pack());
-    math.define("med", HElem::new(MEDIUM.into()).pack());
-    math.define("thick", HElem::new(THICK.into()).pack());
-    math.define("quad", HElem::new(QUAD.into()).pack());
-    math.define("wide", HElem::new(WIDE.into()).pack());
+    math.define_math("thin", HElem::new(THIN.into()).pack());
+    math.define_math("med", HElem::new(MEDIUM.into()).pack());
+    math.define_math("thick", HElem::new(THICK.into()).pack());
+    math.define_math("quad", HElem::new(QUAD.into()).pack());
+    math.define_math("wide", HElem::new(WIDE.into()).pack());
 }
 
 /// Create the spacing between two fragments in a given style.
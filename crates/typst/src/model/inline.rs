@@ -4,15 +4,16 @@ use crate::diag::SourceResult;
 use crate::engine::Engine;
 use crate::foundations::{
 	elem, Args, Cast, Construct, Content, NativeElement, Packed, Set, Smart, StyleChain,
-	Unlabellable,
+	StyledElem, Unlabellable,
 };
-use crate::layout::{Em, Fragment, Length, Size};
+use crate::layout::{Em, Fragment, Length, LineMetrics, Ratio, Size};
 
 /// Arranges text, spacing and inline-level elements into an inline element.
 ///
 /// Although this function is primarily used in set rules to affect inline element
-/// properties, it can also be used to explicitly render its argument onto a
-/// inline element of its own.
+/// properties, it can also be used to explicitly mark its argument as inline
+/// content. Such an element flows into the surrounding paragraph without a
+/// break, but can be targeted by show rules and labels.
 ///
 /// # Example
 /// ```example
@@ -40,6 +41,21 @@ pub struct InlineElem {
 	#[default(Em::new(0.65).into())]
 	pub leading: Length,
 
+	/// How the [`leading`]($inline.leading) is distributed around lines.
+	///
+	/// By default, the leading is only inserted between consecutive lines. With
+	/// `{"half"}`, each line instead receives half of the leading above and
+	/// half below itself, similar to CSS. This makes short text come out
+	/// visually centered in boxes and table cells.
+	///
+	/// ```example
+	/// #set inline(leading: 1em, leading-distribution: "half")
+	/// #box(fill: aqua, height: 2em, align(horizon)[Centered])
+	/// ```
+	#[ghost]
+	#[default(LeadingDistribution::Between)]
+	pub leading_distribution: LeadingDistribution,
+
 	/// Whether to justify text in its line.
 	///
 	/// Hyphenation will be enabled for justified paragraphs if the
@@ -53,10 +69,44 @@ pub struct InlineElem {
 	#[default(false)]
 	pub justify: bool,
 
-	/// The indent all but the first line of a paragraph should have.
+	/// Whether to also justify the last line of justified text.
+	///
+	/// A last line that consists of a single word is never stretched.
+	///
+	/// ```example
+	/// #set page(width: 180pt)
+	/// #set inline(justify: true, justify-last: true)
+	/// This text is justified, including its
+	/// last line.
+	/// ```
 	#[ghost]
-	#[resolve]
-	pub hanging_indent: Length,
+	#[default(false)]
+	pub justify_last: bool,
+
+	/// How much the spaces of justified text may shrink, relative to how much
+	/// they can shrink by default.
+	///
+	/// Lines that would need to shrink more are avoided by the line breaker
+	/// or, if there is no alternative, overflow.
+	#[ghost]
+	#[default(Ratio::one())]
+	pub min_shrink: Ratio,
+
+	/// How much the spaces of justified text may stretch, relative to how much
+	/// they can stretch by default.
+	///
+	/// Lines that would need to stretch more are avoided by the line breaker
+	/// or, if there is no alternative, are not justified. When set to
+	/// `{auto}`, there is no limit.
+	///
+	/// ```example
+	/// #set page(width: 140pt)
+	/// #set inline(justify: true, max-stretch: 150%)
+	/// A line with a long word like
+	/// incomprehensibilities.
+	/// ```
+	#[ghost]
+	pub max_stretch: Smart<Ratio>,
 
 	/// How to determine line breaks.
 	///
@@ -91,6 +141,14 @@ pub struct InlineElem {
 	#[default(true)]
 	pub shrink: bool,
 
+	/// Whether the element was created with the `inline` function.
+	///
+	/// Such elements are unwrapped into the surrounding inline content during
+	/// realization, once all show rules have been applied to them.
+	#[internal]
+	#[default(false)]
+	pub explicit: bool,
+
 	/// The contents of the inline element.
 	#[external]
 	#[required]
@@ -104,22 +162,31 @@ pub struct InlineElem {
 
 impl Construct for InlineElem {
 	fn construct(engine: &mut Engine, args: &mut Args) -> SourceResult<Content> {
-		// The inline constructor is special: It doesn't create an inline element
-		// element. Instead, it just ensures that the passed content lives in a
-		// separate inline and styles it.
+		// The inline constructor is special: The element it creates doesn't form
+		// an inline run of its own. It is unwrapped into the surrounding inline
+		// content during realization, but can still be targeted by show rules
+		// and labels. Its styles are kept separate from those of the body, so
+		// that they don't interrupt the surrounding paragraph.
 		let styles = Self::set(engine, args)?;
 		let body = args.expect::<Content>("body")?;
-		Ok(body.styled_with_map(styles))
+		let child =
+			if styles.is_empty() { body } else { StyledElem::new(body, styles).pack() };
+		Ok(InlineElem::new(vec![child]).with_explicit(true).pack())
 	}
 }
 
 impl Packed<InlineElem> {
 	/// Layout the inline content into a collection of lines.
+	///
+	/// The `consecutive` flag indicates whether the content directly follows
+	/// other inline content in the flow. It determines whether the first line
+	/// is indented.
 	#[typst_macros::time(name = "inline", span = self.span())]
 	pub fn layout(
 		&self,
 		engine: &mut Engine,
 		styles: StyleChain,
+		consecutive: bool,
 		region: Size,
 		expand: bool,
 	) -> SourceResult<Fragment> {
@@ -127,10 +194,53 @@ impl Packed<InlineElem> {
 			self.children(),
 			engine,
 			styles,
+			consecutive,
 			region,
 			expand,
 		)
 	}
+
+	/// Layout the inline content into a collection of lines and additionally
+	/// report the metrics of each line.
+	#[typst_macros::time(name = "inline", span = self.span())]
+	pub fn layout_with_metrics(
+		&self,
+		engine: &mut Engine,
+		styles: StyleChain,
+		consecutive: bool,
+		region: Size,
+		expand: bool,
+	) -> SourceResult<(Fragment, Vec<LineMetrics>)> {
+		crate::layout::layout_inline_with_metrics(
+			self.children(),
+			engine,
+			styles,
+			consecutive,
+			region,
+			expand,
+		)
+	}
+
+	/// Layout the inline content and report the metrics of its lines.
+	///
+	/// The lines are the same as those produced by [`layout`](Self::layout),
+	/// in order from top to bottom. Inline content without any children has
+	/// no lines.
+	pub fn layout_lines(
+		&self,
+		engine: &mut Engine,
+		styles: StyleChain,
+		consecutive: bool,
+		region: Size,
+		expand: bool,
+	) -> SourceResult<Vec<LineMetrics>> {
+		if self.children().is_empty() {
+			return Ok(vec![]);
+		}
+
+		self.layout_with_metrics(engine, styles, consecutive, region, expand)
+			.map(|(_, metrics)| metrics)
+	}
 }
 
 impl Debug for InlineElem {
@@ -140,6 +250,15 @@ impl Debug for InlineElem {
 	}
 }
 
+/// How the leading is distributed around the lines of an inline element.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Cast)]
+pub enum LeadingDistribution {
+	/// Insert the full leading between consecutive lines.
+	Between,
+	/// Put half of the leading above and half below each line.
+	Half,
+}
+
 /// How to determine line breaks in an inline element.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Cast)]
 pub enum Linebreaks {
@@ -3,7 +3,8 @@ use ecow::{eco_format, EcoString};
 use crate::diag::{At, SourceResult};
 use crate::engine::Engine;
 use crate::foundations::{
-    cast, elem, Content, Label, Packed, Repr, Show, Smart, StyleChain,
+    cast, dict, elem, func, scope, Content, Dict, Label, Packed, Repr, Show, Smart, Str,
+    StyleChain,
 };
 use crate::introspection::Location;
 use crate::layout::Position;
@@ -28,8 +29,9 @@ use crate::text::{Hyphenate, TextElem};
 ///
 /// # Syntax
 /// This function also has dedicated syntax: Text that starts with `http://` or
-/// `https://` is automatically turned into a link.
-#[elem(Show)]
+/// `https://` is automatically turned into a link. Use [`link.parse`] to split
+/// such a link into its parts.
+#[elem(scope, Show)]
 pub struct LinkElem {
     /// The destination the link points to.
     ///
@@ -81,6 +83,39 @@ pub struct LinkElem {
     pub body: Content,
 }
 
+#[scope]
+impl LinkElem {
+    /// Splits a URL into its parts, like an automatic link in markup.
+    ///
+    /// Returns a dictionary with the `scheme` (e.g. `{"https"}`), the
+    /// `authority` (e.g. `{"typst.app"}`), and the `path` including the query
+    /// and fragment (e.g. `{"/docs?q=1"}`). Returns `{none}` if the URL
+    /// wouldn't be turned into an automatic link as a whole.
+    ///
+    /// ```example
+    /// #show link: it => {
+    ///   let parts = if type(it.dest) == str { link.parse(it.dest) }
+    ///   if parts == none { it } else [#it (#parts.authority)]
+    /// }
+    ///
+    /// https://typst.app/docs \
+    /// #link("mailto:hello@typst.app")
+    /// ```
+    #[func]
+    pub fn parse(
+        /// The URL to split.
+        url: Str,
+    ) -> Option<Dict> {
+        let parts = crate::syntax::parse_link_prefix(&url)
+            .filter(|parts| parts.path.end == url.len())?;
+        Some(dict! {
+            "scheme" => &url[parts.scheme],
+            "authority" => &url[parts.authority],
+            "path" => &url[parts.path],
+        })
+    }
+}
+
 impl LinkElem {
     /// Create a link element from a URL with its bare text.
     pub fn from_url(url: EcoString) -> Self {
@@ -1,14 +1,12 @@
 use std::fmt::{self, Debug, Formatter};
-use std::println;
 
 use crate::diag::SourceResult;
 use crate::engine::Engine;
 use crate::foundations::{
-	elem, Args, Cast, Construct, Content, NativeElement, Packed, Set, Smart, StyleChain,
+	elem, Args, Construct, Content, NativeElement, Packed, Set, Smart, StyleChain,
 	Unlabellable,
 };
-use crate::layout::{Em, Fragment, Length, Size, HElem, FlowElem, LayoutMultiple, Regions};
-use crate::model::{InlineElem};
+use crate::layout::{FlowElem, Fragment, LayoutMultiple, Length, Regions};
 
 /// Arranges text, spacing and inline-level elements into a paragraph.
 ///
@@ -40,21 +38,90 @@ pub struct ParElem {
 	/// The indent the first line of a paragraph should have.
 	///
 	/// Only the first line of a consecutive paragraph will be indented (not
-	/// the first one in a block or on the page).
+	/// the first one in a block or on the page), unless
+	/// [`always-indent-first-line`]($par.always-indent-first-line) is enabled.
 	///
 	/// By typographic convention, paragraph breaks are indicated either by some
 	/// space between paragraphs or by indented first lines. Consider reducing
 	/// the [paragraph spacing]($block.spacing) to the [`leading`]($par.leading)
 	/// when using this property (e.g. using
 	/// `[#show par: set block(spacing: 0.65em)]`).
+	///
+	/// When set to `{auto}`, the indent is one em of the current font size, so
+	/// that it scales with the text.
+	///
+	/// ```example
+	/// #set par(first-line-indent: auto)
+	/// #set block(spacing: 0.65em)
+	/// The indent of this paragraph ...
+	///
+	/// ... matches the size of its text.
+	///
+	/// #set text(size: 14pt)
+	/// So this one ...
+	///
+	/// ... is indented a bit more.
+	/// ```
 	#[ghost]
-	pub first_line_indent: Length,
+	#[default(Smart::Custom(Length::zero()))]
+	pub first_line_indent: Smart<Length>,
 
-	/// Whether or not the first line is always intented, or only when it is a consecutive paragraph
+	/// Whether to indent the first line of every paragraph, including the first
+	/// one in a block or after a heading.
 	#[ghost]
 	#[default(false)]
 	pub always_indent_first_line: bool,
 
+	/// The indent all but the first line of a paragraph should have.
+	///
+	/// The first line is still indented by the
+	/// [`first-line-indent`]($par.first-line-indent). In right-to-left text,
+	/// the lines are indented from the right.
+	///
+	/// ```example
+	/// #set par(hanging-indent: 1em)
+	///
+	/// #lorem(15)
+	/// ```
+	#[ghost]
+	#[resolve]
+	pub hanging_indent: Length,
+
+	/// The minimum number of lines of a paragraph that stay at the bottom of
+	/// a page or column before it breaks.
+	///
+	/// If too few lines fit, they move to the next page along with the rest
+	/// of the paragraph. A value of `{0}` or `{1}` disables this, as does an
+	/// [`orphan` cost]($text.costs) of `{0%}`.
+	///
+	/// ```example
+	/// #set page(height: 80pt)
+	/// #set par(orphans: 3)
+	/// #lorem(8)
+	///
+	/// #lorem(30)
+	/// ```
+	#[ghost]
+	#[default(2)]
+	pub orphans: usize,
+
+	/// The minimum number of lines of a paragraph that are carried over to
+	/// the top of the next page or column when it breaks.
+	///
+	/// If the paragraph has too few lines to keep both the
+	/// [orphans]($par.orphans) and the widows together, it moves to the next
+	/// page as a whole. A value of `{0}` or `{1}` disables this, as does a
+	/// [`widow` cost]($text.costs) of `{0%}`.
+	///
+	/// ```example
+	/// #set page(height: 80pt)
+	/// #set par(widows: 3)
+	/// #lorem(24)
+	/// ```
+	#[ghost]
+	#[default(2)]
+	pub widows: usize,
+
 	/// The paragraph's children.
 	#[internal]
 	#[variadic]
@@ -85,46 +152,11 @@ impl LayoutMultiple for Packed<ParElem> {
 		styles: StyleChain,
 		regions: Regions,
 	) -> SourceResult<Fragment> {
-		println!("We made it!");
-		// if consecutive || ParElem::always_indent_first_line_in(styles) {
-		if self.children[0].is::<InlineElem>() {
-			println!("InlineElem");
-			// self.children[0]
-			// 	.to_packed::<InlineElem>()
-			// 	.unwrap_or_default()
-			// 	.children
-			// 	.insert(0,HElem::new(ParElem::first_line_indent_in(styles).into()).into());
-		}
-		// }
-
-		let children = &self.children;
-		Packed::new(FlowElem::new(children.to_vec())).layout(engine, styles, regions)
-
-	// 	let mut frames = Vec::new(); 
-	// 	for (i,child) in self.children.into_iter().enumerate() {
-	// 		if i == 0 && consecutive {
-	// 			if let Some(elem) = child.to_packed::<InlineElem>() {
-	// 				let mut grandChildren = elem.children;
-	// 				grandChildren.insert(0,HElem::new(ParElem::first_line_indent_in(styles).into()).into());
-	// 			}
-	// 		}
-	// 		let frames = if let Some(layoutable) = child.with::<dyn LayoutSingle>() {
-	// 			child.layout(
-	// 				self.children(),
-	// 				engine,
-	// 				styles,
-	// 				region,
-	// 				expand,
-	// 			)
-	// 			.into_frames()
-	// 		} else if child.can::<dyn LayoutMultiple>() {
-	// 			child.layout(
-
-	// 			)
-	// 			.into_frames()
-	// 		};
-	// 	}
-		// Ok(Fragment::frames(frames))
+		// Whether the first line is indented is decided by inline layout, which
+		// knows whether the inline content is consecutive.
+		Packed::new(FlowElem::new(self.children.to_vec()))
+			.spanned(self.span())
+			.layout(engine, styles, regions)
 	}
 }
 
@@ -139,7 +171,8 @@ impl Debug for ParElem {
 ///
 /// This starts a new paragraph. Especially useful when used within code like
 /// [for loops]($scripting/#loops). Multiple consecutive
-/// paragraph breaks collapse into a single one.
+/// paragraph breaks collapse into a single one, unless they are
+/// [strong]($parbreak.weak).
 ///
 /// # Example
 /// ```example
@@ -154,6 +187,25 @@ impl Debug for ParElem {
 /// Instead of calling this function, you can insert a blank line into your
 /// markup to create a paragraph break.
 #[elem(title = "Paragraph Break", Unlabellable)]
-pub struct ParbreakElem {}
+pub struct ParbreakElem {
+	/// If `{true}`, the paragraph break collapses with adjacent paragraph
+	/// breaks.
+	///
+	/// Consecutive strong paragraph breaks don't collapse: Each one after the
+	/// first adds another gap of [block spacing]($block.spacing). Unlike weak
+	/// ones, which only separate paragraphs, strong paragraph breaks can also
+	/// be targeted by show rules.
+	///
+	/// ```example
+	/// Roses are red,
+	/// violets are blue.
+	/// #parbreak(weak: false)
+	/// #parbreak(weak: false)
+	/// Sugar is sweet,
+	/// and so are you.
+	/// ```
+	#[default(true)]
+	pub weak: bool,
+}
 
 impl Unlabellable for Packed<ParbreakElem> {}
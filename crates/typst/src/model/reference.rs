@@ -58,6 +58,8 @@ use crate::text::TextElem;
 /// This function also has dedicated syntax: A reference to a label can be
 /// created by typing an `@` followed by the name of the label (e.g.
 /// `[= Introduction <intro>]` can be referenced by typing `[@intro]`).
+/// Trailing dots and colons are not part of the reference, so you can end a
+/// sentence with one.
 ///
 /// To customize the supplement, add content in square brackets after the
 /// reference: `[@intro[Chapter]]`.
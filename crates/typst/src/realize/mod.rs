@@ -67,9 +67,6 @@ pub fn realize_block<'a>(
 	content: &'a Content,
 	styles: StyleChain<'a>,
 ) -> SourceResult<(Cow<'a, Content>, StyleChain<'a>)> {
-
-	println!("Content Type: {}", content.func().name());
-
 	// These elements implement `Layout` but still require a flow for
 	// proper layout.
 	if content.can::<dyn LayoutMultiple>() && !processable(engine, content, styles) {
@@ -102,6 +99,9 @@ struct Builder<'a, 'v, 't> {
 	list: ListBuilder<'a>,
 	/// The current citation grouping state.
 	cites: CiteGroupBuilder<'a>,
+	/// Whether we are realizing the output of a show rule on an implicit
+	/// inline run, in which case new runs count as already realized.
+	realizing_inline: bool,
 }
 
 impl<'a, 'v, 't> Builder<'a, 'v, 't> {
@@ -114,6 +114,7 @@ impl<'a, 'v, 't> Builder<'a, 'v, 't> {
 			inline: InlineBuilder::default(),
 			list: ListBuilder::default(),
 			cites: CiteGroupBuilder::default(),
+			realizing_inline: false,
 		}
 	}
 
@@ -122,13 +123,19 @@ impl<'a, 'v, 't> Builder<'a, 'v, 't> {
 		mut content: &'a Content,
 		styles: StyleChain<'a>,
 	) -> SourceResult<()> {
-		println!("Accepting: {}", content.func().name());
 		if content.can::<dyn LayoutMath>() && !content.is::<EquationElem>() {
 			content = self
 				.arenas
 				.store(EquationElem::new(content.clone()).pack().spanned(content.span()));
 		}
-		if let Some(realized) = process(self.engine, content, styles)? {
+		// Weak paragraph breaks only separate paragraphs and aren't subject to
+		// show rules.
+		let weak_parbreak = content
+			.to_packed::<ParbreakElem>()
+			.is_some_and(|parbreak| parbreak.weak(styles));
+		let realized =
+			if weak_parbreak { None } else { process(self.engine, content, styles)? };
+		if let Some(realized) = realized {
 			self.engine.route.increase();
 			if !self.engine.route.within(Route::MAX_SHOW_RULE_DEPTH) {
 				bail!(
@@ -136,16 +143,12 @@ impl<'a, 'v, 't> Builder<'a, 'v, 't> {
 					hint: "check whether the show rule matches its own output"
 				);
 			}
-			println!("Processed");
-			println!("Going deeper:");
 			let result = self.accept(self.arenas.store(realized), styles);
-			println!("Coming up!");
 			self.engine.route.decrease();
 			return result;
 		}
 
 		if let Some(styled) = content.to_packed::<StyledElem>() {
-			println!("Styled!\n");
 			return self.styled(styled, styles);
 		}
 
@@ -156,34 +159,35 @@ impl<'a, 'v, 't> Builder<'a, 'v, 't> {
 			return Ok(());
 		}
 
+		if let Some(inline) = content.to_packed::<InlineElem>() {
+			if inline.explicit(styles) {
+				return self.unwrap_inline(inline, styles);
+			}
+		}
+
 		if self.cites.accept(content, styles) {
-			println!("Cites!\n");
 			return Ok(());
 		}
 
 		self.interrupt_cites()?;
 
 		if self.list.accept(content, styles) {
-			println!("List 1!\n");
 			return Ok(());
 		}
 
 		self.interrupt_list()?;
 
 		if self.list.accept(content, styles) {
-			println!("List 2!\n");
 			return Ok(());
 		}
 
 		if self.inline.accept(content, styles) {
-			println!("Inline!\n");
 			return Ok(());
 		}
 
 		self.interrupt_inline()?;
 
 		if self.flow.accept(self.arenas, content, styles) {
-			println!("Flow!\n");
 			return Ok(());
 		}
 
@@ -194,12 +198,9 @@ impl<'a, 'v, 't> Builder<'a, 'v, 't> {
 		self.interrupt_page(keep.then_some(styles), false)?;
 
 		if let Some(doc) = &mut self.doc {
-			println!("Doc!");
 			if doc.accept(self.arenas, content, styles) {
-				println!("Doc accepted!\n");
 				return Ok(());
 			}
-			println!("");
 		}
 
 		if content.is::<PagebreakElem>() {
@@ -222,6 +223,26 @@ impl<'a, 'v, 't> Builder<'a, 'v, 't> {
 		Ok(())
 	}
 
+	/// Unwraps an explicit inline element into the surrounding content.
+	///
+	/// The element's own styles are applied to its children without
+	/// interrupting the current inline run.
+	fn unwrap_inline(
+		&mut self,
+		inline: &'a Packed<InlineElem>,
+		styles: StyleChain<'a>,
+	) -> SourceResult<()> {
+		for child in &inline.children {
+			if let Some(styled) = child.to_packed::<StyledElem>() {
+				let stored = self.arenas.store(styles);
+				self.accept(&styled.child, stored.chain(&styled.styles))?;
+			} else {
+				self.accept(child, styles)?;
+			}
+		}
+		Ok(())
+	}
+
 	fn interrupt_style(
 		&mut self,
 		local: &Styles,
@@ -287,7 +308,21 @@ impl<'a, 'v, 't> Builder<'a, 'v, 't> {
 		self.interrupt_list()?;
 		if !self.inline.0.is_empty() {
 			let (inline, styles) = mem::take(&mut self.inline).finish();
-			self.accept(self.arenas.store(inline.pack()), styles)?;
+			let content = self.arenas.store(inline.pack());
+			if self.realizing_inline {
+				// Runs produced by a show rule on an inline run are already
+				// realized. Showing them again would never terminate for a
+				// rule that produces more inline content.
+				self.flow.accept(self.arenas, content, styles);
+			} else {
+				// Trailing inline content of the rule's output is flushed
+				// here, so that it precedes whatever interrupted the run.
+				self.realizing_inline = true;
+				let result =
+					self.accept(content, styles).and_then(|_| self.interrupt_inline());
+				self.realizing_inline = false;
+				result?;
+			}
 		}
 
 		Ok(())
@@ -372,8 +407,11 @@ impl Default for DocBuilder<'_> {
 }
 
 /// Accepts flow content.
+///
+/// Also tracks whether the last element was a paragraph break and whether the
+/// current run of paragraph breaks contains a strong one.
 #[derive(Default)]
-struct FlowBuilder<'a>(BehavedBuilder<'a>, bool);
+struct FlowBuilder<'a>(BehavedBuilder<'a>, bool, bool);
 
 impl<'a> FlowBuilder<'a> {
 	fn accept(
@@ -382,13 +420,23 @@ impl<'a> FlowBuilder<'a> {
 		content: &'a Content,
 		styles: StyleChain<'a>,
 	) -> bool {
-		if content.is::<ParbreakElem>() {
+		if let Some(parbreak) = content.to_packed::<ParbreakElem>() {
+			// Strong paragraph breaks don't collapse with each other, so each
+			// one after the first in a run adds another gap.
+			if !parbreak.weak(styles) {
+				if self.2 {
+					let spacing = BlockElem::below_in(styles).amount().clone();
+					self.0.push(arenas.store(VElem::strong(spacing).pack()), styles);
+				}
+				self.2 = true;
+			}
 			self.1 = true;
 			return true;
 		}
 
 		let last_was_parbreak = self.1;
 		self.1 = false;
+		self.2 = false;
 
 		if content.is::<VElem>()
 			|| content.is::<ColbreakElem>()
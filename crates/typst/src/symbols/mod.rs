@@ -8,7 +8,7 @@ pub use self::emoji::*;
 pub use self::sym::*;
 pub use self::symbol::*;
 
-use crate::foundations::{category, Category, Scope};
+use crate::foundations::{category, Category, IntoValue, Scope};
 
 /// These two modules give names to symbols and emoji to make them easy to
 /// insert with a normal keyboard. Alternatively, you can also always directly
@@ -22,6 +22,7 @@ pub static SYMBOLS: Category;
 pub(super) fn define(global: &mut Scope) {
     global.category(SYMBOLS);
     global.define_type::<Symbol>();
-    global.define_module(sym());
-    global.define_module(emoji());
+    // These modules are large, so we only build them when they are used.
+    global.define_lazy("sym", || sym().into_value());
+    global.define_lazy("emoji", || emoji().into_value());
 }
@@ -1,15 +1,28 @@
 use crate::foundations::{Module, Scope};
 use crate::symbols::{symbols, Symbol};
+use crate::syntax::is_keyword;
 
 /// A module with all general symbols.
 pub fn sym() -> Module {
     let mut scope = Scope::new();
     for (name, symbol) in SYM {
-        scope.define(*name, symbol.clone());
+        define_symbol(&mut scope, name, symbol);
     }
     Module::new("sym", scope)
 }
 
+/// Define a symbol, which may be named like a keyword.
+///
+/// Symbols like `sym.in` can still be reached as module members and in math,
+/// so their names skip the keyword check.
+pub(crate) fn define_symbol(scope: &mut Scope, name: &str, symbol: &Symbol) {
+    if is_keyword(name) {
+        scope.define_raw(name, symbol.clone());
+    } else {
+        scope.define(name, symbol.clone());
+    }
+}
+
 /// The list of general symbols.
 pub(crate) const SYM: &[(&str, Symbol)] = symbols! {
     // Control.
@@ -273,8 +273,14 @@ impl Show for Packed<StrikeElem> {
 ///
 /// # Example
 /// ```example
-/// This is #highlight[important].
+/// This is #highlight[important]. \
+/// This is ==important, too.==
 /// ```
+///
+/// # Syntax
+/// This function also has dedicated syntax: To highlight content, simply
+/// enclose it in double equals signs (`==`). Note that this only works at word
+/// boundaries. To highlight part of a word, you have to use the function.
 #[elem(Show)]
 pub struct HighlightElem {
     /// The color to highlight the text with.
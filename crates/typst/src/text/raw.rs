@@ -18,7 +18,7 @@ use crate::foundations::{
 };
 use crate::layout::{BlockElem, Em, HAlignment};
 use crate::model::{Figurable, InlineElem};
-use crate::syntax::{split_newlines, LinkedNode, Span, Spanned};
+use crate::syntax::{lines, LinkedNode, Span, Spanned};
 use crate::text::{
     FontFamily, FontList, Hyphenate, LinebreakElem, LocalName, SmartQuoteElem, TextElem,
     TextSize,
@@ -601,7 +601,7 @@ impl<'a> ThemedHighlighter<'a> {
             let segment = &self.code[self.node.range()];
 
             let mut len = 0;
-            for (i, line) in split_newlines(segment).into_iter().enumerate() {
+            for (i, line) in lines(segment).enumerate() {
                 if i != 0 {
                     (self.line_fn)(
                         self.line,
@@ -657,10 +657,7 @@ fn preprocess(
         let tab_size = RawElem::tab_size_in(styles);
         text = align_tabs(&text, tab_size);
     }
-    split_newlines(&text)
-        .into_iter()
-        .map(|line| (line.into(), span))
-        .collect()
+    lines(&text).map(|line| (line.into(), span)).collect()
 }
 
 /// Style a piece of text with a syntect style.
@@ -23,6 +23,12 @@ static ARGS: Lazy<CliArguments> = Lazy::new(CliArguments::parse);
 /// The directory where the test suite is located.
 const SUITE_PATH: &str = "tests/suite";
 
+/// The directory of tests that are checked with all opt-in lints enabled.
+const LINT_PATH: &str = "tests/suite/lint";
+
+/// The directory of tests that are checked with raised evaluation limits.
+const LIMITS_PATH: &str = "tests/suite/limits";
+
 /// The directory where the full test results are stored.
 const STORE_PATH: &str = "tests/store";
 
@@ -5,22 +5,32 @@ use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 
-use comemo::Prehashed;
+use comemo::{Prehashed, Tracked};
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
-use typst::diag::{bail, FileError, FileResult, StrResult};
-use typst::foundations::{func, Bytes, Datetime, NoneValue, Repr, Smart, Value};
-use typst::layout::{Abs, Margin, PageElem};
-use typst::syntax::{FileId, Source};
+use typst::diag::{bail, At, FileError, FileResult, SourceResult, StrResult};
+use typst::engine::Engine;
+use typst::foundations::{
+    dict, func, Args, Array, Bytes, Content, Context, Datetime, Dict, IntoValue,
+    NativeElement, NoneValue, Repr, Resolve, Set, Smart, Str, StyledElem, Value,
+};
+use typst::layout::{
+    measure, Abs, Axes, BlockElem, FlowElem, Frame, FrameItem, LayoutMultiple, Length,
+    Margin, PageElem, Regions, Size,
+};
+use typst::model::{InlineElem, ParElem};
+use typst::realize::{realize_block, Arenas};
+use typst::syntax::{FileId, Source, Span};
 use typst::text::{Font, FontBook, TextElem, TextSize};
 use typst::visualize::Color;
-use typst::{Library, World};
+use typst::{Library, LibraryBuilder, Lints, World};
 
 /// A world that provides access to the tests environment.
 #[derive(Clone)]
 pub struct TestWorld {
     main: Source,
     base: &'static TestBase,
+    library: &'static Prehashed<Library>,
 }
 
 impl TestWorld {
@@ -30,13 +40,22 @@ impl TestWorld {
     /// initialized just once.
     pub fn new(source: Source) -> Self {
         static BASE: Lazy<TestBase> = Lazy::new(TestBase::default);
-        Self { main: source, base: &*BASE }
+        let base = &*BASE;
+        let path = source.id().vpath().as_rootless_path();
+        let library = if path.starts_with(crate::LINT_PATH) {
+            &base.linted_library
+        } else if path.starts_with(crate::LIMITS_PATH) {
+            &base.raised_library
+        } else {
+            &base.library
+        };
+        Self { main: source, base, library }
     }
 }
 
 impl World for TestWorld {
     fn library(&self) -> &Prehashed<Library> {
-        &self.base.library
+        self.library
     }
 
     fn book(&self) -> &Prehashed<FontBook> {
@@ -82,6 +101,8 @@ impl TestWorld {
 /// Shared foundation of all test worlds.
 struct TestBase {
     library: Prehashed<Library>,
+    linted_library: Prehashed<Library>,
+    raised_library: Prehashed<Library>,
     book: Prehashed<FontBook>,
     fonts: Vec<Font>,
     slots: Mutex<HashMap<FileId, FileSlot>>,
@@ -94,8 +115,17 @@ impl Default for TestBase {
             .flat_map(|data| Font::iter(Bytes::from_static(data)))
             .collect();
 
+        let lints = Lints {
+            shadowing: true,
+            unused: true,
+            allow_unused_params: false,
+        };
         Self {
-            library: Prehashed::new(library()),
+            library: Prehashed::new(library(Library::builder())),
+            linted_library: Prehashed::new(library(Library::builder().with_lints(lints))),
+            raised_library: Prehashed::new(library(
+                Library::builder().with_max_call_depth(100),
+            )),
             book: Prehashed::new(FontBook::from_fonts(&fonts)),
             fonts,
             slots: Mutex::new(HashMap::new()),
@@ -169,11 +199,11 @@ fn read(path: &Path) -> FileResult<Cow<'static, [u8]>> {
 }
 
 /// The extended standard library for testing.
-fn library() -> Library {
+fn library(builder: LibraryBuilder) -> Library {
     // Set page width to 120pt with 10pt margins, so that the inner page is
     // exactly 100pt wide. Page height is unbounded and font size is 10pt so
     // that it multiplies to nice round numbers.
-    let mut lib = Library::default();
+    let mut lib = builder.build();
 
     #[func]
     fn test(lhs: Value, rhs: Value) -> StrResult<NoneValue> {
@@ -205,16 +235,154 @@ fn library() -> Library {
         NoneValue
     }
 
+    /// Counts how often it was called with a key, including this call.
+    ///
+    /// The count is an untracked side effect, so cached calls of closures
+    /// that call this don't increase it.
+    #[func]
+    fn tally(key: Str) -> i64 {
+        static TALLIES: Lazy<Mutex<HashMap<Str, i64>>> = Lazy::new(Mutex::default);
+        let mut tallies = TALLIES.lock();
+        let count = tallies.entry(key).or_default();
+        *count += 1;
+        *count
+    }
+
+    /// Lays out inline content and returns the metrics of its lines.
+    #[func(contextual)]
+    fn lines(
+        engine: &mut Engine,
+        context: Tracked<Context>,
+        span: Span,
+        body: Content,
+        #[named]
+        #[default(Abs::pt(100.0).into())]
+        width: Length,
+    ) -> SourceResult<Array> {
+        let styles = context.styles().at(span)?;
+        let arenas = Arenas::default();
+        let (flow, styles) = realize_block(engine, &arenas, &body, styles)?;
+        let Some(flow) = flow.to_packed::<FlowElem>() else {
+            bail!(span, "expected inline content");
+        };
+
+        let mut lines = Array::new();
+        for child in flow.children() {
+            let (child, styles) = match child.to_packed::<StyledElem>() {
+                Some(styled) => (&styled.child, styles.chain(&styled.styles)),
+                None => (child, styles),
+            };
+
+            let Some(inline) = child.to_packed::<InlineElem>() else { continue };
+            let region = Size::new(width.resolve(styles), Abs::inf());
+            let metrics = inline.layout_lines(engine, styles, false, region, true)?;
+            for line in metrics {
+                let line = dict! {
+                    "width" => line.width,
+                    "natural-width" => line.natural_width,
+                    "spaces" => line.spaces,
+                    "hyphenated" => line.hyphenated,
+                    "baseline" => line.baseline,
+                    "height" => line.size.y,
+                    "start" => line.range.start,
+                    "end" => line.range.end,
+                    "justified" => line.justified,
+                };
+                lines.push(line.into_value());
+            }
+        }
+
+        Ok(lines)
+    }
+
+    /// Checks that two lengths are equal up to rounding errors.
+    #[func]
+    fn test_close(lhs: Length, rhs: Length) -> StrResult<NoneValue> {
+        if lhs.em != rhs.em || (lhs.abs - rhs.abs).to_pt().abs() >= 1e-3 {
+            bail!("Assertion failed: {} != {}", lhs.repr(), rhs.repr());
+        }
+        Ok(NoneValue)
+    }
+
+    /// Measures content in a block, with the given paragraph properties.
+    #[func(contextual)]
+    fn measure_par(
+        engine: &mut Engine,
+        context: Tracked<Context>,
+        args: &mut Args,
+        span: Span,
+        body: Content,
+    ) -> SourceResult<Dict> {
+        let styles = ParElem::set(engine, args)?;
+        args.take().finish()?;
+        let block = BlockElem::new().with_body(Some(body.styled_with_map(styles)));
+        measure(engine, context, span, block.pack(), None)
+    }
+
+    /// Lays out content into regions of the given height, one after another
+    /// like columns, and returns how many lines of text each region holds.
+    #[func(contextual)]
+    fn region_lines(
+        engine: &mut Engine,
+        context: Tracked<Context>,
+        span: Span,
+        height: Length,
+        body: Content,
+        #[named]
+        #[default(Abs::pt(80.0).into())]
+        width: Length,
+    ) -> SourceResult<Array> {
+        /// Collect the baselines of all text in the frame.
+        fn baselines(frame: &Frame, offset: Abs, ys: &mut Vec<Abs>) {
+            for (pos, item) in frame.items() {
+                match item {
+                    FrameItem::Group(group) => {
+                        baselines(&group.frame, offset + pos.y, ys)
+                    }
+                    FrameItem::Text(_) => ys.push(offset + pos.y),
+                    _ => {}
+                }
+            }
+        }
+
+        let styles = context.styles().at(span)?;
+        let size = Size::new(width.resolve(styles), height.resolve(styles));
+        let regions = Regions::repeat(size, Axes::splat(false));
+        let fragment = body.measure(engine, styles, regions)?;
+
+        let mut counts = Array::new();
+        for frame in &fragment {
+            let mut ys = vec![];
+            baselines(frame, Abs::zero(), &mut ys);
+            ys.sort();
+            ys.dedup();
+            counts.push((ys.len() as i64).into_value());
+        }
+
+        Ok(counts)
+    }
+
     // Hook up helpers into the global scope.
     lib.global.scope_mut().define_func::<test>();
     lib.global.scope_mut().define_func::<test_repr>();
     lib.global.scope_mut().define_func::<print>();
+    lib.global.scope_mut().define_func::<tally>();
+    lib.global.scope_mut().define_func::<lines>();
+    lib.global.scope_mut().define_func::<test_close>();
+    lib.global.scope_mut().define_func::<measure_par>();
+    lib.global.scope_mut().define_func::<region_lines>();
     lib.global
         .scope_mut()
         .define("conifer", Color::from_u8(0x9f, 0xEB, 0x52, 0xFF));
     lib.global
         .scope_mut()
         .define("forest", Color::from_u8(0x43, 0xA1, 0x27, 0xFF));
+    lib.global.scope_mut().define_deprecated(
+        "pine",
+        Color::from_u8(0x43, 0xA1, 0x27, 0xFF),
+        "use `forest` instead",
+        Some("0.11"),
+    );
 
     // Hook up default styles.
     lib.styles
@@ -35,6 +35,7 @@
 // Test error when captured variable is assigned to.
 #let i = 0
 // Error: 11-12 variables from outside the context expression are read-only and cannot be modified
+// Hint: 11-12 shadow it with `let` to create a mutable copy
 #context (i = 1)
 
 --- context-compatibility-locate ---
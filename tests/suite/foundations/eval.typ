@@ -10,6 +10,13 @@
 #eval("_Hello" + " World!_", mode: "markup") \
 #eval("RR_1^NN", mode: "math", scope: (RR: math.NN, NN: math.RR))
 
+--- eval-caller-scope-hidden ---
+// The evaluated code sees only the given scope, not the caller's variables.
+#let xyzzy = 1
+#test(eval("xyzzy", scope: (xyzzy: 2)), 2)
+// Error: 7-14 unknown variable: xyzzy
+#eval("xyzzy")
+
 --- eval-syntax-error-1 ---
 // Error: 7-12 expected pattern
 #eval("let")
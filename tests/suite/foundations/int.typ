@@ -12,6 +12,33 @@
 // Error: 2-8 invalid hexadecimal number: 0x123z
 #0x123z
 
+--- int-digit-separators ---
+// Test digit separators in numbers.
+#test(1_000_000, 1000000)
+#test(0xFF_FF, 0xFFFF)
+#test(0o7_7, 0o77)
+#test(0b1010_0001, 0b10100001)
+#test(3.141_592, 3.141592)
+#test(1_0e1_0, 10e10)
+#test(1_5pt, 15pt)
+#test(range(1_0, 2_0), range(10, 20))
+
+--- int-digit-separators-misplaced ---
+// Error: 2-6 digit separators must be placed between digits
+#100_
+
+--- int-digit-separators-doubled ---
+// Error: 2-6 digit separators must be placed between digits
+#1__0
+
+--- int-digit-separators-after-prefix ---
+// Error: 2-7 digit separators must be placed between digits
+#0x_FF
+
+--- int-digit-separators-before-suffix ---
+// Error: 2-7 digit separators must be placed between digits
+#10_pt
+
 --- int-constructor ---
 // Test conversion to numbers.
 #test(int(false), 0)
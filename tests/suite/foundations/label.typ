@@ -68,3 +68,32 @@ _Visible_
 #test(str(<hey>), "hey")
 #test(str(label("hey")), "hey")
 #test(str([Hmm<hey>].label), "hey")
+
+--- label-sanitize ---
+// Test creating labels from arbitrary text.
+#test(label.sanitize("What's new?"), <what-s-new>)
+#test(label.sanitize("1. Über Größe"), <über-größe>)
+#test(label.sanitize("fig:chart"), <fig-chart>)
+#test(label.sanitize("?!"), none)
+
+--- label-namespaced ---
+#test(str(<eq:euler:1>), "eq:euler:1")
+#test(str([Hi<fig.1>].label), "fig.1")
+
+--- label-trailing-dot ---
+// Warning: 11-23 label should not end with '.'
+// Hint: 11-23 a reference like `@sec:intro.` leaves the '.' to the surrounding text
+// Hint: 11-23 this will become an error in a future version
+#test(str(<sec:intro.>), "sec:intro.")
+
+--- label-trailing-colon ---
+// Warning: 7-15 label should not end with ':'
+// Hint: 7-15 a reference like `@intro:` leaves the ':' to the surrounding text
+// Hint: 7-15 this will become an error in a future version
+Hello <intro:>
+
+--- label-digit-start ---
+#test(str(<2024>), "2024")
+#test([@2024].func(), ref)
+#test([@2019smith].target, <2019smith>)
+#test([@1st.].children.first().target, <1st>)
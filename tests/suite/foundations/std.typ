@@ -20,6 +20,7 @@
 
 --- std-mutation ---
 // Error: 3-6 cannot mutate a constant: std
+// Hint: 3-6 shadow it with `let` to define a mutable variable of the same name
 #(std = 10)
 
 --- std-shadowed-mutation ---
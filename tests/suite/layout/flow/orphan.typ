@@ -29,3 +29,35 @@ This is the start and it goes on.
 // All three lines go to the next page.
 #set text(olive)
 #lorem(10)
+
+--- flow-par-widows-split ---
+// Each column fits four lines. Instead of leaving the last line alone, the
+// fourth line moves along with it.
+#set text(size: 10pt, top-edge: 1em, bottom-edge: 0pt)
+#set par(leading: 5pt)
+#context test(region-lines(55pt)[One \ Two \ Three \ Four \ Five], (3, 2))
+
+--- flow-par-widows-and-orphans-disabled ---
+#set text(size: 10pt, top-edge: 1em, bottom-edge: 0pt)
+#set par(leading: 5pt, orphans: 1, widows: 0)
+#context test(region-lines(55pt)[One \ Two \ Three \ Four \ Five], (4, 1))
+
+--- flow-par-orphans-move-paragraph ---
+// Only two lines of the second paragraph would fit, but three are required.
+#set text(size: 10pt, top-edge: 1em, bottom-edge: 0pt)
+#set par(leading: 5pt, orphans: 3)
+#set block(spacing: 5pt)
+#context test(
+  region-lines(55pt)[
+    First \ Second
+
+    Third \ Fourth \ Fifth \ Sixth
+  ],
+  (2, 4),
+)
+
+--- flow-par-widows-and-orphans-taller-than-page ---
+// The paragraph can't be kept together, so it just overflows the column.
+#set text(size: 10pt, top-edge: 1em, bottom-edge: 0pt)
+#set par(leading: 5pt, orphans: 3, widows: 3, first-line-indent: 10pt)
+#context test(region-lines(25pt)[One \ Two \ Three], (3,))
@@ -47,11 +47,99 @@ First line indents and hyphenation play nicely with justified text.
 A B C \
 D
 
+--- justify-manual-linebreak-metrics ---
+// Test that lines with hard breaks aren't justified.
+#set inline(justify: true)
+#context {
+  let lines = lines[A B C \ D]
+  test(lines.len(), 2)
+  test(lines.at(0).spaces, 2)
+  test(lines.at(0).width, lines.at(0).natural-width)
+  test(lines.at(1).width, lines.at(1).natural-width)
+  assert(lines.at(0).baseline < lines.at(1).baseline)
+}
+
 --- justify-justified-linebreak ---
 // Test forced justification with justified break.
 A B C #linebreak(justify: true)
 D E F #linebreak(justify: true)
 
+--- justify-justified-linebreak-metrics ---
+// Test forced justification with justified break.
+#context {
+  let lines = lines[
+    A B C #linebreak(justify: true)
+    D E F #linebreak(justify: true)
+  ]
+  test(lines.len(), 2)
+  for line in lines {
+    test(line.hyphenated, false)
+    assert(line.width > line.natural-width)
+  }
+}
+
+--- justify-last-line ---
+// Test that the last line is only justified on request.
+#set inline(justify: true, linebreaks: "simple")
+#context {
+  let width = measure[Alpha beta gamma].width + 1pt
+  let body = [Alpha beta gamma delta ep]
+  let ragged = lines(width: width, body)
+  let full = lines(width: width)[#set inline(justify-last: true); #body]
+  test(ragged.len(), 2)
+  test(full.len(), 2)
+  assert(ragged.at(0).width > ragged.at(0).natural-width)
+  test(ragged.at(1).width, ragged.at(1).natural-width)
+  test(ragged.at(1).justified, false)
+  assert(full.at(1).width > full.at(1).natural-width)
+  test(full.at(1).justified, true)
+}
+
+--- justify-last-line-visual ---
+// Compare a ragged last line with a justified one.
+#set page(width: 160pt)
+#set inline(justify: true, linebreaks: "simple")
+
+#block(fill: aqua)[Alpha beta gamma delta epsilon zeta]
+#set inline(justify-last: true)
+#block(fill: aqua)[Alpha beta gamma delta epsilon zeta]
+
+--- justify-last-line-single-word ---
+// Test that a single word in the last line isn't spread apart.
+#set inline(justify: true, justify-last: true, linebreaks: "simple")
+#context {
+  let width = measure[Alpha beta gamma].width + 1pt
+  let lines = lines(width: width)[Alpha beta gamma delta]
+  test(lines.len(), 2)
+  test(lines.at(1).spaces, 0)
+  test(lines.at(1).width, lines.at(1).natural-width)
+}
+
+--- justify-max-stretch ---
+// Test that lines which would need to stretch too much stay ragged.
+#set inline(justify: true, linebreaks: "simple")
+#context {
+  let width = measure[Alpha beta gamma].width + 1pt
+  let body = [Alpha beta gamma delta ep]
+  let stretched = lines(width: width, body)
+  let ragged = lines(width: width)[#set inline(max-stretch: 0%); #body]
+  assert(stretched.at(0).width > stretched.at(0).natural-width)
+  test(ragged.at(0).width, ragged.at(0).natural-width)
+}
+
+--- justify-min-shrink ---
+// Test that spaces don't shrink more than allowed.
+#set inline(justify: true, linebreaks: "optimized")
+#context {
+  let width = measure[Alpha beta gamma].width - 0.5pt
+  let body = [Alpha beta gamma]
+  let shrunk = lines(width: width, body)
+  let strict = lines(width: width)[#set inline(min-shrink: 0%); #body]
+  test(shrunk.len(), 1)
+  assert(shrunk.at(0).width < shrunk.at(0).natural-width)
+  test(strict.len(), 2)
+}
+
 --- justify-basically-empty ---
 // Test that there are no hick-ups with justification enabled and
 // basically empty paragraph.
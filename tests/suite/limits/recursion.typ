@@ -0,0 +1,10 @@
+// Test recursion with a raised maximum call depth.
+
+--- limits-recursion-raised-depth ---
+#let count(n) = if n == 0 { 0 } else { count(n - 1) + 1 }
+#test(count(90), 90)
+
+--- limits-recursion-raised-depth-exceeded ---
+// Error: 40-52 maximum function call depth exceeded
+#let count(n) = if n == 0 { 0 } else { count(n - 1) + 1 }
+#test(count(150), 150)
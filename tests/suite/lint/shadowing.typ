@@ -0,0 +1,39 @@
+// Test the opt-in lint for shadowed variables.
+
+--- lint-shadowing-std ---
+// Warning: 6-11 variable `table` shadows an earlier definition
+// Hint: 6-11 `table` is defined in the standard library
+// Hint: 6-11 prefix the name with an underscore to silence this warning
+#let table = [Not a table]
+
+--- lint-shadowing-outer ---
+#let x = 1
+#{
+  // Warning: 7-8 variable `x` shadows an earlier definition
+  // Hint: 7-8 `x` was previously defined at line 1
+  // Hint: 7-8 prefix the name with an underscore to silence this warning
+  let x = 2
+  x
+}
+
+--- lint-shadowing-loop ---
+// Warns only once, even though the binding runs in every iteration.
+#let total = 0
+#for i in range(3) {
+  // Warning: 7-12 variable `total` shadows an earlier definition
+  // Hint: 7-12 `total` was previously defined at line 2
+  // Hint: 7-12 prefix the name with an underscore to silence this warning
+  let total = i
+  total
+}
+
+--- lint-shadowing-redefine ---
+// Redefining a variable in the same scope is not shadowing.
+#let x = 1
+#let x = x + 1
+
+--- lint-shadowing-underscore ---
+#let _tmp = 1
+#{
+  let _tmp = 2
+}
@@ -0,0 +1,34 @@
+// Test the opt-in lint for unused variables.
+
+--- lint-unused-local ---
+#{
+  // Warning: 7-8 variable `x` is never used
+  // Hint: 7-8 prefix the name with an underscore to silence this warning
+  let x = 1
+}
+
+--- lint-unused-underscore ---
+#{
+  let _x = 1
+}
+
+--- lint-unused-exported ---
+// Top-level bindings are exported from the module.
+#let x = 1
+
+--- lint-unused-loop ---
+// A binding counts as used if it is used in any iteration.
+#for i in range(3) {
+  let x = i
+  if i == 2 { x }
+}
+
+--- lint-unused-param ---
+// Warning: 8-9 variable `x` is never used
+// Hint: 8-9 prefix the name with an underscore to silence this warning
+#let f(x) = 1
+#f(2)
+
+--- lint-unused-param-nested-closure ---
+#let f(x) = () => x
+#let g = f(1)
@@ -57,3 +57,19 @@ $
 10.1degree \
 10.1 degree
 $
+
+--- math-spacing-only-in-math ---
+#test($thin$.body, math.thin)
+
+// Error: 2-6 unknown variable: thin
+// Hint: 2-6 `thin` is only available in math mode
+#thin
+
+--- math-spacing-only-in-math-wildcard-import ---
+// A wildcard import of the math module doesn't make them available in code.
+#import math: *
+#test($thin$.body, math.thin)
+
+// Error: 2-6 unknown variable: thin
+// Hint: 2-6 `thin` is only available in math mode
+#thin
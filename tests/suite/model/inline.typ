@@ -0,0 +1,111 @@
+// Test configuring inline properties.
+
+--- inline-leading-distribution-half ---
+// Test that half-leading puts equal space above and below a single line.
+#context {
+  let between = measure[#set inline(leading: 10pt); A]
+  let half = measure[
+    #set inline(leading: 10pt, leading-distribution: "half")
+    A
+  ]
+  test(half.height, between.height + 10pt)
+}
+
+#context {
+  let between = lines[#set inline(leading: 10pt); A]
+  let half = lines[#set inline(leading: 10pt, leading-distribution: "half"); A]
+  test(half.at(0).baseline, between.at(0).baseline + 5pt)
+}
+
+--- inline-leading-distribution-multiple-lines ---
+// Test that half-leading keeps the distance between baselines.
+#context {
+  let body = [A \ B \ C]
+  let between = lines[#set inline(leading: 10pt); #body]
+  let half = lines[#set inline(leading: 10pt, leading-distribution: "half"); #body]
+  for i in range(1, 3) {
+    test(
+      half.at(i).baseline - half.at(i - 1).baseline,
+      between.at(i).baseline - between.at(i - 1).baseline,
+    )
+  }
+}
+
+--- inline-leading-distribution-between-uncentered ---
+// Test that without half-leading, a single line has no leading around it.
+#context {
+  let plain = measure[A]
+  let between = measure[#set inline(leading: 10pt); A]
+  test(between.height, plain.height)
+}
+
+--- inline-leading-distribution-invalid ---
+// Error: 35-41 expected "between" or "half"
+#set inline(leading-distribution: "full")
+
+--- inline-explicit-unwrapped ---
+// Test that an explicit inline element doesn't form a separate paragraph.
+#context {
+  let plain = measure[A B C]
+  test(measure[A #inline[B] C], plain)
+  test(measure[A #inline(justify: true)[B] C], plain)
+  test(measure[#inline[#inline[A B] C]], plain)
+}
+
+--- inline-show-set ---
+// Test that show-set rules apply to all inline content.
+#context {
+  let plain = measure[A]
+  [
+    #show inline: set text(size: 2 * text.size)
+    #context test(measure[A].height > plain.height, true)
+    #context test(measure[#inline[A]].height > plain.height, true)
+  ]
+}
+
+--- inline-show-transform ---
+// Test that show rules can transform explicit inline elements.
+#show inline: it => [B #it]
+#context test(measure[#inline[A]], measure[B A])
+
+--- inline-show-transform-implicit ---
+// Test that a show rule producing more inline content terminates and keeps
+// trailing content of its output in front of the interrupting block.
+#show inline: it => [B #it C]
+#context {
+  let size = measure[A #block[D]]
+  test(size.height > measure[A].height, true)
+}
+
+--- inline-labelled ---
+// Test that labels on explicit inline elements survive unwrapping.
+#inline[#h(1pt)] <inl>
+
+#context {
+  let found = query(<inl>)
+  test(found.len(), 1)
+  test(found.first().func(), inline)
+}
+
+--- inline-labelled-ref ---
+// Test that a reference resolves to a labelled explicit inline element.
+#show ref: it => test(it.element.func(), inline)
+#inline[#h(1pt)] <inl>
+@inl
+
+--- inline-lines-metrics ---
+// Test the metrics of a paragraph broken into three lines.
+#context {
+  let metrics = lines(width: 40pt)[#set inline(justify: true); AAA BBB CCC]
+  test(metrics.len(), 3)
+  for i in range(1, 3) {
+    test(metrics.at(i).baseline > metrics.at(i - 1).baseline, true)
+    test(metrics.at(i).start, metrics.at(i - 1).end)
+  }
+  test(metrics.map(line => line.justified), (true, true, false))
+  test(metrics.first().start, 0)
+}
+
+--- inline-lines-empty ---
+// Test that empty inline content has no lines.
+#context test(lines[], ())
@@ -38,6 +38,21 @@ https://example.com/)
 // Error: 1-22 automatic links cannot contain unbalanced brackets, use the `link` function instead
 https://exam(ple.com/
 
+--- link-parse ---
+// Test splitting URLs like automatic links.
+#test(
+  link.parse("https://typst.app/docs?q=1#top"),
+  (scheme: "https", authority: "typst.app", path: "/docs?q=1#top"),
+)
+#test(
+  link.parse("http://[::1]:8080"),
+  (scheme: "http", authority: "[::1]:8080", path: ""),
+)
+#test(link.parse("https://example.com/(paren)").path, "/(paren)")
+#test(link.parse("https://example.com/."), none)
+#test(link.parse("https://exam(ple.com/"), none)
+#test(link.parse("mailto:hello@typst.app"), none)
+
 --- link-show ---
 // Styled with underline and color.
 #show link: it => underline(text(fill: rgb("283663"), it))
@@ -55,6 +55,76 @@ starts a paragraph, also with indent.
 
 ثم يصبح النص رطبًا وقابل للطرق ويبدو المستند رائعًا.
 
+--- par-always-indent-first-line ---
+// Test that the first line of every paragraph can be indented, even when it
+// directly follows a heading, a list, or another paragraph.
+#let width(indent, always, body) = measure-par(
+  first-line-indent: indent,
+  always-indent-first-line: always,
+  body,
+).width
+
+#context {
+  let after-heading = [
+    = Heading
+    A paragraph after a heading.
+  ]
+  let after-list = [
+    - Item
+    A paragraph after a list.
+  ]
+  let after-par = [
+    Short.
+
+    A longer paragraph after another one.
+  ]
+
+  for body in (after-heading, after-list, after-par) {
+    let plain = width(0pt, true, body)
+    test-close(width(12pt, true, body), plain + 12pt)
+  }
+
+  for body in (after-heading, after-list) {
+    let plain = width(0pt, false, body)
+    test-close(width(12pt, false, body), plain)
+  }
+}
+
+--- par-first-line-indent-consecutive ---
+// Test that only a paragraph directly following another one is indented.
+#let width(body) = measure-par(first-line-indent: 12pt, body).width
+
+#context {
+  let long = measure[A longer paragraph.].width
+  test-close(width[A longer paragraph.], long)
+  test-close(width[
+    Short.
+
+    A longer paragraph.
+  ], long + 12pt)
+  test-close(width[
+    = Heading
+    A longer paragraph.
+  ], long)
+}
+
+--- par-first-line-indent-auto ---
+// Test that an automatic indent is one em of the paragraph's own text size.
+#let width(size, indent) = measure-par(
+  first-line-indent: indent,
+  always-indent-first-line: true,
+  text(size: size)[A paragraph.],
+).width
+
+#context {
+  let plain = width(11pt, 0pt)
+  test-close(width(11pt, auto), plain + 11pt)
+  test-close(width(11pt, auto), width(11pt, 1em))
+
+  let plain = width(18pt, 0pt)
+  test-close(width(18pt, auto), plain + 18pt)
+}
+
 --- par-spacing-and-first-line-indent ---
 // This is madness.
 #set par(first-line-indent: 12pt)
@@ -67,6 +137,17 @@ Why would anybody ever ...
 #set par(hanging-indent: 15pt, justify: true)
 #lorem(10)
 
+--- par-hanging-indent-first-line ---
+// Test that the hanging indent applies to all but the first line.
+#let width(dir, body) = measure-par(hanging-indent: 1.5em, text(dir: dir, body)).width
+
+#context for dir in (ltr, rtl) {
+  let long = measure[WWWW].width
+  let indent = 1.5em.to-absolute()
+  test-close(width(dir)[WWWW \ A], long)
+  test-close(width(dir)[A \ WWWW], long + indent)
+}
+
 --- par-hanging-indent-manual-linebreak ---
 #set par(hanging-indent: 1em)
 Welcome \ here. Does this work well?
@@ -76,3 +157,45 @@ Welcome \ here. Does this work well?
 #set text(dir: rtl)
 لآن وقد أظلم الليل وبدأت النجوم
 تنضخ وجه الطبيعة التي أعْيَتْ من طول ما انبعثت في النهار
+
+--- parbreak-weak-collapse ---
+// Test that consecutive weak paragraph breaks collapse into one.
+#set block(spacing: 10pt)
+#let height(body) = measure-par(body).height
+#context {
+  let once = height[A #parbreak() B]
+  test(height[A #parbreak() #parbreak() #parbreak() B], once)
+  test(height[A #parbreak(weak: false) B], once)
+}
+
+--- parbreak-strong ---
+// Test that consecutive strong paragraph breaks each add a gap.
+#set block(spacing: 10pt)
+#let height(body) = measure-par(body).height
+#context {
+  let once = height[A #parbreak() B]
+  let strong = parbreak(weak: false)
+  test-close(height[A #strong #strong B], once + 10pt)
+  test-close(height[A #strong #parbreak() #strong B], once + 10pt)
+  test-close(height[A #strong #strong #strong B], once + 20pt)
+}
+
+--- parbreak-show-strong ---
+// Test that show rules apply to strong paragraph breaks only, but not to
+// weak ones like those around `par`.
+#set block(spacing: 10pt)
+#let height(body) = measure-par(body).height
+#context {
+  let plain = height[A #parbreak() B]
+  let shown = height[
+    #show parbreak: it => v(30pt)
+    A #parbreak(weak: false) B
+  ]
+  test-close(shown, plain + 30pt)
+
+  let body = [A #parbreak() B #par[C] D]
+  test(height[
+    #show parbreak: it => panic("weak paragraph break was shown")
+    #body
+  ], height(body))
+}
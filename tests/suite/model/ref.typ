@@ -9,6 +9,12 @@ See @setup.
 == Setup <setup>
 As seen in @intro, we proceed.
 
+--- ref-trailing-punctuation ---
+// Test that trailing dots and colons are left to the text.
+#let body = [See @intro. And @eq:euler:1: it works.]
+#let refs = body.children.filter(child => child.func() == ref)
+#test(refs.map(it => it.target), (<intro>, <eq:euler:1>))
+
 --- ref-label-missing ---
 // Error: 1-5 label `<foo>` does not exist in the document
 @foo
@@ -139,10 +139,19 @@
 }
 #f()
 
+--- closure-capture-is-copy ---
+// Mutating a variable after it was captured doesn't affect the closure.
+#let x = (1, 2)
+#let f() = x
+#x.push(3)
+#test(f(), (1, 2))
+#test(x, (1, 2, 3))
+
 --- closure-capture-mutate ---
 #let x = ()
 #let f() = {
   // Error: 3-4 variables from outside the function are read-only and cannot be modified
+  // Hint: 3-4 shadow it with `let` to create a mutable copy
   x.at(1) = 2
 }
 #f()
@@ -199,6 +208,15 @@
 // Error: 13-14 duplicate parameter: a
 #let f(a, ..a) = none
 
+--- closure-param-duplicate-in-pattern ---
+// Error: 16-17 duplicate parameter: a
+#let f((a, b), a) = none
+
+--- closure-param-shadows-name-and-capture ---
+#let x = 1
+#let f(f, x) = (f, x)
+#test(f(2, 3), (2, 3))
+
 --- closure-pattern-bad-string ---
 // Error: 7-14 expected pattern, found string
 #((a, "named": b) => none)
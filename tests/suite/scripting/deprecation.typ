@@ -0,0 +1,23 @@
+// Test deprecated bindings. The test library defines `pine` as a deprecated
+// alias of `forest`.
+
+--- deprecated-warns-once ---
+// Warning: 7-11 `pine` is deprecated, use `forest` instead
+// Hint: 7-11 it was deprecated in version 0.11
+#test(pine, forest)
+#test(pine, forest)
+
+--- deprecated-let-alias ---
+// Reading the deprecated name warns, but the alias doesn't.
+// Warning: 12-16 `pine` is deprecated, use `forest` instead
+// Hint: 12-16 it was deprecated in version 0.11
+#let old = pine
+#test(old, forest)
+#test(old, pine)
+
+--- deprecated-in-closure ---
+// Warning: 12-16 `pine` is deprecated, use `forest` instead
+// Hint: 12-16 it was deprecated in version 0.11
+#let f() = pine
+#test(f(), forest)
+#test(f(), forest)
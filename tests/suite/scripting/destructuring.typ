@@ -113,6 +113,9 @@
 // Error: 13-14 duplicate binding: a
 #let (a: a, a) = (a: 1, b: 2)
 
+// Error: 16-17 duplicate binding: x
+#let (a: x, b: x) = (a: 1, b: 2)
+
 // Error: 13-20 expected pattern, found function call
 #let (a, b: b.at(0)) = (a: 1, b: 2)
 
@@ -133,3 +133,20 @@ A#for "v" thing
 
 // Error: 7 expected keyword `in`
 #for a + b in iter {}
+
+--- for-loop-shadow-pattern ---
+// The body can shadow the pattern's bindings without affecting other
+// iterations or the surrounding scope.
+#let x = 0
+#let seen = ()
+#for x in (1, 2, 3) {
+  let x = x * 10
+  seen.push(x)
+}
+#test(seen, (10, 20, 30))
+#test(x, 0)
+
+--- for-loop-pattern-scoped ---
+#for i in range(3) {}
+// Error: 2-3 unknown variable: i
+#i
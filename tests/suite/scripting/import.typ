@@ -34,6 +34,38 @@
 // It exists now!
 #test(d, 3)
 
+--- import-wildcard-shadows-local ---
+// A wildcard import shadows earlier local definitions.
+#let b = "local"
+#import "module.typ": *
+#test(b, 1)
+
+--- import-wildcard-same-value ---
+// Two wildcard imports may define a name with the same value.
+#import "modules/chap1.typ": *
+#import "modules/chap2.typ": *
+#test(name, "Klaus")
+
+--- import-wildcard-twice ---
+#import "module.typ": *
+#import "module.typ": *
+#test(b, 1)
+
+--- import-wildcard-conflict ---
+// Two wildcard imports with different values for the same name.
+#import "module.typ": *
+// Error: 9-28 `b` is already defined by an earlier wildcard import
+// Hint: 9-28 the earlier definition is at tests/suite/scripting/module.typ:5
+// Hint: 9-28 this import defines it at tests/suite/scripting/modules/other.typ:3
+// Hint: 9-28 import `b` explicitly to choose a definition
+#import "modules/other.typ": *
+
+--- import-wildcard-conflict-explicit ---
+// An explicit import after the wildcards resolves the ambiguity.
+#import "module.typ": *
+#import "modules/other.typ": b
+#test(b, "other")
+
 --- import-item-renamed ---
 // A renamed item import.
 #import "module.typ": item as something
@@ -225,6 +257,19 @@ This is never reached.
 // Error: 7-12 unknown variable: chap1
 #test(chap1.b, "Klaus")
 
+--- import-module-name-invalid ---
+// The bare name of the module can't be referenced.
+// Error: 9-26 cannot import module "1st" under its own name
+// Hint: 9-26 "1st" is not a valid identifier
+// Hint: 9-26 rename the import, e.g. with `as st`
+#import "modules/1st.typ"
+
+--- import-module-name-invalid-renamed ---
+#import "modules/1st.typ" as first
+#test(first.value, 1)
+#import "modules/1st.typ": value
+#test(value, 1)
+
 --- import-incomplete ---
 // Error: 8 expected expression
 #import
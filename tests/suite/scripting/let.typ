@@ -158,3 +158,38 @@ Three
 // Error: 21-24 expected pattern, found keyword `let`
 // Hint: 21-24 keyword `let` is not allowed as an identifier; try `let_` instead
 #let (context, foo, let) = (5, 6, 7)
+
+--- let-unknown-variable-suggest-user ---
+#let counts = (1, 2)
+// Error: 2-8 unknown variable: cuonts
+// Hint: 2-8 did you mean `counts`?
+#cuonts
+
+--- let-unknown-variable-suggest-std ---
+// Error: 3-9 unknown variable: lenght
+// Hint: 3-9 did you mean `length`?
+#(lenght)
+
+--- let-unknown-variable-suggest-case ---
+// Error: 2-7 unknown variable: Lorem
+// Hint: 2-7 did you mean `lorem`?
+#Lorem(5)
+
+--- let-unknown-variable-suggest-math ---
+// Error: 2-7 unknown variable: alpah
+// Hint: 2-7 did you mean `alpha`?
+$alpah$
+
+--- let-unknown-variable-suggest-nearest ---
+// The nearest scope wins among equally similar names.
+#let bar1 = 1
+#{
+  let bar2 = 2
+  // Error: 3-7 unknown variable: bar3
+  // Hint: 3-7 did you mean `bar2`?
+  bar3
+}
+
+--- let-unknown-variable-no-suggestion ---
+// Error: 2-9 unknown variable: qwfpgjl
+#qwfpgjl
@@ -48,4 +48,5 @@
 
 --- method-mutate-on-std-constant ---
 // Error: 2-5 cannot mutate a constant: box
+// Hint: 2-5 shadow it with `let` to define a mutable variable of the same name
 #box.push(1)
@@ -0,0 +1,3 @@
+// SKIP
+// A module whose file name is not a valid identifier.
+#let value = 1
@@ -0,0 +1,3 @@
+// SKIP
+// A module that shares names with `module.typ`.
+#let b = "other"
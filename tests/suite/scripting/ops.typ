@@ -456,8 +456,19 @@
 
 --- ops-assign-to-std-constant ---
 // Error: 3-7 cannot mutate a constant: rect
+// Hint: 3-7 shadow it with `let` to define a mutable variable of the same name
 #(rect = "hi")
 
+--- ops-add-assign-to-std-constant ---
+// Error: 3-7 cannot mutate a constant: calc
+// Hint: 3-7 shadow it with `let` to define a mutable variable of the same name
+#(calc += 1)
+
+--- ops-add-assign-to-shadowed-std-constant ---
+#let calc = 1
+#(calc += 1)
+#test(calc, 2)
+
 --- ops-assign-to-shadowed-std-constant ---
 // Works if we define rect beforehand
 // (since then it doesn't resolve to the standard library version anymore).
@@ -12,6 +12,15 @@
 
 #test(fib(10), 55)
 
+--- recursion-memoized ---
+// Closure calls are memoized, so a closure is only evaluated once for the
+// same arguments at the same call site. The `tally` calls in the body count
+// the evaluations.
+#let f(x) = tally("recursion-memoized")
+#for _ in range(3) { let _ = f(1) }
+#let _ = f(2)
+#test(tally("recursion-memoized"), 3)
+
 --- recursion-unnamed-invalid ---
 // Test with unnamed function.
 // Error: 17-18 unknown variable: f
@@ -41,6 +50,17 @@
 #let rec(n) = rec(n) + 1
 #rec(1)
 
+--- recursion-maximum-depth-through-native ---
+// Error: 13-24 maximum function call depth exceeded
+#let f(x) = (x,).map(f)
+#f(1)
+
+--- recursion-maximum-depth-with-base-case ---
+// The default limit is lower than the depth of this recursion.
+// Error: 40-52 maximum function call depth exceeded
+#let count(n) = if n == 0 { 0 } else { count(n - 1) + 1 }
+#test(count(90), 90)
+
 --- recursion-via-include-in-layout ---
 // Test cyclic imports during layout.
 // Error: 2-38 maximum show rule depth exceeded
@@ -13,6 +13,16 @@
 
 #sym.arrow.r;this and this#sym.arrow.l;
 
+--- symbol-modules-lazy ---
+// The symbol modules are built on first use, but behave like any other module.
+#test(type(sym), module)
+#test(repr(emoji), "<module emoji>")
+#test(str(sym.arrow.r), "→")
+#test(sym, std.sym)
+
+// Error: 10-17 unknown symbol modifier
+#sym.dot.garbage
+
 --- symbol-constructor ---
 #let envelope = symbol(
   "🖂",
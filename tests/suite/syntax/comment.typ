@@ -34,6 +34,15 @@ Second part
 First part          //
 Second part
 
+--- comment-doc ---
+// Doc comments are trivia in all modes.
+//! A doc comment.
+#let x = {
+  //! Inside of code.
+  1 //! After code.
+}
+#test(x, 1)
+
 --- comment-block-unclosed ---
 // End should not appear without start.
 // Error: 7-9 unexpected end of block comment
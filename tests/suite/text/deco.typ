@@ -52,6 +52,51 @@ This is the built-in #highlight[highlight with default color].
 We can also specify a customized value
 #highlight(fill: green.lighten(80%))[to highlight].
 
+--- highlight-syntax ---
+#test([==Hi==], highlight[Hi])
+#test([a ==b== c], [a #highlight[b] c])
+#test([a==b==c].text, "a==b==c")
+
+--- highlight-syntax-heading ---
+#test([== Heading].func(), heading)
+#test([==Heading==].func(), highlight)
+
+--- highlight-syntax-nested ---
+#test([*==Hi==*], strong(highlight[Hi]))
+#test([==_Hi_==], highlight(emph[Hi]))
+#test([_a ==b==_], emph[a #highlight[b]])
+
+--- highlight-syntax-unclosed ---
+// Without a closing marker, the opening one is just text.
+#let highlighted(body) = body.children.any(child => child.func() == highlight)
+#test(highlighted[==Hello world], false)
+#test(highlighted[==Hello
+
+World==], false)
+
+--- highlight-syntax-unclosed-nested ---
+// Error: 3-4 unclosed delimiter
+#[*Cannot ==be interleaved]
+
+--- highlight-syntax-prose ---
+// Equals signs in prose don't start a highlight.
+#let highlighted(body) = body.children.any(child => child.func() == highlight)
+#test(highlighted[f(x)==y], false)
+#test(highlighted[a ==> b], false)
+#test(highlighted[a==>b], false)
+#test(highlighted[x=="a"], false)
+#test(highlighted[$a$==b and c], false)
+
+--- highlight-syntax-flanking ---
+// Markers must directly touch the highlighted content.
+#let count(body) = body.children.filter(child => child.func() == highlight).len()
+#test(count[a == b and c == d], 0)
+#test(count[a ==b and c ==d], 0)
+#test(count[a== b and c== d], 0)
+#test(count[a ==b and c== d], 1)
+#test(count[==a== and ==b==], 2)
+#test(count[==a ==b==], 1)
+
 --- highlight-bounds ---
 // Test default highlight bounds.
 #highlight[ace],
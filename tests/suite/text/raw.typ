@@ -626,5 +626,11 @@ fn main() {
 
 --- raw-unclosed ---
 // Unterminated.
-// Error: 1-2:1 unclosed raw text
+// Error: 1-2 unclosed raw text
 `endless
+
+--- raw-unclosed-block ---
+// Error: 1-4 unclosed raw text
+```rust
+fn main() {}
+``